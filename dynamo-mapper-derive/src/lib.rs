@@ -0,0 +1,101 @@
+//! Derive macros of `dynamo-mapper`, which are re-exported with the `derive` feature.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr, Result};
+
+/// Derive `PatchItem` for a struct whose fields are all `Option<T>` or `Patch<T>`.
+///
+/// `None` and `Patch::Keep` leave the attribute as it is, `Some` and `Patch::Set` set the
+/// value, and `Patch::Remove` removes the attribute. The attribute names are the field names,
+/// or the ones given by `#[patch(rename = "...")]`.
+///
+/// ```
+/// use dynamo_mapper::helpers::expression::patch::{Patch, PatchItem};
+///
+/// #[derive(PatchItem)]
+/// struct PersonPatch {
+///     name: Option<String>,
+///     #[patch(rename = "nick_name")]
+///     nickname: Patch<String>,
+///     age: Option<u32>,
+/// }
+///
+/// let patch = PersonPatch {
+///     name: Some("Tanaka".into()),
+///     nickname: Patch::Remove,
+///     age: None,
+/// };
+/// let diff = patch.into_diff();
+/// assert_eq!(diff.expression.to_string(), "SET #patch0 = :patch0 REMOVE #patch1");
+/// assert_eq!(diff.names["#patch1"], "nick_name");
+/// ```
+#[proc_macro_derive(PatchItem, attributes(patch))]
+pub fn derive_patch_item(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_patch_item(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand_patch_item(input: DeriveInput) -> Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input,
+                    "PatchItem can be derived only for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input,
+                "PatchItem can be derived only for structs",
+            ))
+        }
+    };
+
+    let mut calls = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let name = attribute_name(field)?.unwrap_or_else(|| ident.to_string());
+        calls.push(quote! { .field(#name, self.#ident) });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::dynamo_mapper::helpers::expression::patch::PatchItem
+            for #ident #ty_generics #where_clause
+        {
+            fn into_diff(self) -> ::dynamo_mapper::helpers::expression::diff::ItemDiff {
+                ::dynamo_mapper::helpers::expression::patch::PatchBuilder::new()
+                    #(#calls)*
+                    .build()
+            }
+        }
+    })
+}
+
+/// Return the name given by `#[patch(rename = "...")]`.
+fn attribute_name(field: &syn::Field) -> Result<Option<String>> {
+    let mut name = None;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("patch"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let value: LitStr = meta.value()?.parse()?;
+                name = Some(value.value());
+                Ok(())
+            } else {
+                Err(meta.error("unsupported patch attribute"))
+            }
+        })?;
+    }
+    Ok(name)
+}
@@ -0,0 +1,311 @@
+use super::{interceptor::Decoded, retry::RetryPolicy, BoxError, TableNameResolver};
+
+use aws_sdk_dynamodb::{
+    operation::{
+        batch_get_item::{builders::BatchGetItemInputBuilder, BatchGetItemOutput},
+        batch_write_item::{builders::BatchWriteItemInputBuilder, BatchWriteItemOutput},
+        delete_item::{builders::DeleteItemInputBuilder, DeleteItemOutput},
+        get_item::{builders::GetItemInputBuilder, GetItemOutput},
+        put_item::{builders::PutItemInputBuilder, PutItemOutput},
+        query::{builders::QueryInputBuilder, QueryOutput},
+        scan::{builders::ScanInputBuilder, ScanOutput},
+        transact_get_items::{builders::TransactGetItemsInputBuilder, TransactGetItemsOutput},
+        transact_write_items::{
+            builders::TransactWriteItemsInputBuilder, TransactWriteItemsOutput,
+        },
+        update_item::{builders::UpdateItemInputBuilder, UpdateItemOutput},
+        update_time_to_live::{builders::UpdateTimeToLiveInputBuilder, UpdateTimeToLiveOutput},
+    },
+    Client,
+};
+use std::future::Future;
+
+/// The backend which the operations send DynamoDB requests to.
+///
+/// This is implemented to [`Client`], and you can implement this to fakes, wrappers with
+/// logging or an in-memory implementation for tests. Each method receives the input builder
+/// the operation has built.
+///
+/// The error should be [`aws_sdk_dynamodb::Error`] if the backend emulates a DynamoDB error
+/// like `ConditionalCheckFailedException`, so that callers can handle it regardless of the
+/// backend. [`Client`] converts the SDK errors into it.
+///
+/// All the request methods fail with an "unsupported operation" error by default, so you
+/// need to implement only the ones your backend supports.
+pub trait DynamoBackend: Send + Sync {
+    fn get_item(
+        &self,
+        input: GetItemInputBuilder,
+    ) -> impl Future<Output = Result<GetItemOutput, BoxError>> + Send {
+        let _ = input;
+        async { Err(unsupported("GetItem")) }
+    }
+
+    fn put_item(
+        &self,
+        input: PutItemInputBuilder,
+    ) -> impl Future<Output = Result<PutItemOutput, BoxError>> + Send {
+        let _ = input;
+        async { Err(unsupported("PutItem")) }
+    }
+
+    fn update_item(
+        &self,
+        input: UpdateItemInputBuilder,
+    ) -> impl Future<Output = Result<UpdateItemOutput, BoxError>> + Send {
+        let _ = input;
+        async { Err(unsupported("UpdateItem")) }
+    }
+
+    fn delete_item(
+        &self,
+        input: DeleteItemInputBuilder,
+    ) -> impl Future<Output = Result<DeleteItemOutput, BoxError>> + Send {
+        let _ = input;
+        async { Err(unsupported("DeleteItem")) }
+    }
+
+    fn query(
+        &self,
+        input: QueryInputBuilder,
+    ) -> impl Future<Output = Result<QueryOutput, BoxError>> + Send {
+        let _ = input;
+        async { Err(unsupported("Query")) }
+    }
+
+    fn scan(
+        &self,
+        input: ScanInputBuilder,
+    ) -> impl Future<Output = Result<ScanOutput, BoxError>> + Send {
+        let _ = input;
+        async { Err(unsupported("Scan")) }
+    }
+
+    fn batch_get_item(
+        &self,
+        input: BatchGetItemInputBuilder,
+    ) -> impl Future<Output = Result<BatchGetItemOutput, BoxError>> + Send {
+        let _ = input;
+        async { Err(unsupported("BatchGetItem")) }
+    }
+
+    fn batch_write_item(
+        &self,
+        input: BatchWriteItemInputBuilder,
+    ) -> impl Future<Output = Result<BatchWriteItemOutput, BoxError>> + Send {
+        let _ = input;
+        async { Err(unsupported("BatchWriteItem")) }
+    }
+
+    fn transact_get_items(
+        &self,
+        input: TransactGetItemsInputBuilder,
+    ) -> impl Future<Output = Result<TransactGetItemsOutput, BoxError>> + Send {
+        let _ = input;
+        async { Err(unsupported("TransactGetItems")) }
+    }
+
+    fn transact_write_items(
+        &self,
+        input: TransactWriteItemsInputBuilder,
+    ) -> impl Future<Output = Result<TransactWriteItemsOutput, BoxError>> + Send {
+        let _ = input;
+        async { Err(unsupported("TransactWriteItems")) }
+    }
+
+    fn update_time_to_live(
+        &self,
+        input: UpdateTimeToLiveInputBuilder,
+    ) -> impl Future<Output = Result<UpdateTimeToLiveOutput, BoxError>> + Send {
+        let _ = input;
+        async { Err(unsupported("UpdateTimeToLive")) }
+    }
+
+    /// Return the retry policy of the operations sent to this backend unless they set their
+    /// own. Default is None, and the operations are not retried.
+    ///
+    /// Wrappers of another backend should return the policy of the inner one.
+    fn retry_policy(&self) -> Option<&RetryPolicy> {
+        None
+    }
+
+    /// Return the resolver of the table names of the operations sent to this backend.
+    /// Default is None, and [`DynamodbTable::table_name`](crate::DynamodbTable::table_name)
+    /// is used as it is.
+    ///
+    /// Wrappers of another backend should return the resolver of the inner one.
+    fn table_name_resolver(&self) -> Option<&dyn TableNameResolver> {
+        None
+    }
+
+    /// Called by the operations with the objects they decoded from the response. Default does
+    /// nothing.
+    ///
+    /// Wrappers of another backend should pass the objects to the inner one.
+    fn after_decode(&self, _decoded: &Decoded<'_>) {}
+}
+
+/// Return the error of the request methods the backend doesn't implement.
+fn unsupported(operation: &str) -> BoxError {
+    format!("unsupported operation: {operation}").into()
+}
+
+macro_rules! impl_client_backend {
+    ($($method:ident: $input:ty => $output:ty,)*) => {
+        impl DynamoBackend for Client {
+            $(
+                fn $method(
+                    &self,
+                    input: $input,
+                ) -> impl Future<Output = Result<$output, BoxError>> + Send {
+                    async move {
+                        input
+                            .send_with(self)
+                            .await
+                            .map_err(|err| Box::new(aws_sdk_dynamodb::Error::from(err)) as BoxError)
+                    }
+                }
+            )*
+        }
+    };
+}
+
+impl_client_backend! {
+    get_item: GetItemInputBuilder => GetItemOutput,
+    put_item: PutItemInputBuilder => PutItemOutput,
+    update_item: UpdateItemInputBuilder => UpdateItemOutput,
+    delete_item: DeleteItemInputBuilder => DeleteItemOutput,
+    query: QueryInputBuilder => QueryOutput,
+    scan: ScanInputBuilder => ScanOutput,
+    batch_get_item: BatchGetItemInputBuilder => BatchGetItemOutput,
+    batch_write_item: BatchWriteItemInputBuilder => BatchWriteItemOutput,
+    transact_get_items: TransactGetItemsInputBuilder => TransactGetItemsOutput,
+    transact_write_items: TransactWriteItemsInputBuilder => TransactWriteItemsOutput,
+    update_time_to_live: UpdateTimeToLiveInputBuilder => UpdateTimeToLiveOutput,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        helpers::attribute_value::AttributeMap,
+        operations::{get_item::GetItem, put_item::PutItem},
+        DynamodbTable, Item, Key,
+    };
+    use aws_sdk_dynamodb::types::AttributeValue;
+    use std::sync::Mutex;
+
+    #[derive(Debug, PartialEq)]
+    struct Person {
+        id: String,
+        name: String,
+    }
+
+    struct PersonKey;
+
+    impl<'a> Key<'a> for PersonKey {
+        const PARTITION_KEY: &'a str = "pk";
+        const SORT_KEY: Option<&'a str> = None;
+
+        type PartitionInput = String;
+        type SortInput = ();
+
+        fn partition_key(input: Self::PartitionInput) -> AttributeValue {
+            AttributeValue::S(input)
+        }
+
+        fn sort_key(_: Self::SortInput) -> Option<AttributeValue> {
+            None
+        }
+    }
+
+    impl<'a> DynamodbTable<'a> for Person {
+        const TABLE_NAME: &'a str = "People";
+
+        type Key = PersonKey;
+
+        fn key_inputs(&self) -> (String, ()) {
+            (self.id.clone(), ())
+        }
+    }
+
+    impl<'a> GetItem<'a> for Person {}
+    impl<'a> PutItem<'a> for Person {}
+
+    impl TryFrom<Item> for Person {
+        type Error = BoxError;
+
+        fn try_from(item: Item) -> Result<Self, Self::Error> {
+            let map = AttributeMap::from(item);
+            Ok(Person {
+                id: map.s("id").ok_or("no id")?.into(),
+                name: map.s("name").ok_or("no name")?.into(),
+            })
+        }
+    }
+
+    impl From<Person> for Item {
+        fn from(person: Person) -> Item {
+            AttributeMap::new()
+                .set_s("id", person.id)
+                .set_s("name", person.name)
+                .into_item()
+        }
+    }
+
+    /// A fake backend which stores the put item and returns it on get.
+    #[derive(Default)]
+    struct FakeBackend {
+        item: Mutex<Option<Item>>,
+    }
+
+    impl DynamoBackend for FakeBackend {
+        async fn get_item(&self, input: GetItemInputBuilder) -> Result<GetItemOutput, BoxError> {
+            assert_eq!(input.get_table_name().as_deref(), Some("People"));
+            let item = self.item.lock().unwrap().clone();
+            Ok(GetItemOutput::builder().set_item(item).build())
+        }
+
+        async fn put_item(&self, input: PutItemInputBuilder) -> Result<PutItemOutput, BoxError> {
+            *self.item.lock().unwrap() = input.get_item().clone();
+            Ok(PutItemOutput::builder().build())
+        }
+    }
+
+    #[tokio::test]
+    async fn operations_send_requests_to_backend() {
+        let backend = FakeBackend::default();
+        let person = Person {
+            id: "1".into(),
+            name: "Tanaka".into(),
+        };
+
+        let result = Person {
+            id: "1".into(),
+            name: "Tanaka".into(),
+        }
+        .put()
+        .send(&backend)
+        .await;
+        assert!(result.is_ok());
+
+        let stored = backend.item.lock().unwrap().clone().unwrap();
+        assert_eq!(stored.get("pk"), Some(&AttributeValue::S("1".into())));
+
+        let result = Person::get_item()
+            .set_key("1".into(), ())
+            .send(&backend)
+            .await;
+        assert_eq!(result.unwrap(), Some(person));
+    }
+
+    #[tokio::test]
+    async fn unimplemented_methods_fail() {
+        let backend = FakeBackend::default();
+        let err = backend
+            .scan(ScanInputBuilder::default().table_name("People"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "unsupported operation: Scan");
+    }
+}
@@ -0,0 +1,83 @@
+//! Synchronous API with the `blocking` feature.
+//!
+//! Each operation has the `_blocking` counterparts of its `send` methods like
+//! [`GetItemOperation::send_blocking`](crate::operations::get_item::GetItemOperation::send_blocking),
+//! which run the async ones to completion on a runtime shared by the whole process. The same
+//! objects can be used by CLI tools and build scripts without async code.
+//! [`TableSchema`](crate::schema::TableSchema) has
+//! [`create_table_blocking`](crate::schema::TableSchema::create_table_blocking),
+//! [`delete_table_blocking`](crate::schema::TableSchema::delete_table_blocking) and
+//! [`verify_blocking`](crate::schema::TableSchema::verify_blocking) as well.
+//!
+//! Use [`block_on`] for the other async APIs, like loading the AWS config to make the client.
+//!
+//! ```no_run
+//! use dynamo_mapper::blocking::block_on;
+//!
+//! # fn main() -> Result<(), dynamo_mapper::Error> {
+//! let config = block_on(aws_config::load_from_env())?;
+//! let client = aws_sdk_dynamodb::Client::new(&config);
+//! // let person = Person::get_item().set_key(id, ()).send_blocking(&client)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The blocking API fails with [`Error::Blocking`] within an async runtime, where blocking the
+//! thread would stall the other tasks. Use the async API there instead.
+use crate::Error;
+
+use std::future::Future;
+use std::sync::OnceLock;
+use tokio::runtime::{Builder, Handle, Runtime};
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+/// Run the future to completion on the runtime of the blocking API.
+///
+/// The runtime is built on the first call and shared by all threads, so the connections of
+/// the client are pooled across the calls.
+///
+/// Fail with [`Error::Blocking`] if called within an async runtime, or if the runtime couldn't
+/// be built.
+pub fn block_on<F: Future>(future: F) -> Result<F::Output, Error> {
+    if Handle::try_current().is_ok() {
+        return Err(Error::Blocking(
+            "called within an async runtime".to_string(),
+        ));
+    }
+
+    let runtime = match RUNTIME.get() {
+        Some(runtime) => runtime,
+        None => {
+            let runtime = Builder::new_multi_thread()
+                .thread_name("dynamo-mapper-blocking")
+                .enable_all()
+                .build()
+                .map_err(|err| Error::Blocking(format!("failed to build the runtime: {err}")))?;
+            // Another thread may have set the runtime meanwhile, then this one is dropped.
+            RUNTIME.get_or_init(|| runtime)
+        }
+    };
+    Ok(runtime.block_on(future))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorKind;
+
+    #[test]
+    fn block_on_from_threads() {
+        let handles: Vec<_> = (0..4)
+            .map(|n| std::thread::spawn(move || block_on(async move { n * 2 }).unwrap()))
+            .collect();
+        let results: Vec<i32> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(results, vec![0, 2, 4, 6]);
+    }
+
+    #[tokio::test]
+    async fn block_on_within_runtime_fails() {
+        let err = block_on(async {}).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Blocking);
+    }
+}
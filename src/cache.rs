@@ -0,0 +1,642 @@
+//! A read-through cache of the items in the process.
+//!
+//! [`Cached`] wraps a backend and keeps the items returned by GetItem and BatchGetItem for
+//! a while, so the objects read over and over like configurations don't hit DynamoDB each
+//! time. The items are keyed by the table name and the primary key, and the missing items are
+//! cached as well.
+//!
+//! - An entry expires after the TTL, and the least recently used entry is evicted when the
+//!   cache is full.
+//! - PutItem, UpdateItem, DeleteItem, BatchWriteItem and TransactWriteItems sent through the
+//!   same backend invalidate the written keys.
+//! - Strongly consistent reads bypass the cache and refresh it with the result.
+//! - Reads with `ProjectionExpression` or `AttributesToGet` bypass the cache.
+//!
+//! Writes by other processes are not seen until the entries expire, so choose the TTL by how
+//! stale the items can be. A read running concurrently with a write may also cache the old
+//! item until the TTL.
+//!
+//! ```
+//! use dynamo_mapper::{cache::Cached, memory::MemoryBackend};
+//! use std::time::Duration;
+//!
+//! let backend = Cached::new(MemoryBackend::new())
+//!     .with_capacity(10_000)
+//!     .with_ttl(Duration::from_secs(5));
+//! ```
+use crate::{
+    helpers::attribute_value::normalize_number, interceptor::Decoded, retry::RetryPolicy, BoxError,
+    DynamoBackend, Item, TableNameResolver,
+};
+
+use aws_sdk_dynamodb::{
+    operation::{
+        batch_get_item::{builders::BatchGetItemInputBuilder, BatchGetItemOutput},
+        batch_write_item::{builders::BatchWriteItemInputBuilder, BatchWriteItemOutput},
+        delete_item::{builders::DeleteItemInputBuilder, DeleteItemOutput},
+        get_item::{builders::GetItemInputBuilder, GetItemOutput},
+        put_item::{builders::PutItemInputBuilder, PutItemOutput},
+        query::{builders::QueryInputBuilder, QueryOutput},
+        scan::{builders::ScanInputBuilder, ScanOutput},
+        transact_get_items::{builders::TransactGetItemsInputBuilder, TransactGetItemsOutput},
+        transact_write_items::{
+            builders::TransactWriteItemsInputBuilder, TransactWriteItemsOutput,
+        },
+        update_item::{builders::UpdateItemInputBuilder, UpdateItemOutput},
+        update_time_to_live::{builders::UpdateTimeToLiveInputBuilder, UpdateTimeToLiveOutput},
+    },
+    types::{AttributeValue, KeysAndAttributes},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// The table name and the primary key of a cached item.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    table_name: String,
+    /// The pairs of the attribute name and the formatted value in the order of the names.
+    key: Vec<(String, String)>,
+}
+
+impl CacheKey {
+    fn new(table_name: &str, key: &Item) -> Self {
+        let mut key: Vec<(String, String)> = key
+            .iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    AttributeValue::N(number) => format!("N({})", normalize_number(number)),
+                    value => format!("{value:?}"),
+                };
+                (name.clone(), value)
+            })
+            .collect();
+        key.sort();
+        Self {
+            table_name: table_name.into(),
+            key,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    item: Option<Item>,
+    expires_at: Instant,
+    used: u64,
+}
+
+/// The entries in the order of the last use.
+#[derive(Debug, Default)]
+struct Lru {
+    entries: HashMap<CacheKey, Entry>,
+    order: BTreeMap<u64, CacheKey>,
+    /// The key attribute names of each table, to find the key of a written item.
+    key_names: HashMap<String, Vec<String>>,
+    tick: u64,
+}
+
+impl Lru {
+    /// Return the cached item, which is None if the item doesn't exist.
+    fn get(&mut self, key: &CacheKey, now: Instant) -> Option<Option<Item>> {
+        let entry = self.entries.get_mut(key)?;
+        if entry.expires_at <= now {
+            self.remove(key);
+            return None;
+        }
+
+        self.tick += 1;
+        self.order.remove(&entry.used);
+        self.order.insert(self.tick, key.clone());
+        entry.used = self.tick;
+        Some(entry.item.clone())
+    }
+
+    fn insert(&mut self, key: CacheKey, item: Option<Item>, expires_at: Instant, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+
+        self.remove(&key);
+        while self.entries.len() >= capacity {
+            match self.order.pop_first() {
+                Some((_, oldest)) => self.entries.remove(&oldest),
+                None => break,
+            };
+        }
+
+        self.key_names
+            .entry(key.table_name.clone())
+            .or_insert_with(|| key.key.iter().map(|(name, _)| name.clone()).collect());
+        self.tick += 1;
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(
+            key,
+            Entry {
+                item,
+                expires_at,
+                used: self.tick,
+            },
+        );
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.used);
+        }
+    }
+
+    /// Remove the entry of the whole item written by PutItem.
+    fn remove_item(&mut self, table_name: &str, item: &Item) {
+        let Some(names) = self.key_names.get(table_name) else {
+            return;
+        };
+        let key: Option<Item> = names
+            .iter()
+            .map(|name| Some((name.clone(), item.get(name)?.clone())))
+            .collect();
+        if let Some(key) = key {
+            self.remove(&CacheKey::new(table_name, &key));
+        }
+    }
+}
+
+/// A [`DynamoBackend`] which caches the items read by GetItem and BatchGetItem.
+///
+/// The clones share the cache. The default capacity is 1,000 items and the default TTL is
+/// 60 seconds.
+#[derive(Debug, Clone)]
+pub struct Cached<B> {
+    inner: B,
+    cache: Arc<Mutex<Lru>>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+fn cacheable(projection_expression: Option<&str>, attributes_to_get: Option<&[String]>) -> bool {
+    projection_expression.is_none() && attributes_to_get.is_none()
+}
+
+impl<B: DynamoBackend> Cached<B> {
+    /// Wrap the backend with an empty cache.
+    pub fn new(inner: B) -> Self {
+        Self {
+            inner,
+            cache: Arc::new(Mutex::new(Lru::default())),
+            capacity: 1_000,
+            ttl: Duration::from_secs(60),
+        }
+    }
+
+    /// Set the maximum number of the cached items.
+    pub fn with_capacity(self, capacity: usize) -> Self {
+        Self { capacity, ..self }
+    }
+
+    /// Set how long the items are cached.
+    pub fn with_ttl(self, ttl: Duration) -> Self {
+        Self { ttl, ..self }
+    }
+
+    /// Return the number of the cached items including the expired ones.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Return true if no item is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all the cached items.
+    pub fn clear(&self) {
+        let mut cache = self.lock();
+        cache.entries.clear();
+        cache.order.clear();
+    }
+
+    /// Return the inner backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Lru> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn insert(&self, key: CacheKey, item: Option<Item>) {
+        let expires_at = Instant::now() + self.ttl;
+        self.lock().insert(key, item, expires_at, self.capacity);
+    }
+
+    fn invalidate_key(&self, table_name: Option<&str>, key: Option<&Item>) {
+        if let (Some(table_name), Some(key)) = (table_name, key) {
+            self.lock().remove(&CacheKey::new(table_name, key));
+        }
+    }
+
+    fn invalidate_item(&self, table_name: Option<&str>, item: Option<&Item>) {
+        if let (Some(table_name), Some(item)) = (table_name, item) {
+            self.lock().remove_item(table_name, item);
+        }
+    }
+
+    /// Cache the items of the response by the requested keys. The keys not in the response
+    /// nor in the unprocessed keys are cached as missing.
+    fn cache_batch_response(
+        &self,
+        table_name: &str,
+        request: &KeysAndAttributes,
+        output: &BatchGetItemOutput,
+    ) {
+        if !cacheable(
+            request.projection_expression.as_deref(),
+            request.attributes_to_get.as_deref(),
+        ) {
+            return;
+        }
+
+        let items = output
+            .responses
+            .as_ref()
+            .and_then(|responses| responses.get(table_name));
+        let unprocessed = output
+            .unprocessed_keys
+            .as_ref()
+            .and_then(|keys| keys.get(table_name));
+
+        for key in &request.keys {
+            if unprocessed.is_some_and(|unprocessed| unprocessed.keys.contains(key)) {
+                continue;
+            }
+            let item = items.into_iter().flatten().find(|item| {
+                key.iter()
+                    .all(|(name, value)| item.get(name) == Some(value))
+            });
+            self.insert(CacheKey::new(table_name, key), item.cloned());
+        }
+    }
+}
+
+impl<B: DynamoBackend> DynamoBackend for Cached<B> {
+    async fn get_item(&self, input: GetItemInputBuilder) -> Result<GetItemOutput, BoxError> {
+        let cache_key = match (input.get_table_name(), input.get_key()) {
+            (Some(table_name), Some(key))
+                if cacheable(
+                    input.get_projection_expression().as_deref(),
+                    input.get_attributes_to_get().as_deref(),
+                ) =>
+            {
+                Some(CacheKey::new(table_name, key))
+            }
+            _ => None,
+        };
+
+        if let Some(key) = cache_key.as_ref() {
+            if *input.get_consistent_read() != Some(true) {
+                if let Some(item) = self.lock().get(key, Instant::now()) {
+                    return Ok(GetItemOutput::builder().set_item(item).build());
+                }
+            }
+        }
+
+        let output = self.inner.get_item(input).await?;
+        if let Some(key) = cache_key {
+            self.insert(key, output.item.clone());
+        }
+        Ok(output)
+    }
+
+    async fn put_item(&self, input: PutItemInputBuilder) -> Result<PutItemOutput, BoxError> {
+        let table_name = input.get_table_name().clone();
+        let item = input.get_item().clone();
+        let result = self.inner.put_item(input).await;
+        self.invalidate_item(table_name.as_deref(), item.as_ref());
+        result
+    }
+
+    async fn update_item(
+        &self,
+        input: UpdateItemInputBuilder,
+    ) -> Result<UpdateItemOutput, BoxError> {
+        let table_name = input.get_table_name().clone();
+        let key = input.get_key().clone();
+        let result = self.inner.update_item(input).await;
+        self.invalidate_key(table_name.as_deref(), key.as_ref());
+        result
+    }
+
+    async fn delete_item(
+        &self,
+        input: DeleteItemInputBuilder,
+    ) -> Result<DeleteItemOutput, BoxError> {
+        let table_name = input.get_table_name().clone();
+        let key = input.get_key().clone();
+        let result = self.inner.delete_item(input).await;
+        self.invalidate_key(table_name.as_deref(), key.as_ref());
+        result
+    }
+
+    async fn query(&self, input: QueryInputBuilder) -> Result<QueryOutput, BoxError> {
+        self.inner.query(input).await
+    }
+
+    async fn scan(&self, input: ScanInputBuilder) -> Result<ScanOutput, BoxError> {
+        self.inner.scan(input).await
+    }
+
+    async fn batch_get_item(
+        &self,
+        input: BatchGetItemInputBuilder,
+    ) -> Result<BatchGetItemOutput, BoxError> {
+        let Some(mut request_items) = input.get_request_items().clone() else {
+            return self.inner.batch_get_item(input).await;
+        };
+
+        // Take the cached items out of the request.
+        let mut hits: HashMap<String, Vec<Item>> = HashMap::new();
+        {
+            let now = Instant::now();
+            let mut cache = self.lock();
+            for (table_name, request) in request_items.iter_mut() {
+                if request.consistent_read == Some(true)
+                    || !cacheable(
+                        request.projection_expression.as_deref(),
+                        request.attributes_to_get.as_deref(),
+                    )
+                {
+                    continue;
+                }
+                request.keys.retain(
+                    |key| match cache.get(&CacheKey::new(table_name, key), now) {
+                        Some(item) => {
+                            hits.entry(table_name.clone()).or_default().extend(item);
+                            false
+                        }
+                        None => true,
+                    },
+                );
+            }
+        }
+        request_items.retain(|_, request| !request.keys.is_empty());
+
+        let mut output = if request_items.is_empty() {
+            BatchGetItemOutput::builder().build()
+        } else {
+            self.inner
+                .batch_get_item(input.set_request_items(Some(request_items.clone())))
+                .await?
+        };
+
+        for (table_name, request) in &request_items {
+            self.cache_batch_response(table_name, request, &output);
+        }
+
+        let responses = output.responses.get_or_insert_with(HashMap::new);
+        for (table_name, items) in hits {
+            responses.entry(table_name).or_default().extend(items);
+        }
+        Ok(output)
+    }
+
+    async fn batch_write_item(
+        &self,
+        input: BatchWriteItemInputBuilder,
+    ) -> Result<BatchWriteItemOutput, BoxError> {
+        let request_items = input.get_request_items().clone().unwrap_or_default();
+        let result = self.inner.batch_write_item(input).await;
+        for (table_name, requests) in &request_items {
+            for request in requests {
+                if let Some(put) = request.put_request.as_ref() {
+                    self.invalidate_item(Some(table_name), Some(&put.item));
+                }
+                if let Some(delete) = request.delete_request.as_ref() {
+                    self.invalidate_key(Some(table_name), Some(&delete.key));
+                }
+            }
+        }
+        result
+    }
+
+    async fn transact_get_items(
+        &self,
+        input: TransactGetItemsInputBuilder,
+    ) -> Result<TransactGetItemsOutput, BoxError> {
+        self.inner.transact_get_items(input).await
+    }
+
+    async fn transact_write_items(
+        &self,
+        input: TransactWriteItemsInputBuilder,
+    ) -> Result<TransactWriteItemsOutput, BoxError> {
+        let items = input.get_transact_items().clone().unwrap_or_default();
+        let result = self.inner.transact_write_items(input).await;
+        for item in &items {
+            if let Some(put) = item.put.as_ref() {
+                self.invalidate_item(Some(&put.table_name), Some(&put.item));
+            }
+            if let Some(update) = item.update.as_ref() {
+                self.invalidate_key(Some(&update.table_name), Some(&update.key));
+            }
+            if let Some(delete) = item.delete.as_ref() {
+                self.invalidate_key(Some(&delete.table_name), Some(&delete.key));
+            }
+        }
+        result
+    }
+
+    async fn update_time_to_live(
+        &self,
+        input: UpdateTimeToLiveInputBuilder,
+    ) -> Result<UpdateTimeToLiveOutput, BoxError> {
+        self.inner.update_time_to_live(input).await
+    }
+
+    fn retry_policy(&self) -> Option<&RetryPolicy> {
+        self.inner.retry_policy()
+    }
+
+    fn table_name_resolver(&self) -> Option<&dyn TableNameResolver> {
+        self.inner.table_name_resolver()
+    }
+
+    fn after_decode(&self, decoded: &Decoded<'_>) {
+        self.inner.after_decode(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        helpers::attribute_value::AttributeMap,
+        interceptor::{InterceptedBackend, Interceptor, Request},
+        memory::MemoryBackend,
+        schema::{KeyAttribute, TableSchema},
+    };
+    use aws_sdk_dynamodb::{
+        operation::{
+            batch_get_item::BatchGetItemInput, delete_item::DeleteItemInput,
+            get_item::GetItemInput, put_item::PutItemInput,
+        },
+        types::{BillingMode, ScalarAttributeType},
+    };
+
+    fn key(id: &str) -> Item {
+        AttributeMap::new().set_s("id", id).into_item()
+    }
+
+    fn item(id: &str, value: &str) -> Item {
+        AttributeMap::new()
+            .set_s("id", id)
+            .set_s("value", value)
+            .into_item()
+    }
+
+    #[test]
+    fn evict_least_recently_used_and_expired() {
+        let now = Instant::now();
+        let later = now + Duration::from_secs(60);
+        let mut lru = Lru::default();
+        let cache_key = |id| CacheKey::new("Configs", &key(id));
+
+        lru.insert(cache_key("a"), Some(item("a", "1")), later, 2);
+        lru.insert(cache_key("b"), None, later, 2);
+        assert_eq!(lru.get(&cache_key("a"), now), Some(Some(item("a", "1"))));
+
+        // "b" is the least recently used.
+        lru.insert(cache_key("c"), Some(item("c", "3")), now, 2);
+        assert_eq!(lru.get(&cache_key("b"), now), None);
+        assert_eq!(lru.get(&cache_key("a"), now), Some(Some(item("a", "1"))));
+
+        // "c" has expired.
+        assert_eq!(lru.get(&cache_key("c"), now), None);
+        assert_eq!(lru.entries.len(), 1);
+        assert_eq!(lru.order.len(), 1);
+    }
+
+    #[test]
+    fn same_numbers_make_same_key() {
+        let cache_key = |id: &str| {
+            let key = AttributeMap::new().set_n("id", id).into_item();
+            CacheKey::new("Counters", &key)
+        };
+        assert_eq!(cache_key("1"), cache_key("1.0"));
+        assert_eq!(cache_key("150"), cache_key("1.5e2"));
+        assert_eq!(cache_key("-0.05"), cache_key("-5E-2"));
+        assert_eq!(cache_key("0"), cache_key("-0.00"));
+        assert_ne!(cache_key("1"), cache_key("10"));
+        assert_ne!(cache_key("1"), cache_key("-1"));
+    }
+
+    /// Count the reads which reach the inner backend.
+    #[derive(Default)]
+    struct Reads(Mutex<Vec<usize>>);
+
+    impl Interceptor for Reads {
+        fn before_request(&self, request: &mut Request<'_>) -> Result<(), BoxError> {
+            let keys = match request {
+                Request::GetItem(_) => 1,
+                Request::BatchGetItem(input) => input
+                    .get_request_items()
+                    .iter()
+                    .flatten()
+                    .map(|(_, request)| request.keys.len())
+                    .sum(),
+                _ => return Ok(()),
+            };
+            self.0.lock().unwrap().push(keys);
+            Ok(())
+        }
+    }
+
+    fn setup() -> (Cached<InterceptedBackend<MemoryBackend>>, Arc<Reads>) {
+        let backend = MemoryBackend::new();
+        backend
+            .create_table(TableSchema {
+                table_name: "Configs".into(),
+                partition_key: KeyAttribute::new("id", ScalarAttributeType::S),
+                sort_key: None,
+                global_secondary_indexes: vec![],
+                local_secondary_indexes: vec![],
+                billing_mode: BillingMode::PayPerRequest,
+                provisioned_throughput: None,
+                stream_specification: None,
+                ttl_attribute: None,
+            })
+            .unwrap();
+
+        let reads = Arc::new(Reads::default());
+        let backend = InterceptedBackend::new(backend).with_shared(reads.clone());
+        (Cached::new(backend), reads)
+    }
+
+    async fn get(backend: &impl DynamoBackend, id: &str, consistent: bool) -> Option<Item> {
+        let input = GetItemInput::builder()
+            .table_name("Configs")
+            .set_key(Some(key(id)))
+            .consistent_read(consistent);
+        backend.get_item(input).await.unwrap().item
+    }
+
+    async fn put(backend: &impl DynamoBackend, id: &str, value: &str) {
+        let input = PutItemInput::builder()
+            .table_name("Configs")
+            .set_item(Some(item(id, value)));
+        backend.put_item(input).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn read_through_and_invalidate_on_writes() {
+        let (backend, reads) = setup();
+        put(&backend, "a", "1").await;
+
+        assert_eq!(get(&backend, "a", false).await, Some(item("a", "1")));
+        assert_eq!(get(&backend, "a", false).await, Some(item("a", "1")));
+        assert_eq!(reads.0.lock().unwrap().len(), 1);
+
+        put(&backend, "a", "2").await;
+        assert_eq!(get(&backend, "a", false).await, Some(item("a", "2")));
+        assert_eq!(reads.0.lock().unwrap().len(), 2);
+
+        let input = DeleteItemInput::builder()
+            .table_name("Configs")
+            .set_key(Some(key("a")));
+        backend.delete_item(input).await.unwrap();
+        assert_eq!(get(&backend, "a", false).await, None);
+        assert_eq!(get(&backend, "a", false).await, None);
+        assert_eq!(reads.0.lock().unwrap().len(), 3);
+
+        // Consistent reads always reach the backend.
+        assert_eq!(get(&backend, "a", true).await, None);
+        assert_eq!(reads.0.lock().unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn batch_get_only_missed_keys() {
+        let (backend, reads) = setup();
+        put(&backend, "a", "1").await;
+        put(&backend, "b", "2").await;
+        get(&backend, "a", false).await;
+
+        let input = BatchGetItemInput::builder().request_items(
+            "Configs",
+            KeysAndAttributes::builder()
+                .keys(key("a"))
+                .keys(key("b"))
+                .keys(key("c"))
+                .build()
+                .unwrap(),
+        );
+        let output = backend.batch_get_item(input.clone()).await.unwrap();
+        let mut items = output.responses.unwrap().remove("Configs").unwrap();
+        items.sort_by_key(|item| format!("{:?}", item["id"]));
+        assert_eq!(items, vec![item("a", "1"), item("b", "2")]);
+
+        // All the keys including the missing one are cached now.
+        backend.batch_get_item(input).await.unwrap();
+        assert_eq!(*reads.0.lock().unwrap(), vec![1, 2]);
+    }
+}
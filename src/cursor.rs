@@ -0,0 +1,216 @@
+use super::{
+    helpers::json::{item_from_json, item_to_json},
+    Error, Item,
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Nonce,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+const NONCE_LEN: usize = 12;
+const SIGNATURE_LEN: usize = 32;
+
+/// Encodes the pagination key like `last_evaluated_key` into an opaque URL-safe string, which
+/// you can hand to HTTP clients, and decodes it back.
+///
+/// By default the cursor is just base64url-encoded DynamoDB JSON of the key. Set a signing key
+/// to reject tampered cursors and an encryption key to hide the key values from the clients.
+///
+/// ```
+/// # use dynamo_mapper::{cursor::CursorCodec, helpers::attribute_value::AttributeMap};
+/// let codec = CursorCodec::new()
+///     .set_signing_key("signing secret")
+///     .set_encryption_key([7; 32]);
+///
+/// let key = AttributeMap::new()
+///     .set_s("pk", "SHOP#1")
+///     .set_s("sk", "STAFF#100")
+///     .into_item();
+///
+/// let cursor = codec.encode(&key).unwrap();
+/// assert!(!cursor.contains("SHOP#1"));
+/// assert_eq!(codec.decode(&cursor).unwrap(), key);
+/// assert!(CursorCodec::new().decode(&cursor).is_err());
+/// ```
+#[derive(Clone, Default)]
+pub struct CursorCodec {
+    signing_key: Option<Vec<u8>>,
+    encryption_key: Option<[u8; 32]>,
+}
+
+impl std::fmt::Debug for CursorCodec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CursorCodec")
+            .field("signed", &self.signing_key.is_some())
+            .field("encrypted", &self.encryption_key.is_some())
+            .finish()
+    }
+}
+
+impl CursorCodec {
+    /// Create a new instance which neither signs nor encrypts cursors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sign cursors with HMAC-SHA256 using the key.
+    pub fn set_signing_key(self, key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            signing_key: Some(key.into()),
+            ..self
+        }
+    }
+
+    /// Encrypt cursors with ChaCha20-Poly1305 using the 256-bit key.
+    pub fn set_encryption_key(self, key: [u8; 32]) -> Self {
+        Self {
+            encryption_key: Some(key),
+            ..self
+        }
+    }
+
+    /// Encode the pagination key into a cursor.
+    pub fn encode(&self, key: &Item) -> Result<String, Error> {
+        let mut bytes = item_to_json(key).to_string().into_bytes();
+
+        if let Some(encryption_key) = self.encryption_key.as_ref() {
+            let cipher = ChaCha20Poly1305::new(encryption_key.into());
+            let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+            let ciphertext = cipher
+                .encrypt(&nonce, bytes.as_ref())
+                .map_err(|_| Error::Cursor("failed to encrypt".into()))?;
+            bytes = [nonce.as_slice(), &ciphertext].concat();
+        }
+
+        if let Some(signing_key) = self.signing_key.as_ref() {
+            let signature = signer(signing_key)?
+                .chain_update(&bytes)
+                .finalize()
+                .into_bytes();
+            bytes.extend_from_slice(&signature);
+        }
+
+        Ok(URL_SAFE_NO_PAD.encode(bytes))
+    }
+
+    /// Decode the cursor into the pagination key.
+    ///
+    /// Fail if the cursor is malformed, tampered with or made by the codec with other keys.
+    pub fn decode(&self, cursor: &str) -> Result<Item, Error> {
+        let mut bytes = URL_SAFE_NO_PAD
+            .decode(cursor)
+            .map_err(|err| Error::Cursor(err.to_string()))?;
+
+        if let Some(signing_key) = self.signing_key.as_ref() {
+            let split_at = bytes
+                .len()
+                .checked_sub(SIGNATURE_LEN)
+                .ok_or_else(|| Error::Cursor("signature is missing".into()))?;
+            let signature = bytes.split_off(split_at);
+            signer(signing_key)?
+                .chain_update(&bytes)
+                .verify_slice(&signature)
+                .map_err(|_| Error::Cursor("signature mismatch".into()))?;
+        }
+
+        if let Some(encryption_key) = self.encryption_key.as_ref() {
+            if bytes.len() < NONCE_LEN {
+                return Err(Error::Cursor("nonce is missing".into()));
+            }
+            let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+            let cipher = ChaCha20Poly1305::new(encryption_key.into());
+            bytes = cipher
+                .decrypt(Nonce::from_slice(nonce), ciphertext)
+                .map_err(|_| Error::Cursor("failed to decrypt".into()))?;
+        }
+
+        let json: serde_json::Value =
+            serde_json::from_slice(&bytes).map_err(|err| Error::Cursor(err.to_string()))?;
+        json.as_object()
+            .ok_or_else(|| Error::Cursor("key is not an object".into()))
+            .and_then(|key| item_from_json(key).map_err(|err| Error::Cursor(err.to_string())))
+    }
+}
+
+fn signer(key: &[u8]) -> Result<Hmac<Sha256>, Error> {
+    <Hmac<Sha256> as Mac>::new_from_slice(key).map_err(|err| Error::Cursor(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::attribute_value::AttributeMap;
+
+    fn key() -> Item {
+        AttributeMap::new()
+            .set_s("pk", "SHOP#1")
+            .set_s("sk", "STAFF#100")
+            .set_n("age", "20")
+            .into_item()
+    }
+
+    fn tamper(cursor: &str) -> String {
+        let mut bytes = URL_SAFE_NO_PAD.decode(cursor).unwrap();
+        bytes[0] ^= 1;
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    #[test]
+    fn plain_cursor_roundtrips() {
+        let codec = CursorCodec::new();
+        let cursor = codec.encode(&key()).unwrap();
+        assert!(cursor
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(codec.decode(&cursor).unwrap(), key());
+    }
+
+    #[test]
+    fn signed_cursor_rejects_tampering() {
+        let codec = CursorCodec::new().set_signing_key("secret");
+        let cursor = codec.encode(&key()).unwrap();
+        assert_eq!(codec.decode(&cursor).unwrap(), key());
+
+        assert!(matches!(
+            codec.decode(&tamper(&cursor)),
+            Err(Error::Cursor(_))
+        ));
+
+        let other = CursorCodec::new().set_signing_key("other");
+        assert!(other.decode(&cursor).is_err());
+
+        let plain = CursorCodec::new().encode(&key()).unwrap();
+        assert!(codec.decode(&plain).is_err());
+    }
+
+    #[test]
+    fn encrypted_cursor_hides_key_values() {
+        let codec = CursorCodec::new().set_encryption_key([1; 32]);
+        let cursor = codec.encode(&key()).unwrap();
+        let bytes = URL_SAFE_NO_PAD.decode(&cursor).unwrap();
+        assert!(!String::from_utf8_lossy(&bytes).contains("SHOP#1"));
+        assert_eq!(codec.decode(&cursor).unwrap(), key());
+
+        assert!(codec.decode(&tamper(&cursor)).is_err());
+
+        let other = CursorCodec::new().set_encryption_key([2; 32]);
+        assert!(other.decode(&cursor).is_err());
+    }
+
+    #[test]
+    fn it_fails_to_decode_malformed_cursor() {
+        let codec = CursorCodec::new();
+        assert!(codec.decode("!!!").is_err());
+        assert!(codec.decode(&URL_SAFE_NO_PAD.encode("[1]")).is_err());
+
+        let codec = CursorCodec::new().set_signing_key("secret");
+        assert!(codec.decode("").is_err());
+
+        let codec = CursorCodec::new().set_encryption_key([1; 32]);
+        assert!(codec.decode("").is_err());
+    }
+}
@@ -0,0 +1,428 @@
+use super::{helpers::attribute_value::opt_s, BoxError, DynamodbTable, Error, Item};
+
+use std::collections::HashMap;
+
+/// Tells which entity an item belongs to in a single-table design.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Discriminator<'a> {
+    /// Matches the items whose attribute equals to the value, like an entity type attribute.
+    Attribute { name: &'a str, value: &'a str },
+
+    /// Matches the items whose string attribute begins with the prefix, like `STAFF#` of
+    /// the sort key.
+    KeyPrefix { name: &'a str, prefix: &'a str },
+}
+
+impl Discriminator<'_> {
+    /// Return true if the item belongs to the entity.
+    ///
+    /// ```
+    /// # use dynamo_mapper::{helpers::attribute_value::AttributeMap, Discriminator};
+    /// let item = AttributeMap::new()
+    ///     .set_s("sk", "STAFF#100")
+    ///     .set_s("_et", "Staff")
+    ///     .into_item();
+    ///
+    /// let by_prefix = Discriminator::KeyPrefix { name: "sk", prefix: "STAFF#" };
+    /// assert!(by_prefix.matches(&item));
+    ///
+    /// let by_attribute = Discriminator::Attribute { name: "_et", value: "Shop" };
+    /// assert!(!by_attribute.matches(&item));
+    /// ```
+    pub fn matches(&self, item: &Item) -> bool {
+        match self {
+            Self::Attribute { name, value } => {
+                item.get(*name).and_then(opt_s).is_some_and(|v| v == value)
+            }
+            Self::KeyPrefix { name, prefix } => item
+                .get(*name)
+                .and_then(opt_s)
+                .is_some_and(|v| v.starts_with(prefix)),
+        }
+    }
+}
+
+/// Fail if the table defines [`DynamodbTable::ENTITY_TYPE`] and the item is of another entity.
+pub(crate) fn check_entity_type<'a, T>(item: &Item) -> Result<(), Error>
+where
+    T: DynamodbTable<'a> + ?Sized,
+{
+    match T::ENTITY_TYPE {
+        Some(expected) => {
+            let actual = item.get(T::ENTITY_TYPE_ATTRIBUTE).and_then(opt_s);
+            if actual.is_some_and(|v| v == expected) {
+                Ok(())
+            } else {
+                Err(Error::EntityType {
+                    expected: expected.into(),
+                    actual: actual.cloned(),
+                })
+            }
+        }
+        None => Ok(()),
+    }
+}
+
+/// Return true if the `ProjectionExpression` includes [`DynamodbTable::ENTITY_TYPE_ATTRIBUTE`],
+/// or there is no projection, so that the entity type can be checked on the returned items.
+pub(crate) fn projects_entity_type<'a, T>(
+    projection: Option<&str>,
+    names: Option<&HashMap<String, String>>,
+) -> bool
+where
+    T: DynamodbTable<'a> + ?Sized,
+{
+    let Some(projection) = projection else {
+        return true;
+    };
+    projection.split(',').any(|path| {
+        let name = path.trim().split(['.', '[']).next().unwrap_or_default();
+        let name = match name.starts_with('#') {
+            true => names.and_then(|names| names.get(name)).map(String::as_str),
+            false => Some(name),
+        };
+        name == Some(T::ENTITY_TYPE_ATTRIBUTE)
+    })
+}
+
+/// A variant of the [`EntityEnum`], which consists of the discriminator and the decoder.
+pub struct Variant<E> {
+    pub discriminator: Discriminator<'static>,
+    pub decode: fn(Item) -> Result<E, BoxError>,
+}
+
+impl<E> Variant<E> {
+    /// Create a new instance.
+    pub fn new(
+        discriminator: Discriminator<'static>,
+        decode: fn(Item) -> Result<E, BoxError>,
+    ) -> Self {
+        Self {
+            discriminator,
+            decode,
+        }
+    }
+}
+
+/// Implement this trait to an enum which gathers the entities stored in one table, so that
+/// each item is decoded into the right entity.
+///
+/// ```
+/// # use dynamo_mapper::{helpers::attribute_value::AttributeMap, BoxError, Discriminator, EntityEnum, Item, Variant};
+/// struct Shop { name: String }
+/// struct Staff { name: String }
+///
+/// enum ShopCollection {
+///     Shop(Shop),
+///     Staff(Staff),
+/// }
+///
+/// impl EntityEnum for ShopCollection {
+///     fn variants() -> Vec<Variant<Self>> {
+///         vec![
+///             Variant::new(
+///                 Discriminator::KeyPrefix { name: "sk", prefix: "SHOP#" },
+///                 |item| {
+///                     let name = AttributeMap::from(item).s("name").cloned().ok_or("no name")?;
+///                     Ok(Self::Shop(Shop { name }))
+///                 },
+///             ),
+///             Variant::new(
+///                 Discriminator::KeyPrefix { name: "sk", prefix: "STAFF#" },
+///                 |item| {
+///                     let name = AttributeMap::from(item).s("name").cloned().ok_or("no name")?;
+///                     Ok(Self::Staff(Staff { name }))
+///                 },
+///             ),
+///         ]
+///     }
+/// }
+///
+/// impl TryFrom<Item> for ShopCollection {
+///     type Error = BoxError;
+///
+///     fn try_from(item: Item) -> Result<Self, Self::Error> {
+///         Self::decode(item)
+///     }
+/// }
+///
+/// let item = AttributeMap::new()
+///     .set_s("sk", "STAFF#100")
+///     .set_s("name", "Tanaka")
+///     .into_item();
+/// assert!(matches!(ShopCollection::try_from(item), Ok(ShopCollection::Staff(_))));
+/// ```
+pub trait EntityEnum: Sized {
+    /// Return all the variants of the enum.
+    fn variants() -> Vec<Variant<Self>>;
+
+    /// Decode the item with the first variant whose discriminator matches the item.
+    fn decode(item: Item) -> Result<Self, BoxError> {
+        let variant = Self::variants()
+            .into_iter()
+            .find(|variant| variant.discriminator.matches(&item))
+            .ok_or("no entity matches the item")?;
+        (variant.decode)(item)
+    }
+}
+
+/// Tells whether an entity of the [`EntityEnum`] is the parent of the item collection or
+/// one of its children.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Member<P, C> {
+    Parent(P),
+    Child(C),
+}
+
+/// Implement this trait to an [`EntityEnum`] whose items share a partition as one parent
+/// with its children, like a shop and its staff.
+pub trait CollectionEnum: EntityEnum {
+    type Parent;
+    type Child;
+
+    /// Split the entity into the parent or a child.
+    fn into_member(self) -> Member<Self::Parent, Self::Child>;
+}
+
+/// A parent entity assembled with its children from one item collection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemCollection<P, C> {
+    pub parent: Option<P>,
+    pub children: Vec<C>,
+}
+
+impl<P, C> ItemCollection<P, C> {
+    /// Assemble the entities into the parent and its children keeping their order.
+    ///
+    /// The parent is None if it is not in the entities, for example when the query is
+    /// paginated. Fail if there are multiple parents.
+    pub fn assemble<E>(entities: impl IntoIterator<Item = E>) -> Result<Self, BoxError>
+    where
+        E: CollectionEnum<Parent = P, Child = C>,
+    {
+        let mut parent: Option<P> = None;
+        let mut children: Vec<C> = vec![];
+
+        for entity in entities {
+            match entity.into_member() {
+                Member::Parent(p) => {
+                    if parent.replace(p).is_some() {
+                        return Err("multiple parents are in the item collection".into());
+                    }
+                }
+                Member::Child(c) => children.push(c),
+            }
+        }
+
+        Ok(Self { parent, children })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{helpers::attribute_value::AttributeMap, Key};
+    use aws_sdk_dynamodb::types::AttributeValue;
+
+    #[derive(Debug, PartialEq)]
+    enum Entity {
+        Foo(String),
+        Bar(String),
+    }
+
+    impl EntityEnum for Entity {
+        fn variants() -> Vec<Variant<Self>> {
+            vec![
+                Variant::new(
+                    Discriminator::Attribute {
+                        name: "_et",
+                        value: "Foo",
+                    },
+                    |item| {
+                        Ok(Entity::Foo(
+                            AttributeMap::from(item).s("id").unwrap().into(),
+                        ))
+                    },
+                ),
+                Variant::new(
+                    Discriminator::KeyPrefix {
+                        name: "sk",
+                        prefix: "BAR#",
+                    },
+                    |item| {
+                        Ok(Entity::Bar(
+                            AttributeMap::from(item).s("id").unwrap().into(),
+                        ))
+                    },
+                ),
+            ]
+        }
+    }
+
+    impl CollectionEnum for Entity {
+        type Parent = String;
+        type Child = String;
+
+        fn into_member(self) -> Member<Self::Parent, Self::Child> {
+            match self {
+                Entity::Foo(v) => Member::Parent(v),
+                Entity::Bar(v) => Member::Child(v),
+            }
+        }
+    }
+
+    struct Foo;
+    struct Bar;
+    struct FooKey;
+
+    impl<'a> Key<'a> for FooKey {
+        const PARTITION_KEY: &'a str = "pk";
+        const SORT_KEY: Option<&'a str> = None;
+
+        type PartitionInput = String;
+        type SortInput = ();
+
+        fn partition_key(input: Self::PartitionInput) -> AttributeValue {
+            AttributeValue::S(input)
+        }
+
+        fn sort_key(_: Self::SortInput) -> Option<AttributeValue> {
+            None
+        }
+    }
+
+    impl<'a> DynamodbTable<'a> for Foo {
+        const TABLE_NAME: &'a str = "Foo";
+        const ENTITY_TYPE: Option<&'a str> = Some("Foo");
+
+        type Key = FooKey;
+
+        fn key_inputs(&self) -> (String, ()) {
+            ("1".into(), ())
+        }
+    }
+
+    impl<'a> DynamodbTable<'a> for Bar {
+        const TABLE_NAME: &'a str = "Foo";
+
+        type Key = FooKey;
+
+        fn key_inputs(&self) -> (String, ()) {
+            ("1".into(), ())
+        }
+    }
+
+    #[test]
+    fn check_entity_type_rejects_other_entity() {
+        let item = AttributeMap::new().set_s("_et", "Foo").into_item();
+        assert!(check_entity_type::<Foo>(&item).is_ok());
+
+        let item = AttributeMap::new().set_s("_et", "Bar").into_item();
+        assert!(matches!(
+            check_entity_type::<Foo>(&item),
+            Err(Error::EntityType { expected, actual }) if expected == "Foo" && actual == Some("Bar".into())
+        ));
+
+        let item = AttributeMap::new().set_s("pk", "1").into_item();
+        assert!(matches!(
+            check_entity_type::<Foo>(&item),
+            Err(Error::EntityType { actual: None, .. })
+        ));
+
+        let item = AttributeMap::new().set_s("pk", "1").into_item();
+        assert!(check_entity_type::<Bar>(&item).is_ok());
+    }
+
+    #[test]
+    fn attribute_discriminator_matches_equal_value() {
+        let discriminator = Discriminator::Attribute {
+            name: "_et",
+            value: "Foo",
+        };
+
+        let item = AttributeMap::new().set_s("_et", "Foo").into_item();
+        assert!(discriminator.matches(&item));
+
+        let item = AttributeMap::new().set_s("_et", "FooBar").into_item();
+        assert!(!discriminator.matches(&item));
+
+        let item = AttributeMap::new().set_n("_et", "1").into_item();
+        assert!(!discriminator.matches(&item));
+    }
+
+    #[test]
+    fn key_prefix_discriminator_matches_prefix() {
+        let discriminator = Discriminator::KeyPrefix {
+            name: "sk",
+            prefix: "BAR#",
+        };
+
+        let item = AttributeMap::new().set_s("sk", "BAR#1").into_item();
+        assert!(discriminator.matches(&item));
+
+        let item = AttributeMap::new().set_s("sk", "FOO#1").into_item();
+        assert!(!discriminator.matches(&item));
+
+        let item = AttributeMap::new().set_s("pk", "BAR#1").into_item();
+        assert!(!discriminator.matches(&item));
+    }
+
+    #[test]
+    fn entity_enum_decodes_with_matched_variant() {
+        let item = AttributeMap::new()
+            .set_s("_et", "Foo")
+            .set_s("id", "1")
+            .into_item();
+        assert_eq!(Entity::decode(item).unwrap(), Entity::Foo("1".into()));
+
+        let item = AttributeMap::new()
+            .set_s("sk", "BAR#2")
+            .set_s("id", "2")
+            .into_item();
+        assert_eq!(Entity::decode(item).unwrap(), Entity::Bar("2".into()));
+
+        let item = AttributeMap::new()
+            .set_s("sk", "BAZ#3")
+            .set_s("id", "3")
+            .into_item();
+        assert!(Entity::decode(item).is_err());
+    }
+
+    #[test]
+    fn item_collection_assembles_parent_with_children() {
+        let entities = vec![
+            Entity::Bar("2".into()),
+            Entity::Foo("1".into()),
+            Entity::Bar("3".into()),
+        ];
+        assert_eq!(
+            ItemCollection::assemble(entities).unwrap(),
+            ItemCollection {
+                parent: Some("1".to_string()),
+                children: vec!["2".to_string(), "3".to_string()],
+            }
+        );
+
+        let entities = vec![Entity::Bar("2".into())];
+        assert_eq!(
+            ItemCollection::assemble(entities).unwrap(),
+            ItemCollection {
+                parent: None,
+                children: vec!["2".to_string()],
+            }
+        );
+
+        let entities = vec![Entity::Foo("1".into()), Entity::Foo("2".into())];
+        assert!(ItemCollection::assemble(entities).is_err());
+    }
+
+    #[test]
+    fn projection_includes_entity_type() {
+        let names = HashMap::from([("#et".to_string(), "_et".to_string())]);
+        assert!(projects_entity_type::<Foo>(None, None));
+        assert!(projects_entity_type::<Foo>(Some("id, _et"), None));
+        assert!(projects_entity_type::<Foo>(Some("id,#et"), Some(&names)));
+        assert!(!projects_entity_type::<Foo>(Some("id, name"), None));
+        assert!(!projects_entity_type::<Foo>(Some("#et"), None));
+    }
+}
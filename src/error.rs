@@ -1,10 +1,187 @@
 use super::BoxError;
 
+use std::fmt;
+
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
+    #[cfg(feature = "blocking")]
+    #[error("blocking API failure: {0}")]
+    Blocking(String),
+
     #[error("conversion failure from DynamoDB item into your object: {0}")]
     Conversion(#[source] BoxError),
 
+    #[cfg(feature = "cursor")]
+    #[error("invalid cursor: {0}")]
+    Cursor(String),
+
+    #[error(
+        "entity type mismatch: expected {expected}, found {}",
+        actual.as_deref().unwrap_or("none")
+    )]
+    EntityType {
+        expected: String,
+        actual: Option<String>,
+    },
+
+    /// The error of the backend.
+    ///
+    /// [`Client`](aws_sdk_dynamodb::Client) and the backends emulating DynamoDB return boxed
+    /// [`aws_sdk_dynamodb::Error`], so downcast the error into it to inspect the DynamoDB
+    /// errors, or use [`Error::kind`] to classify them.
     #[error(transparent)]
     Sdk(BoxError),
+
+    #[cfg(feature = "streams")]
+    #[error("invalid stream record: {0}")]
+    Stream(String),
+}
+
+/// Classification of [`Error`] to handle or report errors without matching the DynamoDB
+/// error variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The condition of the request was not satisfied.
+    ConditionalCheckFailed,
+    /// The transaction was canceled.
+    TransactionCanceled,
+    /// The request conflicted with an ongoing transaction on the same item.
+    TransactionConflict,
+    /// The request was throttled by the capacity or the request limits.
+    Throttled,
+    /// The table or the index doesn't exist.
+    ResourceNotFound,
+    /// The request was invalid.
+    Validation,
+    /// The item couldn't be converted into the object.
+    Conversion,
+    /// The pagination cursor was invalid.
+    #[cfg(feature = "cursor")]
+    Cursor,
+    /// The item was of another entity.
+    EntityType,
+    /// The stream record was invalid.
+    #[cfg(feature = "streams")]
+    Stream,
+    /// The blocking API was called within an async runtime or couldn't build its runtime.
+    #[cfg(feature = "blocking")]
+    Blocking,
+    /// Other errors of the backend like network failures.
+    Other,
+}
+
+impl ErrorKind {
+    /// Return the name of the kind in snake case like `conditional_check_failed`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ConditionalCheckFailed => "conditional_check_failed",
+            Self::TransactionCanceled => "transaction_canceled",
+            Self::TransactionConflict => "transaction_conflict",
+            Self::Throttled => "throttled",
+            Self::ResourceNotFound => "resource_not_found",
+            Self::Validation => "validation",
+            Self::Conversion => "conversion",
+            #[cfg(feature = "cursor")]
+            Self::Cursor => "cursor",
+            Self::EntityType => "entity_type",
+            #[cfg(feature = "streams")]
+            Self::Stream => "stream",
+            #[cfg(feature = "blocking")]
+            Self::Blocking => "blocking",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Error {
+    /// Return the kind of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            #[cfg(feature = "blocking")]
+            Self::Blocking(_) => ErrorKind::Blocking,
+            Self::Conversion(_) => ErrorKind::Conversion,
+            #[cfg(feature = "cursor")]
+            Self::Cursor(_) => ErrorKind::Cursor,
+            Self::EntityType { .. } => ErrorKind::EntityType,
+            Self::Sdk(err) => sdk_error_kind(err),
+            #[cfg(feature = "streams")]
+            Self::Stream(_) => ErrorKind::Stream,
+        }
+    }
+}
+
+pub(crate) fn sdk_error_kind(err: &BoxError) -> ErrorKind {
+    use aws_sdk_dynamodb::{error::ProvideErrorMetadata, Error as SdkError};
+
+    let Some(err) = err.downcast_ref::<SdkError>() else {
+        return if err.to_string().starts_with("ValidationException") {
+            ErrorKind::Validation
+        } else {
+            ErrorKind::Other
+        };
+    };
+
+    match err {
+        SdkError::ConditionalCheckFailedException(_) => ErrorKind::ConditionalCheckFailed,
+        SdkError::TransactionCanceledException(_) => ErrorKind::TransactionCanceled,
+        SdkError::TransactionConflictException(_) => ErrorKind::TransactionConflict,
+        SdkError::ProvisionedThroughputExceededException(_)
+        | SdkError::RequestLimitExceeded(_)
+        | SdkError::ThrottlingException(_) => ErrorKind::Throttled,
+        SdkError::ResourceNotFoundException(_) => ErrorKind::ResourceNotFound,
+        err if err.code() == Some("ValidationException") => ErrorKind::Validation,
+        _ => ErrorKind::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_dynamodb::types::error::{
+        ConditionalCheckFailedException, ProvisionedThroughputExceededException,
+    };
+
+    #[test]
+    fn classify_sdk_errors() {
+        let err = aws_sdk_dynamodb::Error::ConditionalCheckFailedException(
+            ConditionalCheckFailedException::builder().build(),
+        );
+        assert_eq!(
+            Error::Sdk(Box::new(err)).kind(),
+            ErrorKind::ConditionalCheckFailed
+        );
+
+        let err = aws_sdk_dynamodb::Error::ProvisionedThroughputExceededException(
+            ProvisionedThroughputExceededException::builder().build(),
+        );
+        assert_eq!(Error::Sdk(Box::new(err)).kind(), ErrorKind::Throttled);
+
+        let err = "ValidationException: invalid expression".into();
+        assert_eq!(Error::Sdk(err).kind(), ErrorKind::Validation);
+
+        let err = "connection refused".into();
+        assert_eq!(Error::Sdk(err).kind(), ErrorKind::Other);
+    }
+
+    #[test]
+    fn classify_crate_errors() {
+        let err = Error::Conversion("no name".into());
+        assert_eq!(err.kind(), ErrorKind::Conversion);
+        assert_eq!(err.kind().to_string(), "conversion");
+    }
+
+    #[cfg(feature = "cursor")]
+    #[test]
+    fn classify_cursor_errors() {
+        let err = Error::Cursor("tampered".into());
+        assert_eq!(err.kind(), ErrorKind::Cursor);
+    }
 }
@@ -0,0 +1,1089 @@
+//! Conversion of the requests, responses and errors into JSON in the shape of the DynamoDB API.
+use crate::{
+    helpers::json::{item_from_json, item_to_json, value_to_json},
+    BoxError, Item,
+};
+
+use aws_sdk_dynamodb::{
+    error::ProvideErrorMetadata,
+    operation::{
+        batch_get_item::{builders::BatchGetItemInputBuilder, BatchGetItemOutput},
+        batch_write_item::{builders::BatchWriteItemInputBuilder, BatchWriteItemOutput},
+        delete_item::{builders::DeleteItemInputBuilder, DeleteItemOutput},
+        get_item::{builders::GetItemInputBuilder, GetItemOutput},
+        put_item::{builders::PutItemInputBuilder, PutItemOutput},
+        query::{builders::QueryInputBuilder, QueryOutput},
+        scan::{builders::ScanInputBuilder, ScanOutput},
+        transact_get_items::{builders::TransactGetItemsInputBuilder, TransactGetItemsOutput},
+        transact_write_items::{
+            builders::TransactWriteItemsInputBuilder, TransactWriteItemsOutput,
+        },
+        update_item::{builders::UpdateItemInputBuilder, UpdateItemOutput},
+        update_time_to_live::{builders::UpdateTimeToLiveInputBuilder, UpdateTimeToLiveOutput},
+    },
+    types::{
+        error::{
+            ConditionalCheckFailedException, IdempotentParameterMismatchException,
+            InternalServerError, ItemCollectionSizeLimitExceededException, LimitExceededException,
+            ProvisionedThroughputExceededException, RequestLimitExceeded, ResourceInUseException,
+            ResourceNotFoundException, ThrottlingException, TransactionCanceledException,
+            TransactionConflictException, TransactionInProgressException,
+        },
+        CancellationReason, Condition, DeleteRequest, ItemResponse, KeysAndAttributes, PutRequest,
+        TimeToLiveSpecification, TransactWriteItem, WriteRequest,
+    },
+};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// A request builder which can be recorded.
+pub(super) trait Request {
+    /// The DynamoDB API name of the operation.
+    const OPERATION: &'static str;
+
+    fn to_json(&self) -> Value;
+}
+
+/// An output which can be recorded and replayed.
+pub(super) trait Response: Sized {
+    fn to_json(&self) -> Value;
+
+    fn from_json(value: &Value) -> Result<Self, BoxError>;
+}
+
+/// A builder of a JSON object which skips absent fields.
+struct Object(Map<String, Value>);
+
+impl Object {
+    fn new() -> Self {
+        Self(Map::new())
+    }
+
+    fn value(mut self, key: &str, value: Option<Value>) -> Self {
+        if let Some(value) = value {
+            self.0.insert(key.into(), value);
+        }
+        self
+    }
+
+    fn string(self, key: &str, value: Option<&str>) -> Self {
+        self.value(key, value.map(|v| Value::String(v.into())))
+    }
+
+    fn name<T: AsRef<str>>(self, key: &str, value: Option<&T>) -> Self {
+        self.string(key, value.map(AsRef::as_ref))
+    }
+
+    fn strings(self, key: &str, value: Option<&Vec<String>>) -> Self {
+        self.value(
+            key,
+            value.map(|v| v.iter().cloned().map(Value::String).collect()),
+        )
+    }
+
+    fn bool(self, key: &str, value: Option<bool>) -> Self {
+        self.value(key, value.map(Value::Bool))
+    }
+
+    fn int(self, key: &str, value: Option<i32>) -> Self {
+        self.value(key, value.map(Value::from))
+    }
+
+    fn item(self, key: &str, value: Option<&Item>) -> Self {
+        self.value(key, value.map(item_to_json))
+    }
+
+    fn items(self, key: &str, value: Option<&Vec<Item>>) -> Self {
+        self.value(key, value.map(|v| v.iter().map(item_to_json).collect()))
+    }
+
+    fn names(self, key: &str, value: Option<&HashMap<String, String>>) -> Self {
+        self.value(
+            key,
+            value.map(|v| {
+                v.iter()
+                    .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+                    .collect::<Map<_, _>>()
+                    .into()
+            }),
+        )
+    }
+
+    fn conditions(self, key: &str, value: Option<&HashMap<String, Condition>>) -> Self {
+        self.value(
+            key,
+            value.map(|v| {
+                v.iter()
+                    .map(|(k, v)| (k.clone(), condition_to_json(v)))
+                    .collect::<Map<_, _>>()
+                    .into()
+            }),
+        )
+    }
+
+    fn build(self) -> Value {
+        Value::Object(self.0)
+    }
+}
+
+fn condition_to_json(condition: &Condition) -> Value {
+    Object::new()
+        .string(
+            "ComparisonOperator",
+            Some(condition.comparison_operator().as_str()),
+        )
+        .value(
+            "AttributeValueList",
+            condition
+                .attribute_value_list
+                .as_ref()
+                .map(|v| v.iter().map(value_to_json).collect()),
+        )
+        .build()
+}
+
+fn field<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    value.get(key).filter(|v| !v.is_null())
+}
+
+fn invalid(key: &str) -> BoxError {
+    format!("invalid fixture field {key}").into()
+}
+
+fn item_from_value(value: &Value) -> Result<Item, BoxError> {
+    item_from_json(value.as_object().ok_or("an item must be an object")?)
+}
+
+fn item_field(value: &Value, key: &str) -> Result<Option<Item>, BoxError> {
+    field(value, key).map(item_from_value).transpose()
+}
+
+fn items_field(value: &Value, key: &str) -> Result<Option<Vec<Item>>, BoxError> {
+    field(value, key)
+        .map(|v| {
+            v.as_array()
+                .ok_or_else(|| invalid(key))?
+                .iter()
+                .map(item_from_value)
+                .collect()
+        })
+        .transpose()
+}
+
+fn string_field(value: &Value, key: &str) -> Result<Option<String>, BoxError> {
+    field(value, key)
+        .map(|v| v.as_str().map(String::from).ok_or_else(|| invalid(key)))
+        .transpose()
+}
+
+fn int_field(value: &Value, key: &str) -> Result<Option<i32>, BoxError> {
+    field(value, key)
+        .map(|v| {
+            v.as_i64()
+                .and_then(|v| i32::try_from(v).ok())
+                .ok_or_else(|| invalid(key))
+        })
+        .transpose()
+}
+
+fn names_field(value: &Value, key: &str) -> Result<Option<HashMap<String, String>>, BoxError> {
+    field(value, key)
+        .map(|v| {
+            v.as_object()
+                .ok_or_else(|| invalid(key))?
+                .iter()
+                .map(|(k, v)| {
+                    v.as_str()
+                        .map(|v| (k.clone(), v.to_string()))
+                        .ok_or_else(|| invalid(key))
+                })
+                .collect()
+        })
+        .transpose()
+}
+
+/// Return the entries of the object field as pairs of the key and the value.
+fn entries<'a>(value: &'a Value, key: &str) -> Result<Vec<(&'a String, &'a Value)>, BoxError> {
+    match field(value, key) {
+        Some(v) => Ok(v.as_object().ok_or_else(|| invalid(key))?.iter().collect()),
+        None => Ok(vec![]),
+    }
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> Result<&'a [Value], BoxError> {
+    match field(value, key) {
+        Some(v) => Ok(v.as_array().ok_or_else(|| invalid(key))?),
+        None => Ok(&[]),
+    }
+}
+
+fn keys_and_attributes_to_json(keys: &KeysAndAttributes) -> Value {
+    Object::new()
+        .value("Keys", Some(keys.keys().iter().map(item_to_json).collect()))
+        .strings("AttributesToGet", keys.attributes_to_get.as_ref())
+        .bool("ConsistentRead", keys.consistent_read())
+        .string("ProjectionExpression", keys.projection_expression())
+        .names(
+            "ExpressionAttributeNames",
+            keys.expression_attribute_names(),
+        )
+        .build()
+}
+
+fn keys_and_attributes_from_json(value: &Value) -> Result<KeysAndAttributes, BoxError> {
+    let keys = array(value, "Keys")?
+        .iter()
+        .map(item_from_value)
+        .collect::<Result<Vec<_>, _>>()?;
+    let keys = KeysAndAttributes::builder()
+        .set_keys(Some(keys))
+        .set_projection_expression(string_field(value, "ProjectionExpression")?)
+        .set_expression_attribute_names(names_field(value, "ExpressionAttributeNames")?)
+        .set_consistent_read(field(value, "ConsistentRead").and_then(Value::as_bool))
+        .build()?;
+    Ok(keys)
+}
+
+fn write_request_to_json(request: &WriteRequest) -> Value {
+    Object::new()
+        .value(
+            "PutRequest",
+            request
+                .put_request()
+                .map(|put| Object::new().item("Item", Some(put.item())).build()),
+        )
+        .value(
+            "DeleteRequest",
+            request
+                .delete_request()
+                .map(|delete| Object::new().item("Key", Some(delete.key())).build()),
+        )
+        .build()
+}
+
+fn write_request_from_json(value: &Value) -> Result<WriteRequest, BoxError> {
+    let put_request = field(value, "PutRequest")
+        .map(|put| -> Result<_, BoxError> {
+            Ok(PutRequest::builder()
+                .set_item(item_field(put, "Item")?)
+                .build()?)
+        })
+        .transpose()?;
+    let delete_request = field(value, "DeleteRequest")
+        .map(|delete| -> Result<_, BoxError> {
+            Ok(DeleteRequest::builder()
+                .set_key(item_field(delete, "Key")?)
+                .build()?)
+        })
+        .transpose()?;
+    Ok(WriteRequest::builder()
+        .set_put_request(put_request)
+        .set_delete_request(delete_request)
+        .build())
+}
+
+fn transact_write_item_to_json(item: &TransactWriteItem) -> Value {
+    let common = |table_name: &str,
+                  condition: Option<&str>,
+                  names: Option<&HashMap<String, String>>,
+                  values: Option<&Item>,
+                  rvoccf: Option<&str>| {
+        Object::new()
+            .string("TableName", Some(table_name))
+            .string("ConditionExpression", condition)
+            .names("ExpressionAttributeNames", names)
+            .item("ExpressionAttributeValues", values)
+            .string("ReturnValuesOnConditionCheckFailure", rvoccf)
+    };
+
+    Object::new()
+        .value(
+            "ConditionCheck",
+            item.condition_check().map(|v| {
+                common(
+                    v.table_name(),
+                    Some(v.condition_expression()),
+                    v.expression_attribute_names(),
+                    v.expression_attribute_values(),
+                    v.return_values_on_condition_check_failure()
+                        .map(|v| v.as_str()),
+                )
+                .item("Key", Some(v.key()))
+                .build()
+            }),
+        )
+        .value(
+            "Put",
+            item.put().map(|v| {
+                common(
+                    v.table_name(),
+                    v.condition_expression(),
+                    v.expression_attribute_names(),
+                    v.expression_attribute_values(),
+                    v.return_values_on_condition_check_failure()
+                        .map(|v| v.as_str()),
+                )
+                .item("Item", Some(v.item()))
+                .build()
+            }),
+        )
+        .value(
+            "Delete",
+            item.delete().map(|v| {
+                common(
+                    v.table_name(),
+                    v.condition_expression(),
+                    v.expression_attribute_names(),
+                    v.expression_attribute_values(),
+                    v.return_values_on_condition_check_failure()
+                        .map(|v| v.as_str()),
+                )
+                .item("Key", Some(v.key()))
+                .build()
+            }),
+        )
+        .value(
+            "Update",
+            item.update().map(|v| {
+                common(
+                    v.table_name(),
+                    v.condition_expression(),
+                    v.expression_attribute_names(),
+                    v.expression_attribute_values(),
+                    v.return_values_on_condition_check_failure()
+                        .map(|v| v.as_str()),
+                )
+                .item("Key", Some(v.key()))
+                .string("UpdateExpression", Some(v.update_expression()))
+                .build()
+            }),
+        )
+        .build()
+}
+
+impl Request for GetItemInputBuilder {
+    const OPERATION: &'static str = "GetItem";
+
+    fn to_json(&self) -> Value {
+        Object::new()
+            .string("TableName", self.get_table_name().as_deref())
+            .item("Key", self.get_key().as_ref())
+            .strings("AttributesToGet", self.get_attributes_to_get().as_ref())
+            .bool("ConsistentRead", *self.get_consistent_read())
+            .name(
+                "ReturnConsumedCapacity",
+                self.get_return_consumed_capacity().as_ref(),
+            )
+            .string(
+                "ProjectionExpression",
+                self.get_projection_expression().as_deref(),
+            )
+            .names(
+                "ExpressionAttributeNames",
+                self.get_expression_attribute_names().as_ref(),
+            )
+            .build()
+    }
+}
+
+impl Request for PutItemInputBuilder {
+    const OPERATION: &'static str = "PutItem";
+
+    fn to_json(&self) -> Value {
+        Object::new()
+            .string("TableName", self.get_table_name().as_deref())
+            .item("Item", self.get_item().as_ref())
+            .name("ReturnValues", self.get_return_values().as_ref())
+            .name(
+                "ReturnConsumedCapacity",
+                self.get_return_consumed_capacity().as_ref(),
+            )
+            .name(
+                "ReturnItemCollectionMetrics",
+                self.get_return_item_collection_metrics().as_ref(),
+            )
+            .name(
+                "ConditionalOperator",
+                self.get_conditional_operator().as_ref(),
+            )
+            .string(
+                "ConditionExpression",
+                self.get_condition_expression().as_deref(),
+            )
+            .names(
+                "ExpressionAttributeNames",
+                self.get_expression_attribute_names().as_ref(),
+            )
+            .item(
+                "ExpressionAttributeValues",
+                self.get_expression_attribute_values().as_ref(),
+            )
+            .name(
+                "ReturnValuesOnConditionCheckFailure",
+                self.get_return_values_on_condition_check_failure().as_ref(),
+            )
+            .build()
+    }
+}
+
+impl Request for UpdateItemInputBuilder {
+    const OPERATION: &'static str = "UpdateItem";
+
+    fn to_json(&self) -> Value {
+        Object::new()
+            .string("TableName", self.get_table_name().as_deref())
+            .item("Key", self.get_key().as_ref())
+            .name("ReturnValues", self.get_return_values().as_ref())
+            .name(
+                "ReturnConsumedCapacity",
+                self.get_return_consumed_capacity().as_ref(),
+            )
+            .name(
+                "ReturnItemCollectionMetrics",
+                self.get_return_item_collection_metrics().as_ref(),
+            )
+            .name(
+                "ConditionalOperator",
+                self.get_conditional_operator().as_ref(),
+            )
+            .string("UpdateExpression", self.get_update_expression().as_deref())
+            .string(
+                "ConditionExpression",
+                self.get_condition_expression().as_deref(),
+            )
+            .names(
+                "ExpressionAttributeNames",
+                self.get_expression_attribute_names().as_ref(),
+            )
+            .item(
+                "ExpressionAttributeValues",
+                self.get_expression_attribute_values().as_ref(),
+            )
+            .name(
+                "ReturnValuesOnConditionCheckFailure",
+                self.get_return_values_on_condition_check_failure().as_ref(),
+            )
+            .build()
+    }
+}
+
+impl Request for DeleteItemInputBuilder {
+    const OPERATION: &'static str = "DeleteItem";
+
+    fn to_json(&self) -> Value {
+        Object::new()
+            .string("TableName", self.get_table_name().as_deref())
+            .item("Key", self.get_key().as_ref())
+            .name("ReturnValues", self.get_return_values().as_ref())
+            .name(
+                "ReturnConsumedCapacity",
+                self.get_return_consumed_capacity().as_ref(),
+            )
+            .name(
+                "ReturnItemCollectionMetrics",
+                self.get_return_item_collection_metrics().as_ref(),
+            )
+            .name(
+                "ConditionalOperator",
+                self.get_conditional_operator().as_ref(),
+            )
+            .string(
+                "ConditionExpression",
+                self.get_condition_expression().as_deref(),
+            )
+            .names(
+                "ExpressionAttributeNames",
+                self.get_expression_attribute_names().as_ref(),
+            )
+            .item(
+                "ExpressionAttributeValues",
+                self.get_expression_attribute_values().as_ref(),
+            )
+            .name(
+                "ReturnValuesOnConditionCheckFailure",
+                self.get_return_values_on_condition_check_failure().as_ref(),
+            )
+            .build()
+    }
+}
+
+impl Request for QueryInputBuilder {
+    const OPERATION: &'static str = "Query";
+
+    fn to_json(&self) -> Value {
+        Object::new()
+            .string("TableName", self.get_table_name().as_deref())
+            .string("IndexName", self.get_index_name().as_deref())
+            .name("Select", self.get_select().as_ref())
+            .strings("AttributesToGet", self.get_attributes_to_get().as_ref())
+            .int("Limit", *self.get_limit())
+            .bool("ConsistentRead", *self.get_consistent_read())
+            .conditions("KeyConditions", self.get_key_conditions().as_ref())
+            .conditions("QueryFilter", self.get_query_filter().as_ref())
+            .name(
+                "ConditionalOperator",
+                self.get_conditional_operator().as_ref(),
+            )
+            .bool("ScanIndexForward", *self.get_scan_index_forward())
+            .item("ExclusiveStartKey", self.get_exclusive_start_key().as_ref())
+            .name(
+                "ReturnConsumedCapacity",
+                self.get_return_consumed_capacity().as_ref(),
+            )
+            .string(
+                "ProjectionExpression",
+                self.get_projection_expression().as_deref(),
+            )
+            .string("FilterExpression", self.get_filter_expression().as_deref())
+            .string(
+                "KeyConditionExpression",
+                self.get_key_condition_expression().as_deref(),
+            )
+            .names(
+                "ExpressionAttributeNames",
+                self.get_expression_attribute_names().as_ref(),
+            )
+            .item(
+                "ExpressionAttributeValues",
+                self.get_expression_attribute_values().as_ref(),
+            )
+            .build()
+    }
+}
+
+impl Request for ScanInputBuilder {
+    const OPERATION: &'static str = "Scan";
+
+    fn to_json(&self) -> Value {
+        Object::new()
+            .string("TableName", self.get_table_name().as_deref())
+            .string("IndexName", self.get_index_name().as_deref())
+            .strings("AttributesToGet", self.get_attributes_to_get().as_ref())
+            .int("Limit", *self.get_limit())
+            .name("Select", self.get_select().as_ref())
+            .conditions("ScanFilter", self.get_scan_filter().as_ref())
+            .name(
+                "ConditionalOperator",
+                self.get_conditional_operator().as_ref(),
+            )
+            .item("ExclusiveStartKey", self.get_exclusive_start_key().as_ref())
+            .name(
+                "ReturnConsumedCapacity",
+                self.get_return_consumed_capacity().as_ref(),
+            )
+            .int("TotalSegments", *self.get_total_segments())
+            .int("Segment", *self.get_segment())
+            .string(
+                "ProjectionExpression",
+                self.get_projection_expression().as_deref(),
+            )
+            .string("FilterExpression", self.get_filter_expression().as_deref())
+            .names(
+                "ExpressionAttributeNames",
+                self.get_expression_attribute_names().as_ref(),
+            )
+            .item(
+                "ExpressionAttributeValues",
+                self.get_expression_attribute_values().as_ref(),
+            )
+            .bool("ConsistentRead", *self.get_consistent_read())
+            .build()
+    }
+}
+
+impl Request for BatchGetItemInputBuilder {
+    const OPERATION: &'static str = "BatchGetItem";
+
+    fn to_json(&self) -> Value {
+        Object::new()
+            .value(
+                "RequestItems",
+                self.get_request_items().as_ref().map(|items| {
+                    items
+                        .iter()
+                        .map(|(k, v)| (k.clone(), keys_and_attributes_to_json(v)))
+                        .collect::<Map<_, _>>()
+                        .into()
+                }),
+            )
+            .name(
+                "ReturnConsumedCapacity",
+                self.get_return_consumed_capacity().as_ref(),
+            )
+            .build()
+    }
+}
+
+impl Request for BatchWriteItemInputBuilder {
+    const OPERATION: &'static str = "BatchWriteItem";
+
+    fn to_json(&self) -> Value {
+        Object::new()
+            .value(
+                "RequestItems",
+                self.get_request_items().as_ref().map(|items| {
+                    items
+                        .iter()
+                        .map(|(k, v)| (k.clone(), v.iter().map(write_request_to_json).collect()))
+                        .collect::<Map<_, _>>()
+                        .into()
+                }),
+            )
+            .name(
+                "ReturnConsumedCapacity",
+                self.get_return_consumed_capacity().as_ref(),
+            )
+            .name(
+                "ReturnItemCollectionMetrics",
+                self.get_return_item_collection_metrics().as_ref(),
+            )
+            .build()
+    }
+}
+
+impl Request for TransactGetItemsInputBuilder {
+    const OPERATION: &'static str = "TransactGetItems";
+
+    fn to_json(&self) -> Value {
+        Object::new()
+            .value(
+                "TransactItems",
+                self.get_transact_items().as_ref().map(|items| {
+                    items
+                        .iter()
+                        .map(|item| {
+                            Object::new()
+                                .value(
+                                    "Get",
+                                    item.get().map(|get| {
+                                        Object::new()
+                                            .string("TableName", Some(get.table_name()))
+                                            .item("Key", Some(get.key()))
+                                            .string(
+                                                "ProjectionExpression",
+                                                get.projection_expression(),
+                                            )
+                                            .names(
+                                                "ExpressionAttributeNames",
+                                                get.expression_attribute_names(),
+                                            )
+                                            .build()
+                                    }),
+                                )
+                                .build()
+                        })
+                        .collect()
+                }),
+            )
+            .name(
+                "ReturnConsumedCapacity",
+                self.get_return_consumed_capacity().as_ref(),
+            )
+            .build()
+    }
+}
+
+impl Request for TransactWriteItemsInputBuilder {
+    const OPERATION: &'static str = "TransactWriteItems";
+
+    fn to_json(&self) -> Value {
+        Object::new()
+            .value(
+                "TransactItems",
+                self.get_transact_items()
+                    .as_ref()
+                    .map(|items| items.iter().map(transact_write_item_to_json).collect()),
+            )
+            .name(
+                "ReturnConsumedCapacity",
+                self.get_return_consumed_capacity().as_ref(),
+            )
+            .name(
+                "ReturnItemCollectionMetrics",
+                self.get_return_item_collection_metrics().as_ref(),
+            )
+            .string(
+                "ClientRequestToken",
+                self.get_client_request_token().as_deref(),
+            )
+            .build()
+    }
+}
+
+fn ttl_spec_to_json(spec: &TimeToLiveSpecification) -> Value {
+    Object::new()
+        .bool("Enabled", Some(spec.enabled()))
+        .string("AttributeName", Some(spec.attribute_name()))
+        .build()
+}
+
+impl Request for UpdateTimeToLiveInputBuilder {
+    const OPERATION: &'static str = "UpdateTimeToLive";
+
+    fn to_json(&self) -> Value {
+        Object::new()
+            .string("TableName", self.get_table_name().as_deref())
+            .value(
+                "TimeToLiveSpecification",
+                self.get_time_to_live_specification()
+                    .as_ref()
+                    .map(ttl_spec_to_json),
+            )
+            .build()
+    }
+}
+
+impl Response for GetItemOutput {
+    fn to_json(&self) -> Value {
+        Object::new().item("Item", self.item()).build()
+    }
+
+    fn from_json(value: &Value) -> Result<Self, BoxError> {
+        Ok(Self::builder().set_item(item_field(value, "Item")?).build())
+    }
+}
+
+macro_rules! impl_attributes_response {
+    ($($output:ty),*) => {
+        $(
+            impl Response for $output {
+                fn to_json(&self) -> Value {
+                    Object::new().item("Attributes", self.attributes()).build()
+                }
+
+                fn from_json(value: &Value) -> Result<Self, BoxError> {
+                    Ok(Self::builder()
+                        .set_attributes(item_field(value, "Attributes")?)
+                        .build())
+                }
+            }
+        )*
+    };
+}
+
+impl_attributes_response!(PutItemOutput, UpdateItemOutput, DeleteItemOutput);
+
+macro_rules! impl_items_response {
+    ($($output:ty),*) => {
+        $(
+            impl Response for $output {
+                fn to_json(&self) -> Value {
+                    Object::new()
+                        .items("Items", self.items.as_ref())
+                        .int("Count", Some(self.count()))
+                        .int("ScannedCount", Some(self.scanned_count()))
+                        .item("LastEvaluatedKey", self.last_evaluated_key())
+                        .build()
+                }
+
+                fn from_json(value: &Value) -> Result<Self, BoxError> {
+                    Ok(Self::builder()
+                        .set_items(items_field(value, "Items")?)
+                        .set_count(int_field(value, "Count")?)
+                        .set_scanned_count(int_field(value, "ScannedCount")?)
+                        .set_last_evaluated_key(item_field(value, "LastEvaluatedKey")?)
+                        .build())
+                }
+            }
+        )*
+    };
+}
+
+impl_items_response!(QueryOutput, ScanOutput);
+
+impl Response for BatchGetItemOutput {
+    fn to_json(&self) -> Value {
+        Object::new()
+            .value(
+                "Responses",
+                self.responses().map(|responses| {
+                    responses
+                        .iter()
+                        .map(|(k, v)| (k.clone(), v.iter().map(item_to_json).collect()))
+                        .collect::<Map<_, _>>()
+                        .into()
+                }),
+            )
+            .value(
+                "UnprocessedKeys",
+                self.unprocessed_keys().map(|keys| {
+                    keys.iter()
+                        .map(|(k, v)| (k.clone(), keys_and_attributes_to_json(v)))
+                        .collect::<Map<_, _>>()
+                        .into()
+                }),
+            )
+            .build()
+    }
+
+    fn from_json(value: &Value) -> Result<Self, BoxError> {
+        let responses = field(value, "Responses")
+            .map(|_| {
+                entries(value, "Responses")?
+                    .into_iter()
+                    .map(|(table, items)| {
+                        let items = items
+                            .as_array()
+                            .ok_or_else(|| invalid("Responses"))?
+                            .iter()
+                            .map(item_from_value)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok((table.clone(), items))
+                    })
+                    .collect::<Result<HashMap<_, _>, BoxError>>()
+            })
+            .transpose()?;
+        let unprocessed_keys = field(value, "UnprocessedKeys")
+            .map(|_| {
+                entries(value, "UnprocessedKeys")?
+                    .into_iter()
+                    .map(|(table, keys)| Ok((table.clone(), keys_and_attributes_from_json(keys)?)))
+                    .collect::<Result<HashMap<_, _>, BoxError>>()
+            })
+            .transpose()?;
+
+        Ok(Self::builder()
+            .set_responses(responses)
+            .set_unprocessed_keys(unprocessed_keys)
+            .build())
+    }
+}
+
+impl Response for BatchWriteItemOutput {
+    fn to_json(&self) -> Value {
+        Object::new()
+            .value(
+                "UnprocessedItems",
+                self.unprocessed_items().map(|items| {
+                    items
+                        .iter()
+                        .map(|(k, v)| (k.clone(), v.iter().map(write_request_to_json).collect()))
+                        .collect::<Map<_, _>>()
+                        .into()
+                }),
+            )
+            .build()
+    }
+
+    fn from_json(value: &Value) -> Result<Self, BoxError> {
+        let unprocessed_items = field(value, "UnprocessedItems")
+            .map(|_| {
+                entries(value, "UnprocessedItems")?
+                    .into_iter()
+                    .map(|(table, requests)| {
+                        let requests = requests
+                            .as_array()
+                            .ok_or_else(|| invalid("UnprocessedItems"))?
+                            .iter()
+                            .map(write_request_from_json)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok((table.clone(), requests))
+                    })
+                    .collect::<Result<HashMap<_, _>, BoxError>>()
+            })
+            .transpose()?;
+
+        Ok(Self::builder()
+            .set_unprocessed_items(unprocessed_items)
+            .build())
+    }
+}
+
+impl Response for TransactGetItemsOutput {
+    fn to_json(&self) -> Value {
+        Object::new()
+            .value(
+                "Responses",
+                self.responses.as_ref().map(|responses| {
+                    responses
+                        .iter()
+                        .map(|response| Object::new().item("Item", response.item()).build())
+                        .collect()
+                }),
+            )
+            .build()
+    }
+
+    fn from_json(value: &Value) -> Result<Self, BoxError> {
+        let responses = field(value, "Responses")
+            .map(|_| {
+                array(value, "Responses")?
+                    .iter()
+                    .map(|response| {
+                        Ok(ItemResponse::builder()
+                            .set_item(item_field(response, "Item")?)
+                            .build())
+                    })
+                    .collect::<Result<Vec<_>, BoxError>>()
+            })
+            .transpose()?;
+
+        Ok(Self::builder().set_responses(responses).build())
+    }
+}
+
+impl Response for TransactWriteItemsOutput {
+    fn to_json(&self) -> Value {
+        Object::new().build()
+    }
+
+    fn from_json(_: &Value) -> Result<Self, BoxError> {
+        Ok(Self::builder().build())
+    }
+}
+
+impl Response for UpdateTimeToLiveOutput {
+    fn to_json(&self) -> Value {
+        Object::new()
+            .value(
+                "TimeToLiveSpecification",
+                self.time_to_live_specification().map(ttl_spec_to_json),
+            )
+            .build()
+    }
+
+    fn from_json(value: &Value) -> Result<Self, BoxError> {
+        let spec = field(value, "TimeToLiveSpecification")
+            .map(|spec| -> Result<_, BoxError> {
+                Ok(TimeToLiveSpecification::builder()
+                    .set_enabled(field(spec, "Enabled").and_then(Value::as_bool))
+                    .set_attribute_name(string_field(spec, "AttributeName")?)
+                    .build()?)
+            })
+            .transpose()?;
+
+        Ok(Self::builder().set_time_to_live_specification(spec).build())
+    }
+}
+
+fn cancellation_reason_to_json(reason: &CancellationReason) -> Value {
+    Object::new()
+        .string("Code", reason.code())
+        .string("Message", reason.message())
+        .item("Item", reason.item())
+        .build()
+}
+
+fn cancellation_reason_from_json(value: &Value) -> Result<CancellationReason, BoxError> {
+    Ok(CancellationReason::builder()
+        .set_code(string_field(value, "Code")?)
+        .set_message(string_field(value, "Message")?)
+        .set_item(item_field(value, "Item")?)
+        .build())
+}
+
+macro_rules! simple_errors {
+    ($($variant:ident),* $(,)?) => {
+        fn simple_error_to_json(err: &aws_sdk_dynamodb::Error) -> Option<Value> {
+            let (code, message) = match err {
+                $(aws_sdk_dynamodb::Error::$variant(err) => (stringify!($variant), err.message()),)*
+                _ => return None,
+            };
+            Some(
+                Object::new()
+                    .string("Code", Some(code))
+                    .string("Message", message)
+                    .build(),
+            )
+        }
+
+        fn simple_error_from_json(code: &str, message: Option<String>) -> Option<aws_sdk_dynamodb::Error> {
+            match code {
+                $(stringify!($variant) => Some(aws_sdk_dynamodb::Error::$variant(
+                    $variant::builder().set_message(message).build(),
+                )),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+simple_errors!(
+    IdempotentParameterMismatchException,
+    InternalServerError,
+    ItemCollectionSizeLimitExceededException,
+    LimitExceededException,
+    ProvisionedThroughputExceededException,
+    RequestLimitExceeded,
+    ResourceInUseException,
+    ResourceNotFoundException,
+    ThrottlingException,
+    TransactionConflictException,
+    TransactionInProgressException,
+);
+
+/// Convert the error into JSON. The DynamoDB errors keep their codes, and the others are
+/// recorded only with their messages.
+pub(super) fn error_to_json(err: &BoxError) -> Value {
+    let Some(err) = err.downcast_ref::<aws_sdk_dynamodb::Error>() else {
+        return Object::new()
+            .string("Message", Some(&err.to_string()))
+            .build();
+    };
+
+    match err {
+        aws_sdk_dynamodb::Error::ConditionalCheckFailedException(err) => Object::new()
+            .string("Code", Some("ConditionalCheckFailedException"))
+            .string("Message", err.message())
+            .item("Item", err.item())
+            .build(),
+        aws_sdk_dynamodb::Error::TransactionCanceledException(err) => Object::new()
+            .string("Code", Some("TransactionCanceledException"))
+            .string("Message", err.message())
+            .value(
+                "CancellationReasons",
+                err.cancellation_reasons
+                    .as_ref()
+                    .map(|reasons| reasons.iter().map(cancellation_reason_to_json).collect()),
+            )
+            .build(),
+        err => simple_error_to_json(err).unwrap_or_else(|| {
+            Object::new()
+                .string("Code", err.code())
+                .string("Message", Some(&err.to_string()))
+                .build()
+        }),
+    }
+}
+
+/// Restore the error from JSON. The errors of the unknown codes are restored as messages.
+pub(super) fn error_from_json(value: &Value) -> Result<BoxError, BoxError> {
+    let code = string_field(value, "Code")?;
+    let message = string_field(value, "Message")?;
+
+    let err = match code.as_deref() {
+        Some("ConditionalCheckFailedException") => {
+            aws_sdk_dynamodb::Error::ConditionalCheckFailedException(
+                ConditionalCheckFailedException::builder()
+                    .set_message(message)
+                    .set_item(item_field(value, "Item")?)
+                    .build(),
+            )
+        }
+        Some("TransactionCanceledException") => {
+            let reasons = field(value, "CancellationReasons")
+                .map(|_| {
+                    array(value, "CancellationReasons")?
+                        .iter()
+                        .map(cancellation_reason_from_json)
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .transpose()?;
+            aws_sdk_dynamodb::Error::TransactionCanceledException(
+                TransactionCanceledException::builder()
+                    .set_message(message)
+                    .set_cancellation_reasons(reasons)
+                    .build(),
+            )
+        }
+        Some(code) => match simple_error_from_json(code, message.clone()) {
+            Some(err) => err,
+            None => {
+                let message = message.unwrap_or_default();
+                return Ok(format!("{code}: {message}").into());
+            }
+        },
+        None => return Ok(message.unwrap_or_default().into()),
+    };
+    Ok(Box::new(err))
+}
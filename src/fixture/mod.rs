@@ -0,0 +1,415 @@
+//! Recording and replaying DynamoDB requests for hermetic tests.
+//!
+//! Wrap a backend, such as a [`Client`](aws_sdk_dynamodb::Client) connected to DynamoDB
+//! Local, with [`RecordingBackend`] and run the test once to capture every request and its
+//! response into a JSON fixture file. Then run the test against [`ReplayBackend`] loaded from
+//! the file, which serves the recorded responses without any network access.
+//!
+//! ```no_run
+//! use dynamo_mapper::fixture::{RecordingBackend, ReplayBackend};
+//! # async fn example(client: aws_sdk_dynamodb::Client) -> Result<(), dynamo_mapper::BoxError> {
+//!
+//! // Capture once against DynamoDB Local.
+//! let backend = RecordingBackend::new(client);
+//! // ... send the operations with `&backend` ...
+//! backend.save("tests/fixtures/orders.json")?;
+//!
+//! // Then replay in the tests.
+//! let backend = ReplayBackend::load("tests/fixtures/orders.json")?;
+//! // ... send the same operations with `&backend` ...
+//! assert_eq!(backend.remaining(), 0);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The fixture is a JSON array of the interactions in the order they were made. Each one has
+//! the API name of the `Operation`, the `Request` in the shape of the DynamoDB API and either
+//! the `Response` or the `Error`. The DynamoDB errors are replayed as the same
+//! [`aws_sdk_dynamodb::Error`] variants, and the other errors are replayed with their
+//! messages.
+//!
+//! A request is served only when it is exactly equal to a recorded one, so requests with
+//! nondeterministic values like timestamps or random ids don't match on replay.
+mod convert;
+
+use self::convert::{error_from_json, error_to_json, Request, Response};
+use crate::{interceptor::Decoded, retry::RetryPolicy, BoxError, DynamoBackend, TableNameResolver};
+
+use aws_sdk_dynamodb::operation::{
+    batch_get_item::{builders::BatchGetItemInputBuilder, BatchGetItemOutput},
+    batch_write_item::{builders::BatchWriteItemInputBuilder, BatchWriteItemOutput},
+    delete_item::{builders::DeleteItemInputBuilder, DeleteItemOutput},
+    get_item::{builders::GetItemInputBuilder, GetItemOutput},
+    put_item::{builders::PutItemInputBuilder, PutItemOutput},
+    query::{builders::QueryInputBuilder, QueryOutput},
+    scan::{builders::ScanInputBuilder, ScanOutput},
+    transact_get_items::{builders::TransactGetItemsInputBuilder, TransactGetItemsOutput},
+    transact_write_items::{builders::TransactWriteItemsInputBuilder, TransactWriteItemsOutput},
+    update_item::{builders::UpdateItemInputBuilder, UpdateItemOutput},
+    update_time_to_live::{builders::UpdateTimeToLiveInputBuilder, UpdateTimeToLiveOutput},
+};
+use serde_json::{json, Value};
+use std::{
+    fs,
+    path::Path,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// A request and its outcome made through a backend.
+#[derive(Debug, Clone, PartialEq)]
+struct Interaction {
+    operation: String,
+    request: Value,
+    outcome: Result<Value, Value>,
+}
+
+impl Interaction {
+    fn to_json(&self) -> Value {
+        let (key, outcome) = match &self.outcome {
+            Ok(response) => ("Response", response),
+            Err(err) => ("Error", err),
+        };
+        json!({
+            "Operation": self.operation,
+            "Request": self.request,
+            key: outcome,
+        })
+    }
+
+    fn from_json(value: &Value) -> Result<Self, BoxError> {
+        let operation = value["Operation"]
+            .as_str()
+            .ok_or("an interaction must have the Operation")?;
+        let outcome = match (value.get("Response"), value.get("Error")) {
+            (Some(response), None) => Ok(response.clone()),
+            (None, Some(err)) => Err(err.clone()),
+            _ => return Err("an interaction must have either the Response or the Error".into()),
+        };
+        Ok(Self {
+            operation: operation.into(),
+            request: value.get("Request").cloned().unwrap_or(Value::Null),
+            outcome,
+        })
+    }
+}
+
+/// A [`DynamoBackend`] which records the requests and the responses of the inner backend.
+#[derive(Debug)]
+pub struct RecordingBackend<B> {
+    inner: B,
+    interactions: Mutex<Vec<Interaction>>,
+}
+
+impl<B: DynamoBackend> RecordingBackend<B> {
+    /// Wrap the backend to record.
+    pub fn new(inner: B) -> Self {
+        Self {
+            inner,
+            interactions: Mutex::new(vec![]),
+        }
+    }
+
+    /// Return the number of the recorded interactions.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Return true if no interactions are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Return the recorded interactions as a fixture.
+    pub fn to_json(&self) -> Value {
+        Value::Array(self.lock().iter().map(Interaction::to_json).collect())
+    }
+
+    /// Write the recorded interactions into the fixture file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), BoxError> {
+        let json = serde_json::to_string_pretty(&self.to_json())?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Return the inner backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Interaction>> {
+        self.interactions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    async fn record<I, O, F>(&self, input: I, send: impl FnOnce(I) -> F) -> Result<O, BoxError>
+    where
+        I: Request,
+        O: Response,
+        F: std::future::Future<Output = Result<O, BoxError>>,
+    {
+        let request = input.to_json();
+        let result = send(input).await;
+        let outcome = match &result {
+            Ok(output) => Ok(output.to_json()),
+            Err(err) => Err(error_to_json(err)),
+        };
+        self.lock().push(Interaction {
+            operation: I::OPERATION.into(),
+            request,
+            outcome,
+        });
+        result
+    }
+}
+
+/// A [`DynamoBackend`] which serves the recorded responses.
+///
+/// Each request is served by the first recorded interaction of the same operation and the same
+/// request which hasn't been served yet, so the same request can be recorded several times
+/// with different responses. A request which doesn't match any interaction fails with an
+/// error starting with `unexpected request`.
+#[derive(Debug)]
+pub struct ReplayBackend {
+    interactions: Mutex<Vec<Option<Interaction>>>,
+}
+
+impl ReplayBackend {
+    /// Create a backend serving the fixture.
+    pub fn from_json(value: &Value) -> Result<Self, BoxError> {
+        let interactions = value
+            .as_array()
+            .ok_or("a fixture must be an array of interactions")?
+            .iter()
+            .map(|interaction| Interaction::from_json(interaction).map(Some))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            interactions: Mutex::new(interactions),
+        })
+    }
+
+    /// Create a backend serving the fixture file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BoxError> {
+        let json = fs::read_to_string(path)?;
+        Self::from_json(&serde_json::from_str(&json)?)
+    }
+
+    /// Return the number of the interactions which haven't been served yet.
+    pub fn remaining(&self) -> usize {
+        self.lock().iter().flatten().count()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Option<Interaction>>> {
+        self.interactions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn replay<I: Request, O: Response>(&self, input: I) -> Result<O, BoxError> {
+        let request = input.to_json();
+        let interaction = self
+            .lock()
+            .iter_mut()
+            .find(|interaction| {
+                interaction.as_ref().is_some_and(|interaction| {
+                    interaction.operation == I::OPERATION && interaction.request == request
+                })
+            })
+            .and_then(Option::take)
+            .ok_or_else(|| format!("unexpected request: {} {request}", I::OPERATION))?;
+
+        match interaction.outcome {
+            Ok(response) => O::from_json(&response),
+            Err(err) => Err(error_from_json(&err)?),
+        }
+    }
+}
+
+macro_rules! impl_fixture_backends {
+    ($($method:ident: $input:ty => $output:ty,)*) => {
+        impl<B: DynamoBackend> DynamoBackend for RecordingBackend<B> {
+            $(
+                async fn $method(&self, input: $input) -> Result<$output, BoxError> {
+                    self.record(input, |input| self.inner.$method(input)).await
+                }
+            )*
+
+            fn retry_policy(&self) -> Option<&RetryPolicy> {
+                self.inner.retry_policy()
+            }
+
+            fn table_name_resolver(&self) -> Option<&dyn TableNameResolver> {
+                self.inner.table_name_resolver()
+            }
+
+            fn after_decode(&self, decoded: &Decoded<'_>) {
+                self.inner.after_decode(decoded)
+            }
+        }
+
+        impl DynamoBackend for ReplayBackend {
+            $(
+                async fn $method(&self, input: $input) -> Result<$output, BoxError> {
+                    self.replay(input)
+                }
+            )*
+        }
+    };
+}
+
+impl_fixture_backends! {
+    get_item: GetItemInputBuilder => GetItemOutput,
+    put_item: PutItemInputBuilder => PutItemOutput,
+    update_item: UpdateItemInputBuilder => UpdateItemOutput,
+    delete_item: DeleteItemInputBuilder => DeleteItemOutput,
+    query: QueryInputBuilder => QueryOutput,
+    scan: ScanInputBuilder => ScanOutput,
+    batch_get_item: BatchGetItemInputBuilder => BatchGetItemOutput,
+    batch_write_item: BatchWriteItemInputBuilder => BatchWriteItemOutput,
+    transact_get_items: TransactGetItemsInputBuilder => TransactGetItemsOutput,
+    transact_write_items: TransactWriteItemsInputBuilder => TransactWriteItemsOutput,
+    update_time_to_live: UpdateTimeToLiveInputBuilder => UpdateTimeToLiveOutput,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        memory::MemoryBackend,
+        schema::{KeyAttribute, TableSchema},
+        Item,
+    };
+    use aws_sdk_dynamodb::{
+        operation::{get_item::GetItemInput, put_item::PutItemInput, query::QueryInput},
+        types::{AttributeValue, BillingMode, ScalarAttributeType},
+    };
+
+    fn item(id: &str, name: &str) -> Item {
+        Item::from([
+            ("id".into(), AttributeValue::S(id.into())),
+            ("name".into(), AttributeValue::S(name.into())),
+        ])
+    }
+
+    fn key(id: &str) -> Item {
+        Item::from([("id".into(), AttributeValue::S(id.into()))])
+    }
+
+    fn put(id: &str, name: &str) -> PutItemInputBuilder {
+        PutItemInput::builder()
+            .table_name("People")
+            .set_item(Some(item(id, name)))
+            .condition_expression("attribute_not_exists(id)")
+    }
+
+    fn get(id: &str) -> GetItemInputBuilder {
+        GetItemInput::builder()
+            .table_name("People")
+            .set_key(Some(key(id)))
+    }
+
+    fn memory_backend() -> MemoryBackend {
+        let schema = TableSchema {
+            table_name: "People".into(),
+            partition_key: KeyAttribute::new("id", ScalarAttributeType::S),
+            sort_key: None,
+            global_secondary_indexes: vec![],
+            local_secondary_indexes: vec![],
+            billing_mode: BillingMode::PayPerRequest,
+            provisioned_throughput: None,
+            stream_specification: None,
+            ttl_attribute: None,
+        };
+        let backend = MemoryBackend::new();
+        backend.create_table(schema).unwrap();
+        backend
+    }
+
+    fn is_conditional_check_failed(err: &BoxError) -> bool {
+        matches!(
+            err.downcast_ref::<aws_sdk_dynamodb::Error>(),
+            Some(aws_sdk_dynamodb::Error::ConditionalCheckFailedException(_))
+        )
+    }
+
+    #[tokio::test]
+    async fn replay_recorded_interactions() {
+        let recording = RecordingBackend::new(memory_backend());
+        recording.put_item(put("1", "Tanaka")).await.unwrap();
+        let err = recording.put_item(put("1", "Suzuki")).await.unwrap_err();
+        assert!(is_conditional_check_failed(&err));
+        recording.get_item(get("1")).await.unwrap();
+        let query = || {
+            QueryInput::builder()
+                .table_name("People")
+                .key_condition_expression("id = :id")
+                .expression_attribute_values(":id", AttributeValue::S("1".into()))
+        };
+        recording.query(query()).await.unwrap();
+        assert_eq!(recording.len(), 4);
+
+        let fixture = recording.to_json();
+        assert_eq!(fixture[0]["Operation"], "PutItem");
+        assert_eq!(fixture[0]["Request"]["Item"]["name"]["S"], "Tanaka");
+        assert_eq!(
+            fixture[1]["Error"]["Code"],
+            "ConditionalCheckFailedException"
+        );
+
+        let replay = ReplayBackend::from_json(&fixture).unwrap();
+        assert_eq!(replay.remaining(), 4);
+
+        replay.put_item(put("1", "Tanaka")).await.unwrap();
+        let err = replay.put_item(put("1", "Suzuki")).await.unwrap_err();
+        assert!(is_conditional_check_failed(&err));
+        let output = replay.get_item(get("1")).await.unwrap();
+        assert_eq!(output.item, Some(item("1", "Tanaka")));
+        let output = replay.query(query()).await.unwrap();
+        assert_eq!(output.items, Some(vec![item("1", "Tanaka")]));
+        assert_eq!(output.count, 1);
+        assert_eq!(replay.remaining(), 0);
+    }
+
+    #[tokio::test]
+    async fn serve_the_same_requests_in_recorded_order() {
+        let recording = RecordingBackend::new(memory_backend());
+        recording.get_item(get("1")).await.unwrap();
+        recording.put_item(put("1", "Tanaka")).await.unwrap();
+        recording.get_item(get("1")).await.unwrap();
+
+        let replay = ReplayBackend::from_json(&recording.to_json()).unwrap();
+        let output = replay.get_item(get("1")).await.unwrap();
+        assert_eq!(output.item, None);
+        let output = replay.get_item(get("1")).await.unwrap();
+        assert_eq!(output.item, Some(item("1", "Tanaka")));
+        assert_eq!(replay.remaining(), 1);
+    }
+
+    #[tokio::test]
+    async fn fail_on_unexpected_request() {
+        let recording = RecordingBackend::new(memory_backend());
+        recording.get_item(get("1")).await.unwrap();
+
+        let replay = ReplayBackend::from_json(&recording.to_json()).unwrap();
+        let err = replay.get_item(get("2")).await.unwrap_err();
+        assert!(err.to_string().starts_with("unexpected request: GetItem"));
+
+        let err = replay.put_item(put("1", "Tanaka")).await.unwrap_err();
+        assert!(err.to_string().starts_with("unexpected request: PutItem"));
+        assert_eq!(replay.remaining(), 1);
+    }
+
+    #[tokio::test]
+    async fn save_and_load_fixture_file() {
+        let recording = RecordingBackend::new(memory_backend());
+        recording.put_item(put("1", "Tanaka")).await.unwrap();
+
+        let path = std::env::temp_dir().join(format!("fixture-{}.json", std::process::id()));
+        recording.save(&path).unwrap();
+        let replay = ReplayBackend::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        replay.put_item(put("1", "Tanaka")).await.unwrap();
+        assert_eq!(replay.remaining(), 0);
+    }
+}
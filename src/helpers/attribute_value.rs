@@ -1,7 +1,8 @@
-use super::Item;
+use super::{ttl, Item};
 
 use aws_sdk_dynamodb::{primitives::Blob, types::AttributeValue};
 use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
 
 /// A wrapper for HashMap<String, AttributeValue>.
 ///
@@ -174,6 +175,16 @@ impl AttributeMap {
         self.set(key, AttributeValue::Ss(val))
     }
 
+    /// Set the TTL value which expires after the given duration from now with the key.
+    pub fn set_expires_in(self, key: impl Into<String>, duration: Duration) -> Self {
+        self.set(key, ttl::expires_in(duration))
+    }
+
+    /// Set the TTL value which expires at the given time with the key.
+    pub fn set_expires_at(self, key: impl Into<String>, time: SystemTime) -> Self {
+        self.set(key, ttl::expires_at(time))
+    }
+
     /// Convert self into HashMap<String, AttributeValue>.
     pub fn into_item(self) -> Item {
         self.into()
@@ -253,6 +264,36 @@ pub fn opt_ss(val: &AttributeValue) -> Option<&Vec<String>> {
     val.as_ss().ok()
 }
 
+/// Format the number into the significant digits and the exponent like `15E-1`, so that
+/// the same numbers like `1.50` and `1.5` are compared equal. The invalid numbers are returned
+/// as they are.
+pub(crate) fn normalize_number(value: &str) -> String {
+    let (base, exponent) = match value.split_once(['e', 'E']) {
+        Some((base, exponent)) => match exponent.parse::<i64>() {
+            Ok(exponent) => (base, exponent),
+            Err(_) => return value.into(),
+        },
+        None => (value, 0),
+    };
+    let (sign, base) = match base.strip_prefix('-') {
+        Some(base) => ("-", base),
+        None => ("", base.strip_prefix('+').unwrap_or(base)),
+    };
+    let (int, frac) = base.split_once('.').unwrap_or((base, ""));
+    let digits = format!("{int}{frac}");
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return value.into();
+    }
+
+    let digits = digits.trim_start_matches('0');
+    let significant = digits.trim_end_matches('0');
+    if significant.is_empty() {
+        return "0".into();
+    }
+    let exponent = exponent - frac.len() as i64 + (digits.len() - significant.len()) as i64;
+    format!("{sign}{significant}E{exponent}")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
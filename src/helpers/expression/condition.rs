@@ -388,7 +388,6 @@ impl fmt::Display for Comperator {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::op;
 
     #[test]
     fn it_creates_equal_to_condition() {
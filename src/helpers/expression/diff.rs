@@ -0,0 +1,266 @@
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+use aws_sdk_dynamodb::types::AttributeValue;
+
+use super::{
+    update::{remove, set, Update, UpdateExpression},
+    Operand,
+};
+use crate::{helpers::attribute_value::normalize_number, Item};
+
+const NAME_PREFIX: &str = "#diff";
+const VALUE_PREFIX: &str = ":diff";
+
+/// An update computed from the difference between the original item and the modified one.
+///
+/// The changed and added attributes are `SET`, and the removed ones are `REMOVE`d. The maps
+/// in both items are compared entry by entry, so only the changed entries are updated with the
+/// nested paths. The other values including lists and sets are updated as a whole.
+///
+/// The values are compared as DynamoDB stores them: the numbers by their values like `1.50`
+/// and `1.5`, and the sets regardless of the order of their elements.
+///
+/// ```
+/// use dynamo_mapper::helpers::{attribute_value::AttributeMap, expression::diff::ItemDiff};
+///
+/// let original = AttributeMap::new()
+///     .set_s("name", "Tanaka")
+///     .set_s("nickname", "Tana")
+///     .set_m("address", AttributeMap::new().set_s("city", "Tokyo").set_s("zip", "100"))
+///     .into_item();
+/// let modified = AttributeMap::new()
+///     .set_s("name", "Tanaka")
+///     .set_m("address", AttributeMap::new().set_s("city", "Osaka").set_s("zip", "100"))
+///     .into_item();
+///
+/// let diff = ItemDiff::new(&original, &modified);
+/// assert_eq!(
+///     diff.expression.to_string(),
+///     "SET #diff0.#diff1 = :diff0 REMOVE #diff2"
+/// );
+/// assert_eq!(diff.names["#diff1"], "city");
+/// assert_eq!(diff.names["#diff2"], "nickname");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemDiff {
+    /// `UpdateExpression` which turns the original item into the modified one.
+    pub expression: UpdateExpression,
+    /// `ExpressionAttributeNames` the expression refers to.
+    pub names: HashMap<String, String>,
+    /// `ExpressionAttributeValues` the expression refers to.
+    pub values: Item,
+}
+
+impl ItemDiff {
+    /// Compute the update from the original item into the modified one.
+    pub fn new(original: &Item, modified: &Item) -> Self {
+        let mut builder = DiffBuilder::default();
+        builder.compare(&[], original, modified);
+        builder.diff
+    }
+
+    /// Return true if the items are the same.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+#[derive(Default)]
+struct DiffBuilder {
+    diff: ItemDiff,
+    placeholders: HashMap<String, String>,
+}
+
+impl DiffBuilder {
+    fn compare(
+        &mut self,
+        parent: &[&str],
+        original: &HashMap<String, AttributeValue>,
+        modified: &HashMap<String, AttributeValue>,
+    ) {
+        let mut names: Vec<&String> = original.keys().chain(modified.keys()).collect();
+        names.sort();
+        names.dedup();
+
+        for name in names {
+            let mut path = parent.to_vec();
+            path.push(name);
+
+            match (original.get(name), modified.get(name)) {
+                (Some(AttributeValue::M(original)), Some(AttributeValue::M(modified))) => {
+                    self.compare(&path, original, modified)
+                }
+                (Some(original), Some(modified)) if same_value(original, modified) => {}
+                (_, Some(modified)) => {
+                    let path = self.path(&path);
+                    let value = self.value(modified.clone());
+                    self.and(set(path.value(value)));
+                }
+                (Some(_), None) => {
+                    let path = self.path(&path);
+                    self.and(remove(path));
+                }
+                (None, None) => {}
+            }
+        }
+    }
+
+    fn and(&mut self, expression: UpdateExpression) {
+        self.diff.expression = mem::take(&mut self.diff.expression).and(expression);
+    }
+
+    /// Return the path of the placeholders like `#diff0.#diff1`.
+    fn path(&mut self, names: &[&str]) -> Operand {
+        let placeholders: Vec<String> = names.iter().map(|name| self.name(name)).collect();
+        Operand::new(placeholders.join("."))
+    }
+
+    /// Return the placeholder of the attribute name, which is shared by the same names.
+    fn name(&mut self, name: &str) -> String {
+        if let Some(placeholder) = self.placeholders.get(name) {
+            return placeholder.clone();
+        }
+
+        let placeholder = format!("{NAME_PREFIX}{}", self.placeholders.len());
+        self.placeholders.insert(name.into(), placeholder.clone());
+        self.diff.names.insert(placeholder.clone(), name.into());
+        placeholder
+    }
+
+    fn value(&mut self, value: AttributeValue) -> Operand {
+        let placeholder = format!("{VALUE_PREFIX}{}", self.diff.values.len());
+        self.diff.values.insert(placeholder.clone(), value);
+        Operand::new(placeholder)
+    }
+}
+
+/// Return true if DynamoDB stores the values as the same one.
+fn same_value(original: &AttributeValue, modified: &AttributeValue) -> bool {
+    fn numbers(values: &[String]) -> HashSet<String> {
+        values.iter().map(|value| normalize_number(value)).collect()
+    }
+
+    match (original, modified) {
+        (AttributeValue::N(original), AttributeValue::N(modified)) => {
+            normalize_number(original) == normalize_number(modified)
+        }
+        (AttributeValue::Ns(original), AttributeValue::Ns(modified)) => {
+            numbers(original) == numbers(modified)
+        }
+        (AttributeValue::Ss(original), AttributeValue::Ss(modified)) => {
+            original.iter().collect::<HashSet<_>>() == modified.iter().collect::<HashSet<_>>()
+        }
+        (AttributeValue::Bs(original), AttributeValue::Bs(modified)) => {
+            original.iter().collect::<HashSet<_>>() == modified.iter().collect::<HashSet<_>>()
+        }
+        (AttributeValue::L(original), AttributeValue::L(modified)) => {
+            original.len() == modified.len()
+                && original
+                    .iter()
+                    .zip(modified)
+                    .all(|(original, modified)| same_value(original, modified))
+        }
+        (AttributeValue::M(original), AttributeValue::M(modified)) => {
+            original.len() == modified.len()
+                && original.iter().all(|(name, original)| {
+                    modified
+                        .get(name)
+                        .is_some_and(|modified| same_value(original, modified))
+                })
+        }
+        (original, modified) => original == modified,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::attribute_value::AttributeMap;
+
+    #[test]
+    fn same_items_have_no_diff() {
+        let item = AttributeMap::new()
+            .set_s("name", "Tanaka")
+            .set_m("tags", AttributeMap::new().set_s("team", "core"))
+            .into_item();
+        assert!(ItemDiff::new(&item, &item).is_empty());
+    }
+
+    #[test]
+    fn set_added_and_changed_attributes() {
+        let original = AttributeMap::new()
+            .set_s("name", "Tanaka")
+            .set_n("age", "30")
+            .into_item();
+        let modified = AttributeMap::new()
+            .set_s("name", "Tanaka")
+            .set_n("age", "31")
+            .set_l("roles", vec![AttributeValue::S("admin".into())])
+            .into_item();
+
+        let diff = ItemDiff::new(&original, &modified);
+        assert_eq!(
+            diff.expression.to_string(),
+            "SET #diff0 = :diff0, #diff1 = :diff1"
+        );
+        assert_eq!(
+            diff.names,
+            HashMap::from([
+                ("#diff0".into(), "age".into()),
+                ("#diff1".into(), "roles".into()),
+            ])
+        );
+        assert_eq!(diff.values[":diff0"], AttributeValue::N("31".into()));
+    }
+
+    #[test]
+    fn update_nested_map_entries() {
+        let original = AttributeMap::new()
+            .set_m(
+                "settings",
+                AttributeMap::new()
+                    .set_m("theme", AttributeMap::new().set_s("color", "dark"))
+                    .set_bool("beta", true),
+            )
+            .into_item();
+        let modified = AttributeMap::new()
+            .set_m(
+                "settings",
+                AttributeMap::new().set_m("theme", AttributeMap::new().set_s("color", "light")),
+            )
+            .into_item();
+
+        let diff = ItemDiff::new(&original, &modified);
+        assert_eq!(
+            diff.expression.to_string(),
+            "SET #diff0.#diff2.#diff3 = :diff0 REMOVE #diff0.#diff1"
+        );
+        assert_eq!(diff.names["#diff1"], "beta");
+        assert_eq!(diff.values[":diff0"], AttributeValue::S("light".into()));
+    }
+
+    #[test]
+    fn same_numbers_and_sets_have_no_diff() {
+        let original = AttributeMap::new()
+            .set_n("price", "1.50")
+            .set_ss("tags", ["a", "b"])
+            .set_ns("sizes", ["10", "2.0"])
+            .set_l("scores", vec![AttributeValue::N("1e2".into())])
+            .into_item();
+        let modified = AttributeMap::new()
+            .set_n("price", "1.5")
+            .set_ss("tags", ["b", "a"])
+            .set_ns("sizes", ["2", "1E1"])
+            .set_l("scores", vec![AttributeValue::N("100".into())])
+            .into_item();
+        assert!(ItemDiff::new(&original, &modified).is_empty());
+
+        let modified = AttributeMap::from(modified)
+            .set_ss("tags", ["a"])
+            .into_item();
+        let diff = ItemDiff::new(&original, &modified);
+        assert_eq!(diff.expression.to_string(), "SET #diff0 = :diff0");
+        assert_eq!(diff.names["#diff0"], "tags");
+    }
+}
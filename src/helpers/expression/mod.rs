@@ -1,5 +1,7 @@
 pub mod condition;
+pub mod diff;
 mod operand;
+pub mod patch;
 pub mod update;
 
 pub use operand::Operand;
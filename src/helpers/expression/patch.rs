@@ -0,0 +1,272 @@
+use std::mem;
+
+use aws_sdk_dynamodb::{primitives::Blob, types::AttributeValue};
+
+use super::{
+    diff::ItemDiff,
+    update::{remove, set, Update},
+    Operand,
+};
+use crate::{helpers::attribute_value::AttributeMap, Item};
+
+/// Derive [`PatchItem`] for a struct whose fields are all `Option<T>` or [`Patch<T>`].
+///
+/// The attribute names are the field names, or the ones given by `#[patch(rename = "...")]`.
+#[cfg(feature = "derive")]
+pub use dynamo_mapper_derive::PatchItem;
+
+const NAME_PREFIX: &str = "#patch";
+const VALUE_PREFIX: &str = ":patch";
+
+/// Change of an attribute in a patch.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Patch<T> {
+    /// Leave the attribute as it is.
+    #[default]
+    Keep,
+    /// Set the attribute to the value.
+    Set(T),
+    /// Remove the attribute.
+    Remove,
+}
+
+/// `None` keeps the attribute.
+impl<T> From<Option<T>> for Patch<T> {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => Self::Set(value),
+            None => Self::Keep,
+        }
+    }
+}
+
+/// The outer `None` keeps the attribute and the inner one removes it, like the double option
+/// for the absent and `null` fields of JSON.
+impl<T> From<Option<Option<T>>> for Patch<T> {
+    fn from(value: Option<Option<T>>) -> Self {
+        match value {
+            Some(Some(value)) => Self::Set(value),
+            Some(None) => Self::Remove,
+            None => Self::Keep,
+        }
+    }
+}
+
+/// Types of the fields of a patch, which are `Option<T>` and [`Patch<T>`].
+pub trait IntoPatch {
+    type Value: PatchValue;
+
+    /// Convert self into [`Patch`].
+    fn into_patch(self) -> Patch<Self::Value>;
+}
+
+impl<T: PatchValue> IntoPatch for Patch<T> {
+    type Value = T;
+
+    fn into_patch(self) -> Patch<T> {
+        self
+    }
+}
+
+impl<T: PatchValue> IntoPatch for Option<T> {
+    type Value = T;
+
+    fn into_patch(self) -> Patch<T> {
+        self.into()
+    }
+}
+
+/// Values which a patch sets to the attributes.
+pub trait PatchValue {
+    /// Convert self into `AttributeValue`.
+    fn into_attribute_value(self) -> AttributeValue;
+}
+
+impl PatchValue for AttributeValue {
+    fn into_attribute_value(self) -> AttributeValue {
+        self
+    }
+}
+
+impl PatchValue for String {
+    fn into_attribute_value(self) -> AttributeValue {
+        AttributeValue::S(self)
+    }
+}
+
+impl PatchValue for &str {
+    fn into_attribute_value(self) -> AttributeValue {
+        AttributeValue::S(self.into())
+    }
+}
+
+impl PatchValue for bool {
+    fn into_attribute_value(self) -> AttributeValue {
+        AttributeValue::Bool(self)
+    }
+}
+
+impl PatchValue for Blob {
+    fn into_attribute_value(self) -> AttributeValue {
+        AttributeValue::B(self)
+    }
+}
+
+impl PatchValue for Item {
+    fn into_attribute_value(self) -> AttributeValue {
+        AttributeValue::M(self)
+    }
+}
+
+impl PatchValue for AttributeMap {
+    fn into_attribute_value(self) -> AttributeValue {
+        self.into_m()
+    }
+}
+
+/// Set as `L`. Use `AttributeValue` for the sets like `SS`.
+impl<T: PatchValue> PatchValue for Vec<T> {
+    fn into_attribute_value(self) -> AttributeValue {
+        AttributeValue::L(
+            self.into_iter()
+                .map(PatchValue::into_attribute_value)
+                .collect(),
+        )
+    }
+}
+
+macro_rules! impl_number_patch_value {
+    ($($ty:ty),*) => {
+        $(
+            impl PatchValue for $ty {
+                fn into_attribute_value(self) -> AttributeValue {
+                    AttributeValue::N(self.to_string())
+                }
+            }
+        )*
+    };
+}
+
+// Floats are not implemented because DynamoDB rejects NaN and infinity. Set them as
+// `AttributeValue::N` after checking they are finite.
+impl_number_patch_value!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// A partial update, like the body of a REST PATCH request.
+///
+/// Implement it with `#[derive(PatchItem)]` of the `derive` feature, or with [`PatchBuilder`].
+/// The update is set to the operation by
+/// [`UpdateItemOperation::set_patch`](crate::operations::update_item::UpdateItemOperation::set_patch).
+pub trait PatchItem {
+    /// Convert self into the update of the changed attributes.
+    fn into_diff(self) -> ItemDiff;
+}
+
+/// Builds the update of a patch field by field.
+///
+/// ```
+/// use dynamo_mapper::helpers::expression::patch::{Patch, PatchBuilder};
+///
+/// let diff = PatchBuilder::new()
+///     .field("name", Some("Tanaka"))
+///     .field("age", None::<u32>)
+///     .field("nickname", Patch::<String>::Remove)
+///     .build();
+///
+/// assert_eq!(diff.expression.to_string(), "SET #patch0 = :patch0 REMOVE #patch1");
+/// assert_eq!(diff.names["#patch1"], "nickname");
+/// ```
+#[derive(Debug, Default)]
+pub struct PatchBuilder {
+    diff: ItemDiff,
+}
+
+impl PatchBuilder {
+    /// Create a new instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the change of the attribute.
+    pub fn field(mut self, name: impl Into<String>, patch: impl IntoPatch) -> Self {
+        let expression = match patch.into_patch() {
+            Patch::Keep => return self,
+            Patch::Set(value) => {
+                let path = self.name(name.into());
+                let value = self.value(value.into_attribute_value());
+                set(path.value(value))
+            }
+            Patch::Remove => remove(self.name(name.into())),
+        };
+        self.diff.expression = mem::take(&mut self.diff.expression).and(expression);
+        self
+    }
+
+    /// Return the update of the changed attributes.
+    pub fn build(self) -> ItemDiff {
+        self.diff
+    }
+
+    fn name(&mut self, name: String) -> Operand {
+        let placeholder = format!("{NAME_PREFIX}{}", self.diff.names.len());
+        self.diff.names.insert(placeholder.clone(), name);
+        Operand::new(placeholder)
+    }
+
+    fn value(&mut self, value: AttributeValue) -> Operand {
+        let placeholder = format!("{VALUE_PREFIX}{}", self.diff.values.len());
+        self.diff.values.insert(placeholder.clone(), value);
+        Operand::new(placeholder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn keep_fields_are_not_updated() {
+        let diff = PatchBuilder::new()
+            .field("name", None::<String>)
+            .field("age", Patch::<u32>::Keep)
+            .build();
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn set_and_remove_fields() {
+        let diff = PatchBuilder::new()
+            .field("age", Some(31))
+            .field("nickname", Patch::<String>::Remove)
+            .field("roles", Patch::Set(vec!["admin", "owner"]))
+            .build();
+
+        assert_eq!(
+            diff.expression.to_string(),
+            "SET #patch0 = :patch0, #patch2 = :patch1 REMOVE #patch1"
+        );
+        assert_eq!(
+            diff.names,
+            HashMap::from([
+                ("#patch0".into(), "age".into()),
+                ("#patch1".into(), "nickname".into()),
+                ("#patch2".into(), "roles".into()),
+            ])
+        );
+        assert_eq!(diff.values[":patch0"], AttributeValue::N("31".into()));
+        assert_eq!(
+            diff.values[":patch1"],
+            AttributeValue::L(vec![
+                AttributeValue::S("admin".into()),
+                AttributeValue::S("owner".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn double_option_into_patch() {
+        assert_eq!(Patch::<u32>::from(None::<Option<u32>>), Patch::Keep);
+        assert_eq!(Patch::<u32>::from(Some(None)), Patch::Remove);
+        assert_eq!(Patch::<u32>::from(Some(Some(1))), Patch::Set(1));
+    }
+}
@@ -0,0 +1,166 @@
+use super::{BoxError, Item};
+
+use aws_sdk_dynamodb::{primitives::Blob, types::AttributeValue};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{Map, Value};
+
+/// Convert the item into DynamoDB JSON like `{"name": {"S": "Tanaka"}}`, which is the format
+/// of AWS CLI, DynamoDB Streams event of AWS Lambda and so on.
+///
+/// ```
+/// # use dynamo_mapper::helpers::{attribute_value::AttributeMap, json::{item_from_json, item_to_json}};
+/// let item = AttributeMap::new()
+///     .set_s("name", "Tanaka")
+///     .set_n("age", "20")
+///     .into_item();
+///
+/// let json = item_to_json(&item);
+/// assert_eq!(json["name"]["S"], "Tanaka");
+/// assert_eq!(json["age"]["N"], "20");
+///
+/// assert_eq!(item_from_json(json.as_object().unwrap()).unwrap(), item);
+/// ```
+pub fn item_to_json(item: &Item) -> Value {
+    Value::Object(
+        item.iter()
+            .map(|(key, value)| (key.clone(), value_to_json(value)))
+            .collect(),
+    )
+}
+
+/// Convert the attribute value into DynamoDB JSON like `{"S": "Tanaka"}`.
+///
+/// Binary values are encoded in base64.
+pub fn value_to_json(value: &AttributeValue) -> Value {
+    let blob = |v: &Blob| Value::String(STANDARD.encode(v.as_ref()));
+    let strings = |v: &Vec<String>| Value::Array(v.iter().cloned().map(Value::String).collect());
+
+    let (tag, inner) = match value {
+        AttributeValue::S(v) => ("S", Value::String(v.clone())),
+        AttributeValue::N(v) => ("N", Value::String(v.clone())),
+        AttributeValue::B(v) => ("B", blob(v)),
+        AttributeValue::Ss(v) => ("SS", strings(v)),
+        AttributeValue::Ns(v) => ("NS", strings(v)),
+        AttributeValue::Bs(v) => ("BS", Value::Array(v.iter().map(blob).collect())),
+        AttributeValue::M(v) => ("M", item_to_json(v)),
+        AttributeValue::L(v) => ("L", Value::Array(v.iter().map(value_to_json).collect())),
+        AttributeValue::Null(v) => ("NULL", Value::Bool(*v)),
+        AttributeValue::Bool(v) => ("BOOL", Value::Bool(*v)),
+        _ => ("NULL", Value::Bool(true)),
+    };
+
+    let mut object = Map::new();
+    object.insert(tag.into(), inner);
+    Value::Object(object)
+}
+
+/// Convert DynamoDB JSON into the item.
+pub fn item_from_json(item: &Map<String, Value>) -> Result<Item, BoxError> {
+    item.iter()
+        .map(|(key, value)| value_from_json(value).map(|value| (key.clone(), value)))
+        .collect()
+}
+
+/// Convert DynamoDB JSON into the attribute value.
+///
+/// Fail if the JSON is not a single-key object tagged by the data type.
+pub fn value_from_json(value: &Value) -> Result<AttributeValue, BoxError> {
+    let invalid = || -> BoxError { format!("invalid attribute value {value}").into() };
+
+    let (tag, inner) = value
+        .as_object()
+        .filter(|object| object.len() == 1)
+        .and_then(|object| object.iter().next())
+        .ok_or_else(invalid)?;
+
+    let string = |v: &Value| v.as_str().map(String::from).ok_or_else(invalid);
+    let strings = |v: &Value| {
+        v.as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(string)
+            .collect::<Result<Vec<String>, BoxError>>()
+    };
+    let blob = |v: &Value| {
+        v.as_str()
+            .and_then(|s| STANDARD.decode(s).ok())
+            .map(Blob::new)
+            .ok_or_else(invalid)
+    };
+
+    let value = match tag.as_str() {
+        "S" => AttributeValue::S(string(inner)?),
+        "N" => AttributeValue::N(string(inner)?),
+        "B" => AttributeValue::B(blob(inner)?),
+        "SS" => AttributeValue::Ss(strings(inner)?),
+        "NS" => AttributeValue::Ns(strings(inner)?),
+        "BS" => AttributeValue::Bs(
+            inner
+                .as_array()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(blob)
+                .collect::<Result<Vec<Blob>, BoxError>>()?,
+        ),
+        "M" => AttributeValue::M(item_from_json(inner.as_object().ok_or_else(invalid)?)?),
+        "L" => AttributeValue::L(
+            inner
+                .as_array()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(value_from_json)
+                .collect::<Result<Vec<AttributeValue>, BoxError>>()?,
+        ),
+        "NULL" => AttributeValue::Null(inner.as_bool().ok_or_else(invalid)?),
+        "BOOL" => AttributeValue::Bool(inner.as_bool().ok_or_else(invalid)?),
+        _ => return Err(invalid()),
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::attribute_value::AttributeMap;
+    use serde_json::json;
+
+    #[test]
+    fn it_converts_all_data_types() {
+        let item = AttributeMap::new()
+            .set_s("s", "foo")
+            .set_n("n", "1.5")
+            .set_b("b", Blob::new("hello"))
+            .set_ss("ss", ["a", "b"])
+            .set_ns("ns", ["1", "2"])
+            .set_bs("bs", vec![Blob::new("a")])
+            .set_m("m", AttributeMap::new().set_bool("bool", true).into_item())
+            .set_l("l", vec![AttributeValue::Null(true)])
+            .into_item();
+
+        let json = item_to_json(&item);
+        assert_eq!(
+            json,
+            json!({
+                "s": { "S": "foo" },
+                "n": { "N": "1.5" },
+                "b": { "B": "aGVsbG8=" },
+                "ss": { "SS": ["a", "b"] },
+                "ns": { "NS": ["1", "2"] },
+                "bs": { "BS": ["YQ=="] },
+                "m": { "M": { "bool": { "BOOL": true } } },
+                "l": { "L": [{ "NULL": true }] },
+            })
+        );
+
+        assert_eq!(item_from_json(json.as_object().unwrap()).unwrap(), item);
+    }
+
+    #[test]
+    fn it_fails_to_convert_invalid_json() {
+        assert!(value_from_json(&json!({ "X": "1" })).is_err());
+        assert!(value_from_json(&json!({ "S": 1 })).is_err());
+        assert!(value_from_json(&json!({ "S": "1", "N": "1" })).is_err());
+        assert!(value_from_json(&json!({ "B": "not base64!" })).is_err());
+        assert!(value_from_json(&json!("foo")).is_err());
+    }
+}
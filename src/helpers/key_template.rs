@@ -0,0 +1,388 @@
+use super::BoxError;
+
+use aws_sdk_dynamodb::types::AttributeValue;
+
+const DELIMITER: char = '#';
+const ESCAPE: char = '\\';
+
+/// A template of the composite key like `ORDER#{date}#{id}`, which renders the key inputs into
+/// [`AttributeValue`] and parses them back.
+///
+/// The template consists of the segments separated by `#`. Each segment is either a literal
+/// or a placeholder `{name}`, which is filled with a value of [`KeySegments`] in order.
+/// `#` and `\` in the values are escaped with `\`, so the values can contain them.
+///
+/// ```
+/// # use aws_sdk_dynamodb::types::AttributeValue;
+/// # use dynamo_mapper::helpers::key_template::KeyTemplate;
+/// const ORDER: KeyTemplate = KeyTemplate::new("ORDER#{date}#{id}");
+///
+/// let value = ORDER.render(&("2023-12-01".to_string(), 100_u32));
+/// assert_eq!(value, AttributeValue::S("ORDER#2023-12-01#100".into()));
+///
+/// let (date, id): (String, u32) = ORDER.parse(&value).unwrap();
+/// assert_eq!(date, "2023-12-01");
+/// assert_eq!(id, 100);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyTemplate<'a> {
+    template: &'a str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TemplateSegment<'a> {
+    Literal(&'a str),
+    Placeholder,
+}
+
+impl<'a> KeyTemplate<'a> {
+    /// Create a new instance.
+    pub const fn new(template: &'a str) -> Self {
+        Self { template }
+    }
+
+    /// Render the inputs into the key value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of the inputs doesn't match the placeholders of the template.
+    pub fn render<S: KeySegments>(&self, input: &S) -> AttributeValue {
+        let values = input.to_segments();
+        let segments = self.segments();
+        let placeholders = segments
+            .iter()
+            .filter(|s| **s == TemplateSegment::Placeholder)
+            .count();
+        assert_eq!(
+            values.len(),
+            placeholders,
+            "The key template {} requires {placeholders} inputs",
+            self.template,
+        );
+        AttributeValue::S(self.join(&segments, &values))
+    }
+
+    /// Render the leading segments of the key value up to the last given input, which is used
+    /// as the `begins_with` condition of the sort key.
+    ///
+    /// The value ends with the delimiter unless all the placeholders are filled, so that
+    /// `ORDER#{date}#{id}` with `2023-12-01` never matches `ORDER#2023-12-010#...`.
+    ///
+    /// ```
+    /// # use aws_sdk_dynamodb::types::AttributeValue;
+    /// # use dynamo_mapper::helpers::key_template::KeyTemplate;
+    /// const ORDER: KeyTemplate = KeyTemplate::new("ORDER#{date}#{id}");
+    ///
+    /// let value = ORDER.render_prefix(&"2023-12-01".to_string());
+    /// assert_eq!(value, AttributeValue::S("ORDER#2023-12-01#".into()));
+    ///
+    /// let value = ORDER.render_prefix(&());
+    /// assert_eq!(value, AttributeValue::S("ORDER#".into()));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the inputs are more than the placeholders of the template.
+    pub fn render_prefix<S: KeySegments>(&self, input: &S) -> AttributeValue {
+        let values = input.to_segments();
+        let segments = self.segments();
+
+        let mut filled = 0;
+        let mut end = 0;
+        for segment in segments.iter() {
+            if *segment == TemplateSegment::Placeholder {
+                if filled == values.len() {
+                    break;
+                }
+                filled += 1;
+            }
+            end += 1;
+        }
+        assert_eq!(
+            filled,
+            values.len(),
+            "The key template {} doesn't have {} placeholders",
+            self.template,
+            values.len(),
+        );
+
+        let mut prefix = self.join(&segments[..end], &values);
+        if end < segments.len() {
+            prefix.push(DELIMITER);
+        }
+        AttributeValue::S(prefix)
+    }
+
+    /// Parse the key value back into the inputs.
+    ///
+    /// Fail if the value is not a String or doesn't match the template.
+    pub fn parse<S: KeySegments>(&self, value: &AttributeValue) -> Result<S, BoxError> {
+        let value = value
+            .as_s()
+            .map_err(|_| format!("key value {value:?} is not a String"))?;
+        let parts = split(value);
+        let segments = self.segments();
+
+        if parts.len() != segments.len() {
+            return Err(format!("key {value} doesn't match template {}", self.template).into());
+        }
+
+        let mut values: Vec<String> = vec![];
+        for (segment, part) in segments.iter().zip(parts) {
+            match segment {
+                TemplateSegment::Literal(literal) if *literal == part => {}
+                TemplateSegment::Literal(_) => {
+                    return Err(
+                        format!("key {value} doesn't match template {}", self.template).into(),
+                    );
+                }
+                TemplateSegment::Placeholder => values.push(unescape(part)),
+            }
+        }
+
+        S::from_segments(values)
+    }
+
+    fn segments(&self) -> Vec<TemplateSegment<'a>> {
+        self.template
+            .split(DELIMITER)
+            .map(|segment| {
+                if segment.starts_with('{') && segment.ends_with('}') && segment.len() >= 2 {
+                    TemplateSegment::Placeholder
+                } else {
+                    TemplateSegment::Literal(segment)
+                }
+            })
+            .collect()
+    }
+
+    fn join(&self, segments: &[TemplateSegment], values: &[String]) -> String {
+        let mut values = values.iter();
+        segments
+            .iter()
+            .map(|segment| match segment {
+                TemplateSegment::Literal(literal) => literal.to_string(),
+                TemplateSegment::Placeholder => {
+                    values.next().map(|v| escape(v)).unwrap_or_default()
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(&DELIMITER.to_string())
+    }
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == DELIMITER || c == ESCAPE {
+            escaped.push(ESCAPE);
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == ESCAPE {
+            if let Some(next) = chars.next() {
+                unescaped.push(next);
+                continue;
+            }
+        }
+        unescaped.push(c);
+    }
+    unescaped
+}
+
+fn split(value: &str) -> Vec<&str> {
+    let mut parts: Vec<&str> = vec![];
+    let mut start = 0;
+    let mut escaped = false;
+
+    for (i, c) in value.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == ESCAPE {
+            escaped = true;
+        } else if c == DELIMITER {
+            parts.push(&value[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+/// A value which fills one placeholder of the [`KeyTemplate`].
+pub trait Segment: Sized {
+    fn to_segment(&self) -> String;
+
+    fn from_segment(segment: &str) -> Result<Self, BoxError>;
+}
+
+macro_rules! impl_segment {
+    ($($t:ty),*) => {
+        $(
+            impl Segment for $t {
+                fn to_segment(&self) -> String {
+                    self.to_string()
+                }
+
+                fn from_segment(segment: &str) -> Result<Self, BoxError> {
+                    segment.parse::<$t>().map_err(|err| err.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_segment!(String, bool, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// The inputs which fill the placeholders of the [`KeyTemplate`] in order.
+///
+/// This is implemented to `()`, a [`Segment`] and tuples of up to 4 [`Segment`]s.
+pub trait KeySegments: Sized {
+    fn to_segments(&self) -> Vec<String>;
+
+    fn from_segments(segments: Vec<String>) -> Result<Self, BoxError>;
+}
+
+impl KeySegments for () {
+    fn to_segments(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn from_segments(segments: Vec<String>) -> Result<Self, BoxError> {
+        if segments.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("expected no segments but got {}", segments.len()).into())
+        }
+    }
+}
+
+impl<T: Segment> KeySegments for T {
+    fn to_segments(&self) -> Vec<String> {
+        vec![self.to_segment()]
+    }
+
+    fn from_segments(segments: Vec<String>) -> Result<Self, BoxError> {
+        match segments.as_slice() {
+            [segment] => T::from_segment(segment),
+            _ => Err(format!("expected 1 segment but got {}", segments.len()).into()),
+        }
+    }
+}
+
+macro_rules! impl_key_segments {
+    ($len:literal; $($t:ident),*) => {
+        impl<$($t: Segment),*> KeySegments for ($($t,)*) {
+            #[allow(non_snake_case)]
+            fn to_segments(&self) -> Vec<String> {
+                let ($($t,)*) = self;
+                vec![$($t.to_segment()),*]
+            }
+
+            fn from_segments(segments: Vec<String>) -> Result<Self, BoxError> {
+                if segments.len() != $len {
+                    return Err(
+                        format!("expected {} segments but got {}", $len, segments.len()).into(),
+                    );
+                }
+                let mut segments = segments.iter();
+                Ok(($($t::from_segment(segments.next().unwrap())?,)*))
+            }
+        }
+    };
+}
+
+impl_key_segments!(2; A, B);
+impl_key_segments!(3; A, B, C);
+impl_key_segments!(4; A, B, C, D);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHOP: KeyTemplate = KeyTemplate::new("SHOP#{id}");
+    const ORDER: KeyTemplate = KeyTemplate::new("ORDER#{date}#{id}");
+
+    fn s(value: &str) -> AttributeValue {
+        AttributeValue::S(value.into())
+    }
+
+    #[test]
+    fn it_renders_single_segment() {
+        assert_eq!(SHOP.render(&"1".to_string()), s("SHOP#1"));
+        assert_eq!(SHOP.render(&100_u32), s("SHOP#100"));
+    }
+
+    #[test]
+    fn it_renders_multiple_segments() {
+        assert_eq!(
+            ORDER.render(&("2023-12-01".to_string(), 1_u8)),
+            s("ORDER#2023-12-01#1")
+        );
+    }
+
+    #[test]
+    fn it_renders_template_without_placeholders() {
+        let template = KeyTemplate::new("PROFILE");
+        assert_eq!(template.render(&()), s("PROFILE"));
+        assert!(template.parse::<()>(&s("PROFILE")).is_ok());
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_panics_if_inputs_dont_match_placeholders() {
+        ORDER.render(&"2023-12-01".to_string());
+    }
+
+    #[test]
+    fn it_escapes_delimiter_in_values() {
+        let value = ORDER.render(&("a#b".to_string(), r"c\d".to_string()));
+        assert_eq!(value, s(r"ORDER#a\#b#c\\d"));
+
+        let (date, id): (String, String) = ORDER.parse(&value).unwrap();
+        assert_eq!(date, "a#b");
+        assert_eq!(id, r"c\d");
+    }
+
+    #[test]
+    fn it_parses_values() {
+        let id: String = SHOP.parse(&s("SHOP#1")).unwrap();
+        assert_eq!(id, "1");
+
+        let (date, id): (String, u64) = ORDER.parse(&s("ORDER#2023-12-01#100")).unwrap();
+        assert_eq!(date, "2023-12-01");
+        assert_eq!(id, 100);
+    }
+
+    #[test]
+    fn it_fails_to_parse_unmatched_values() {
+        assert!(SHOP.parse::<String>(&s("STAFF#1")).is_err());
+        assert!(SHOP.parse::<String>(&s("SHOP#1#2")).is_err());
+        assert!(SHOP
+            .parse::<String>(&AttributeValue::N("1".into()))
+            .is_err());
+        assert!(SHOP.parse::<u8>(&s("SHOP#abc")).is_err());
+        assert!(ORDER.parse::<String>(&s("ORDER#2023-12-01#100")).is_err());
+    }
+
+    #[test]
+    fn it_renders_prefix() {
+        assert_eq!(ORDER.render_prefix(&()), s("ORDER#"));
+        assert_eq!(
+            ORDER.render_prefix(&"2023-12-01".to_string()),
+            s("ORDER#2023-12-01#")
+        );
+        assert_eq!(
+            ORDER.render_prefix(&("2023-12-01".to_string(), 1_u8)),
+            s("ORDER#2023-12-01#1")
+        );
+        assert_eq!(ORDER.render_prefix(&"a#b".to_string()), s(r"ORDER#a\#b#"));
+    }
+}
@@ -2,5 +2,12 @@
 pub mod attribute_value;
 /// Helper structs for building ConditionExpression and UpdateExpression.
 pub mod expression;
+/// Helper functions for converting items from and into DynamoDB JSON.
+#[cfg(feature = "json")]
+pub mod json;
+/// Helper struct for rendering and parsing the composite keys.
+pub mod key_template;
+/// Helper functions for the DynamoDB Time to Live attribute.
+pub mod ttl;
 
 use super::*;
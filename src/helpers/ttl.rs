@@ -0,0 +1,115 @@
+use super::Item;
+
+use aws_sdk_dynamodb::types::AttributeValue;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Create the TTL attribute value which expires after the given duration from now.
+///
+/// ```
+/// # use dynamo_mapper::helpers::ttl::{expiration, expires_in};
+/// # use std::time::{Duration, SystemTime};
+/// let value = expires_in(Duration::from_secs(60));
+/// assert!(expiration(&value).unwrap() > SystemTime::now());
+/// ```
+pub fn expires_in(duration: Duration) -> AttributeValue {
+    expires_at(SystemTime::now() + duration)
+}
+
+/// Create the TTL attribute value which expires at the given time.
+///
+/// DynamoDB requires the TTL attribute to be a Number of the Unix epoch time in seconds,
+/// so the sub-second part of the time is truncated.
+///
+/// ```
+/// # use aws_sdk_dynamodb::types::AttributeValue;
+/// # use dynamo_mapper::helpers::ttl::expires_at;
+/// # use std::time::{Duration, UNIX_EPOCH};
+/// let value = expires_at(UNIX_EPOCH + Duration::from_millis(1_700_000_000_500));
+/// assert_eq!(value, AttributeValue::N("1700000000".into()));
+/// ```
+pub fn expires_at(time: SystemTime) -> AttributeValue {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    AttributeValue::N(secs.to_string())
+}
+
+/// Get the expiration time from the TTL attribute value.
+///
+/// Return None if the value is not a Number of the Unix epoch time in seconds,
+/// in which case DynamoDB never deletes the item.
+pub fn expiration(value: &AttributeValue) -> Option<SystemTime> {
+    value
+        .as_n()
+        .ok()
+        .and_then(|n| n.parse::<u64>().ok())
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// Return true if the item has already expired but DynamoDB has not deleted it yet.
+///
+/// ```
+/// # use dynamo_mapper::helpers::{attribute_value::AttributeMap, ttl::is_expired};
+/// # use std::time::Duration;
+/// let item = AttributeMap::new()
+///     .set_expires_in("ttl", Duration::from_secs(60))
+///     .into_item();
+/// assert!(!is_expired(&item, "ttl"));
+///
+/// let item = AttributeMap::new().set_n("ttl", "1").into_item();
+/// assert!(is_expired(&item, "ttl"));
+/// ```
+pub fn is_expired(item: &Item, attribute: &str) -> bool {
+    item.get(attribute)
+        .and_then(expiration)
+        .is_some_and(|time| time <= SystemTime::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expires_at_returns_epoch_seconds() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(expires_at(time), AttributeValue::N("1700000000".into()));
+    }
+
+    #[test]
+    fn expires_at_returns_zero_before_epoch() {
+        let time = UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(expires_at(time), AttributeValue::N("0".into()));
+    }
+
+    #[test]
+    fn expiration_returns_time_from_number() {
+        let value = AttributeValue::N("1700000000".into());
+        assert_eq!(
+            expiration(&value),
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+
+        let value = AttributeValue::S("1700000000".into());
+        assert!(expiration(&value).is_none());
+
+        let value = AttributeValue::N("1.5".into());
+        assert!(expiration(&value).is_none());
+    }
+
+    #[test]
+    fn is_expired_checks_ttl_attribute() {
+        let past = expires_at(SystemTime::now() - Duration::from_secs(10));
+        let future = expires_in(Duration::from_secs(10));
+
+        let item: Item = [("ttl".to_string(), past)].into();
+        assert!(is_expired(&item, "ttl"));
+        assert!(!is_expired(&item, "other"));
+
+        let item: Item = [("ttl".to_string(), future)].into();
+        assert!(!is_expired(&item, "ttl"));
+
+        let item: Item = [("ttl".to_string(), AttributeValue::S("1".into()))].into();
+        assert!(!is_expired(&item, "ttl"));
+    }
+}
@@ -0,0 +1,545 @@
+//! Hooks around every request sent through a backend.
+//!
+//! [`InterceptedBackend`] wraps a backend and passes every request of the operations through
+//! its [`Interceptor`]s, so cross-cutting concerns like scoping keys by tenant, auditing or
+//! metrics can be implemented once instead of at every call site.
+//!
+//! ```
+//! use dynamo_mapper::{
+//!     interceptor::{InterceptedBackend, Interceptor, Request},
+//!     memory::MemoryBackend,
+//!     BoxError,
+//! };
+//! use aws_sdk_dynamodb::types::ReturnConsumedCapacity;
+//! use std::mem;
+//!
+//! /// Ask DynamoDB to return the consumed capacity of every single item request.
+//! struct Capacity;
+//!
+//! impl Interceptor for Capacity {
+//!     fn before_request(&self, request: &mut Request<'_>) -> Result<(), BoxError> {
+//!         if let Request::GetItem(input) = request {
+//!             **input = mem::take(*input).return_consumed_capacity(ReturnConsumedCapacity::Total);
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let backend = InterceptedBackend::new(MemoryBackend::new()).with(Capacity);
+//! ```
+use crate::{retry::RetryPolicy, BoxError, DynamoBackend, Item, TableNameResolver};
+
+use aws_sdk_dynamodb::{
+    operation::{
+        batch_get_item::{builders::BatchGetItemInputBuilder, BatchGetItemOutput},
+        batch_write_item::{builders::BatchWriteItemInputBuilder, BatchWriteItemOutput},
+        delete_item::{builders::DeleteItemInputBuilder, DeleteItemOutput},
+        get_item::{builders::GetItemInputBuilder, GetItemOutput},
+        put_item::{builders::PutItemInputBuilder, PutItemOutput},
+        query::{builders::QueryInputBuilder, QueryOutput},
+        scan::{builders::ScanInputBuilder, ScanOutput},
+        transact_get_items::{builders::TransactGetItemsInputBuilder, TransactGetItemsOutput},
+        transact_write_items::{
+            builders::TransactWriteItemsInputBuilder, TransactWriteItemsOutput,
+        },
+        update_item::{builders::UpdateItemInputBuilder, UpdateItemOutput},
+        update_time_to_live::{builders::UpdateTimeToLiveInputBuilder, UpdateTimeToLiveOutput},
+    },
+    types::ConsumedCapacity,
+};
+use std::{any::Any, fmt, sync::Arc};
+
+/// The DynamoDB operations sent through a backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    GetItem,
+    PutItem,
+    UpdateItem,
+    DeleteItem,
+    Query,
+    Scan,
+    BatchGetItem,
+    BatchWriteItem,
+    TransactGetItems,
+    TransactWriteItems,
+    UpdateTimeToLive,
+}
+
+impl Operation {
+    /// Return the DynamoDB API name of the operation like `GetItem`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::GetItem => "GetItem",
+            Self::PutItem => "PutItem",
+            Self::UpdateItem => "UpdateItem",
+            Self::DeleteItem => "DeleteItem",
+            Self::Query => "Query",
+            Self::Scan => "Scan",
+            Self::BatchGetItem => "BatchGetItem",
+            Self::BatchWriteItem => "BatchWriteItem",
+            Self::TransactGetItems => "TransactGetItems",
+            Self::TransactWriteItems => "TransactWriteItems",
+            Self::UpdateTimeToLive => "UpdateTimeToLive",
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A request about to be sent, which the interceptors can modify.
+///
+/// The setters of the input builders take the builder by value, so replace it with
+/// [`std::mem::take`] like `**input = mem::take(*input).limit(10)`.
+#[derive(Debug)]
+pub enum Request<'a> {
+    GetItem(&'a mut GetItemInputBuilder),
+    PutItem(&'a mut PutItemInputBuilder),
+    UpdateItem(&'a mut UpdateItemInputBuilder),
+    DeleteItem(&'a mut DeleteItemInputBuilder),
+    Query(&'a mut QueryInputBuilder),
+    Scan(&'a mut ScanInputBuilder),
+    BatchGetItem(&'a mut BatchGetItemInputBuilder),
+    BatchWriteItem(&'a mut BatchWriteItemInputBuilder),
+    TransactGetItems(&'a mut TransactGetItemsInputBuilder),
+    TransactWriteItems(&'a mut TransactWriteItemsInputBuilder),
+    UpdateTimeToLive(&'a mut UpdateTimeToLiveInputBuilder),
+}
+
+/// A successful response of a request.
+#[derive(Debug)]
+pub enum Response<'a> {
+    GetItem(&'a GetItemOutput),
+    PutItem(&'a PutItemOutput),
+    UpdateItem(&'a UpdateItemOutput),
+    DeleteItem(&'a DeleteItemOutput),
+    Query(&'a QueryOutput),
+    Scan(&'a ScanOutput),
+    BatchGetItem(&'a BatchGetItemOutput),
+    BatchWriteItem(&'a BatchWriteItemOutput),
+    TransactGetItems(&'a TransactGetItemsOutput),
+    TransactWriteItems(&'a TransactWriteItemsOutput),
+    UpdateTimeToLive(&'a UpdateTimeToLiveOutput),
+}
+
+macro_rules! impl_operation {
+    ($($ty:ident),*) => {
+        $(
+            impl $ty<'_> {
+                /// Return the operation.
+                pub fn operation(&self) -> Operation {
+                    match self {
+                        Self::GetItem(_) => Operation::GetItem,
+                        Self::PutItem(_) => Operation::PutItem,
+                        Self::UpdateItem(_) => Operation::UpdateItem,
+                        Self::DeleteItem(_) => Operation::DeleteItem,
+                        Self::Query(_) => Operation::Query,
+                        Self::Scan(_) => Operation::Scan,
+                        Self::BatchGetItem(_) => Operation::BatchGetItem,
+                        Self::BatchWriteItem(_) => Operation::BatchWriteItem,
+                        Self::TransactGetItems(_) => Operation::TransactGetItems,
+                        Self::TransactWriteItems(_) => Operation::TransactWriteItems,
+                        Self::UpdateTimeToLive(_) => Operation::UpdateTimeToLive,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_operation!(Request, Response);
+
+impl Response<'_> {
+    /// Return the consumed capacities in the response. This is empty unless the request sets
+    /// `ReturnConsumedCapacity`.
+    pub fn consumed_capacity(&self) -> Vec<&ConsumedCapacity> {
+        match self {
+            Self::GetItem(output) => output.consumed_capacity().into_iter().collect(),
+            Self::PutItem(output) => output.consumed_capacity().into_iter().collect(),
+            Self::UpdateItem(output) => output.consumed_capacity().into_iter().collect(),
+            Self::DeleteItem(output) => output.consumed_capacity().into_iter().collect(),
+            Self::Query(output) => output.consumed_capacity().into_iter().collect(),
+            Self::Scan(output) => output.consumed_capacity().into_iter().collect(),
+            Self::BatchGetItem(output) => output.consumed_capacity().iter().collect(),
+            Self::BatchWriteItem(output) => output.consumed_capacity().iter().collect(),
+            Self::TransactGetItems(output) => output.consumed_capacity().iter().collect(),
+            Self::TransactWriteItems(output) => output.consumed_capacity().iter().collect(),
+            Self::UpdateTimeToLive(_) => vec![],
+        }
+    }
+
+    /// Return the items in the response before they are converted into the objects: the got
+    /// or queried items, and the attributes returned by the write requests.
+    pub fn items(&self) -> Vec<&Item> {
+        match self {
+            Self::GetItem(output) => output.item().into_iter().collect(),
+            Self::PutItem(output) => output.attributes().into_iter().collect(),
+            Self::UpdateItem(output) => output.attributes().into_iter().collect(),
+            Self::DeleteItem(output) => output.attributes().into_iter().collect(),
+            Self::Query(output) => output.items().iter().collect(),
+            Self::Scan(output) => output.items().iter().collect(),
+            Self::BatchGetItem(output) => output
+                .responses()
+                .into_iter()
+                .flat_map(|responses| responses.values().flatten())
+                .collect(),
+            Self::TransactGetItems(output) => output
+                .responses()
+                .iter()
+                .filter_map(|response| response.item())
+                .collect(),
+            Self::BatchWriteItem(_) | Self::TransactWriteItems(_) | Self::UpdateTimeToLive(_) => {
+                vec![]
+            }
+        }
+    }
+}
+
+/// The objects an operation decoded from the items of a response.
+///
+/// Downcast them into the type of the operation with [`Decoded::objects`].
+#[derive(Debug)]
+pub struct Decoded<'a> {
+    operation: Operation,
+    objects: Vec<&'a dyn Any>,
+}
+
+impl<'a> Decoded<'a> {
+    pub(crate) fn new<T: Any>(
+        operation: Operation,
+        objects: impl IntoIterator<Item = &'a T>,
+    ) -> Self {
+        Self {
+            operation,
+            objects: objects
+                .into_iter()
+                .map(|object| object as &dyn Any)
+                .collect(),
+        }
+    }
+
+    /// Return the operation.
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    /// Return the objects of the type. This is empty if the operation decoded the items into
+    /// another type.
+    pub fn objects<T: Any>(&self) -> impl Iterator<Item = &'a T> + '_ {
+        self.objects
+            .iter()
+            .filter_map(|object| object.downcast_ref::<T>())
+    }
+}
+
+/// Hooks called by [`InterceptedBackend`] around every request.
+///
+/// All the hooks do nothing by default.
+pub trait Interceptor: Send + Sync {
+    /// Called before the request is sent. The interceptor can modify the request, for example
+    /// to add a condition, and returning an error cancels the request with the error.
+    fn before_request(&self, _request: &mut Request<'_>) -> Result<(), BoxError> {
+        Ok(())
+    }
+
+    /// Called after the request succeeded, before the operation converts the items into the
+    /// objects.
+    fn after_response(&self, _response: &Response<'_>) {}
+
+    /// Called after the operation converted the items of the response into the objects.
+    ///
+    /// The requests sent directly to the backend, not by the operations, skip this hook.
+    fn after_decode(&self, _decoded: &Decoded<'_>) {}
+
+    /// Called after the request failed with the error of the backend.
+    fn on_error(&self, _operation: Operation, _err: &BoxError) {}
+}
+
+/// A [`DynamoBackend`] which passes every request through the interceptors.
+///
+/// [`Interceptor::before_request`] is called in the order the interceptors are added, and
+/// the others in the reverse order, so the first interceptor sees the request first and the
+/// response last.
+#[derive(Clone)]
+pub struct InterceptedBackend<B> {
+    inner: B,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+}
+
+impl<B: fmt::Debug> fmt::Debug for InterceptedBackend<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InterceptedBackend")
+            .field("inner", &self.inner)
+            .field("interceptors", &self.interceptors.len())
+            .finish()
+    }
+}
+
+impl<B: DynamoBackend> InterceptedBackend<B> {
+    /// Wrap the backend without interceptors.
+    pub fn new(inner: B) -> Self {
+        Self {
+            inner,
+            interceptors: vec![],
+        }
+    }
+
+    /// Add the interceptor to the end of the chain.
+    pub fn with(self, interceptor: impl Interceptor + 'static) -> Self {
+        self.with_shared(Arc::new(interceptor))
+    }
+
+    /// Add the interceptor shared with other backends to the end of the chain.
+    pub fn with_shared(self, interceptor: Arc<dyn Interceptor>) -> Self {
+        let mut interceptors = self.interceptors;
+        interceptors.push(interceptor);
+        Self {
+            interceptors,
+            ..self
+        }
+    }
+
+    /// Return the inner backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+macro_rules! impl_intercepted_backend {
+    ($($method:ident: $variant:ident($input:ty => $output:ty),)*) => {
+        impl<B: DynamoBackend> DynamoBackend for InterceptedBackend<B> {
+            $(
+                async fn $method(&self, mut input: $input) -> Result<$output, BoxError> {
+                    for interceptor in &self.interceptors {
+                        interceptor.before_request(&mut Request::$variant(&mut input))?;
+                    }
+
+                    let result = self.inner.$method(input).await;
+                    for interceptor in self.interceptors.iter().rev() {
+                        match &result {
+                            Ok(output) => interceptor.after_response(&Response::$variant(output)),
+                            Err(err) => interceptor.on_error(Operation::$variant, err),
+                        }
+                    }
+                    result
+                }
+            )*
+
+            fn retry_policy(&self) -> Option<&RetryPolicy> {
+                self.inner.retry_policy()
+            }
+
+            fn table_name_resolver(&self) -> Option<&dyn TableNameResolver> {
+                self.inner.table_name_resolver()
+            }
+
+            fn after_decode(&self, decoded: &Decoded<'_>) {
+                for interceptor in self.interceptors.iter().rev() {
+                    interceptor.after_decode(decoded);
+                }
+                self.inner.after_decode(decoded);
+            }
+        }
+    };
+}
+
+impl_intercepted_backend! {
+    get_item: GetItem(GetItemInputBuilder => GetItemOutput),
+    put_item: PutItem(PutItemInputBuilder => PutItemOutput),
+    update_item: UpdateItem(UpdateItemInputBuilder => UpdateItemOutput),
+    delete_item: DeleteItem(DeleteItemInputBuilder => DeleteItemOutput),
+    query: Query(QueryInputBuilder => QueryOutput),
+    scan: Scan(ScanInputBuilder => ScanOutput),
+    batch_get_item: BatchGetItem(BatchGetItemInputBuilder => BatchGetItemOutput),
+    batch_write_item: BatchWriteItem(BatchWriteItemInputBuilder => BatchWriteItemOutput),
+    transact_get_items: TransactGetItems(TransactGetItemsInputBuilder => TransactGetItemsOutput),
+    transact_write_items: TransactWriteItems(TransactWriteItemsInputBuilder => TransactWriteItemsOutput),
+    update_time_to_live: UpdateTimeToLive(UpdateTimeToLiveInputBuilder => UpdateTimeToLiveOutput),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        memory::MemoryBackend,
+        schema::{KeyAttribute, TableSchema},
+    };
+    use aws_sdk_dynamodb::{
+        operation::{get_item::GetItemInput, put_item::PutItemInput, query::QueryInput},
+        types::{AttributeValue, BillingMode, ScalarAttributeType},
+    };
+    use std::{mem, sync::Mutex};
+
+    fn item(tenant: &str, id: &str) -> Item {
+        Item::from([
+            ("tenant".into(), AttributeValue::S(tenant.into())),
+            ("id".into(), AttributeValue::S(id.into())),
+        ])
+    }
+
+    fn memory_backend() -> MemoryBackend {
+        let schema = TableSchema {
+            table_name: "Documents".into(),
+            partition_key: KeyAttribute::new("tenant", ScalarAttributeType::S),
+            sort_key: Some(KeyAttribute::new("id", ScalarAttributeType::S)),
+            global_secondary_indexes: vec![],
+            local_secondary_indexes: vec![],
+            billing_mode: BillingMode::PayPerRequest,
+            provisioned_throughput: None,
+            stream_specification: None,
+            ttl_attribute: None,
+        };
+        let backend = MemoryBackend::new();
+        backend.create_table(schema).unwrap();
+        backend
+    }
+
+    /// Scope the items and the queries to the tenant.
+    struct Tenant(&'static str);
+
+    impl Interceptor for Tenant {
+        fn before_request(&self, request: &mut Request<'_>) -> Result<(), BoxError> {
+            let tenant = AttributeValue::S(self.0.into());
+            match request {
+                Request::PutItem(input) => {
+                    let mut item = input.get_item().clone().unwrap_or_default();
+                    item.insert("tenant".into(), tenant);
+                    **input = mem::take(*input).set_item(Some(item));
+                }
+                Request::Query(input) => {
+                    let mut values = input
+                        .get_expression_attribute_values()
+                        .clone()
+                        .unwrap_or_default();
+                    values.insert(":tenant".into(), tenant);
+                    **input = mem::take(*input).set_expression_attribute_values(Some(values));
+                }
+                _ => {}
+            }
+            Ok(())
+        }
+    }
+
+    /// Record the hooks called with the name of the interceptor.
+    struct Audit {
+        name: &'static str,
+        logs: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Audit {
+        fn log(&self, message: String) {
+            self.logs
+                .lock()
+                .unwrap()
+                .push(format!("{} {message}", self.name));
+        }
+    }
+
+    impl Interceptor for Audit {
+        fn before_request(&self, request: &mut Request<'_>) -> Result<(), BoxError> {
+            self.log(format!("before {}", request.operation()));
+            Ok(())
+        }
+
+        fn after_response(&self, response: &Response<'_>) {
+            self.log(format!(
+                "after {} {}",
+                response.operation(),
+                response.items().len()
+            ));
+        }
+
+        fn on_error(&self, operation: Operation, _err: &BoxError) {
+            self.log(format!("error {operation}"));
+        }
+    }
+
+    struct Deny;
+
+    impl Interceptor for Deny {
+        fn before_request(&self, request: &mut Request<'_>) -> Result<(), BoxError> {
+            match request {
+                Request::PutItem(_) => Err("writes are denied".into()),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    fn put(tenant: &str, id: &str) -> PutItemInputBuilder {
+        PutItemInput::builder()
+            .table_name("Documents")
+            .set_item(Some(item(tenant, id)))
+            .condition_expression("attribute_not_exists(id)")
+    }
+
+    #[tokio::test]
+    async fn modify_requests_before_sent() {
+        let backend = InterceptedBackend::new(memory_backend()).with(Tenant("acme"));
+        backend.put_item(put("other", "1")).await.unwrap();
+
+        let input = QueryInput::builder()
+            .table_name("Documents")
+            .key_condition_expression("tenant = :tenant");
+        let output = backend.query(input).await.unwrap();
+        assert_eq!(output.items, Some(vec![item("acme", "1")]));
+
+        let items = backend.into_inner().items("Documents").unwrap();
+        assert_eq!(items, vec![item("acme", "1")]);
+    }
+
+    #[tokio::test]
+    async fn call_hooks_in_chain_order() {
+        let logs = Arc::new(Mutex::new(vec![]));
+        let audit = |name| Audit {
+            name,
+            logs: Arc::clone(&logs),
+        };
+        let backend = InterceptedBackend::new(memory_backend())
+            .with(audit("first"))
+            .with(audit("second"));
+
+        backend.put_item(put("acme", "1")).await.unwrap();
+        let input = GetItemInput::builder()
+            .table_name("Documents")
+            .set_key(Some(item("acme", "1")));
+        backend.get_item(input).await.unwrap();
+        backend.put_item(put("acme", "1")).await.unwrap_err();
+
+        assert_eq!(
+            *logs.lock().unwrap(),
+            vec![
+                "first before PutItem",
+                "second before PutItem",
+                "second after PutItem 0",
+                "first after PutItem 0",
+                "first before GetItem",
+                "second before GetItem",
+                "second after GetItem 1",
+                "first after GetItem 1",
+                "first before PutItem",
+                "second before PutItem",
+                "second error PutItem",
+                "first error PutItem",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn cancel_request_with_error() {
+        let logs = Arc::new(Mutex::new(vec![]));
+        let backend = InterceptedBackend::new(memory_backend())
+            .with(Deny)
+            .with(Audit {
+                name: "audit",
+                logs: Arc::clone(&logs),
+            });
+
+        let err = backend.put_item(put("acme", "1")).await.unwrap_err();
+        assert_eq!(err.to_string(), "writes are denied");
+        assert!(logs.lock().unwrap().is_empty());
+        assert!(backend.into_inner().items("Documents").unwrap().is_empty());
+    }
+}
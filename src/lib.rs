@@ -1,19 +1,89 @@
+//! Map your object to DynamoDB table.
+//!
+//! # Tracing
+//!
+//! With the `tracing` feature, each `send` of the operations runs in a span named `dynamodb` at
+//! the INFO level with these fields:
+//!
+//! - `operation`: the DynamoDB API name like `GetItem`.
+//! - `table_name` and `index_name`.
+//! - `key`: the primary key like `customer=tanaka, id=1`, or the partition key for Query.
+//!   The values are replaced with `***` if [`DynamodbTable::REDACT_KEY`] is true, so that only
+//!   the attribute names are recorded.
+//! - `page`: the page number of the Query counting from 1. The pages after the first one are
+//!   recorded only if the operation sets it with
+//!   [`QueryOperation::set_page`](operations::query::QueryOperation::set_page).
+//! - `consumed_capacity`: the capacity units if the request returns them.
+//! - `item_count`: the number of the items DynamoDB returned.
+//! - `latency_ms`: the elapsed time of the operation in milliseconds.
+//! - `error.kind`: the [`ErrorKind`] of the error if the operation fails.
+//!
+//! The span ends with a DEBUG event on success or a WARN event with the error on failure.
+//!
+//! # Metrics
+//!
+//! With the `metrics` feature, the operations request `ReturnConsumedCapacity::Total` unless it
+//! is already set, and emit these metrics through the [`metrics`](https://docs.rs/metrics)
+//! facade, so any recorder like a Prometheus exporter can collect them:
+//!
+//! | Name | Type | Unit | Description |
+//! | ---- | ---- | ---- | ----------- |
+//! | `dynamodb_consumed_read_capacity_units` | histogram | capacity units | read capacity per operation |
+//! | `dynamodb_consumed_write_capacity_units` | histogram | capacity units | write capacity per operation |
+//! | `dynamodb_operation_duration_seconds` | histogram | seconds | latency of the operation |
+//! | `dynamodb_returned_items_total` | counter | items | items DynamoDB returned |
+//! | `dynamodb_throttled_requests_total` | counter | operations | operations failed by throttling |
+//! | `dynamodb_conditional_check_failures_total` | counter | operations | operations failed by the condition |
+//!
+//! All the metrics have the `table`, `entity_type` and `operation` labels. `entity_type` is
+//! [`DynamodbTable::ENTITY_TYPE`], or empty if it is not defined, so the cost of each entity in
+//! a single table can be told apart.
+//!
+//! Only the final outcome of each operation is recorded. The throttled requests which the
+//! [`RetryPolicy`](retry::RetryPolicy) retried successfully are not counted, and the latency
+//! includes the retries.
 use aws_sdk_dynamodb::types::AttributeValue;
 use std::collections::HashMap;
 
 #[macro_use]
 mod macros;
 
+mod backend;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cache;
+#[cfg(feature = "cursor")]
+pub mod cursor;
+mod entity;
 mod error;
+#[cfg(feature = "fixture")]
+pub mod fixture;
 pub mod helpers;
+pub mod interceptor;
+#[cfg(feature = "memory")]
+pub mod memory;
+mod meter;
 pub mod operations;
+pub mod rate_limit;
+pub mod retry;
+pub mod schema;
+#[cfg(feature = "streams")]
+pub mod streams;
 mod table;
+mod table_name;
+mod trace;
 
+pub use backend::*;
+pub use entity::*;
 pub use table::*;
+pub use table_name::*;
 
 /// Common error.
 pub use error::Error;
 
+/// Classification of the common error.
+pub use error::ErrorKind;
+
 /// Type alias of boxed error.
 pub use aws_sdk_dynamodb::error::BoxError;
 
@@ -0,0 +1,817 @@
+//! Evaluation of the parsed expressions against items.
+use super::expression::{
+    Comparator, Condition, Operand, Path, PathElement, SetValue, UpdateAction,
+};
+use crate::Item;
+
+use aws_sdk_dynamodb::types::AttributeValue;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+type EvalResult<T> = Result<T, String>;
+
+/// Significant digits of a number DynamoDB supports.
+const MAX_DIGITS: usize = 38;
+
+/// Range of the exponent of the most significant digit of a number DynamoDB supports.
+const MAX_MAGNITUDE: i64 = 125;
+const MIN_MAGNITUDE: i64 = -130;
+const OUT_OF_RANGE: &str = "Attempting to store a number with magnitude";
+
+/// A decimal number with up to 38 significant digits as DynamoDB supports.
+#[derive(Debug, Clone, Copy)]
+struct Number {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Number {
+    fn parse(value: &str) -> EvalResult<Self> {
+        let invalid = || format!("Invalid number: {value}");
+        let value = value.trim();
+        let (base, exponent) = match value.find(['e', 'E']) {
+            Some(pos) => (
+                &value[..pos],
+                value[pos + 1..].parse::<i32>().map_err(|_| invalid())?,
+            ),
+            None => (value, 0),
+        };
+        let (negative, base) = match base.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, base.strip_prefix('+').unwrap_or(base)),
+        };
+        let (int, frac) = base.split_once('.').unwrap_or((base, ""));
+        let digits = format!("{int}{frac}");
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let significant = digits.trim_start_matches('0').trim_end_matches('0');
+        if significant.is_empty() {
+            return Ok(Self {
+                mantissa: 0,
+                scale: 0,
+            });
+        }
+        if significant.len() > MAX_DIGITS {
+            return Err(format!(
+                "Attempting to store more than {MAX_DIGITS} significant digits in a Number"
+            ));
+        }
+        let trailing_zeros = digits.len() - digits.trim_end_matches('0').len();
+        let scale = frac.len() as i64 - i64::from(exponent) - trailing_zeros as i64;
+        let magnitude = significant.len() as i64 - 1 - scale;
+        if magnitude > MAX_MAGNITUDE {
+            return Err(format!(
+                "Number overflow. {OUT_OF_RANGE} larger than supported range"
+            ));
+        }
+        if magnitude < MIN_MAGNITUDE {
+            return Err(format!(
+                "Number underflow. {OUT_OF_RANGE} smaller than supported range"
+            ));
+        }
+
+        let mut mantissa: i128 = significant.parse().map_err(|_| invalid())?;
+        if negative {
+            mantissa = -mantissa;
+        }
+        if scale < 0 {
+            let factor = 10i128.checked_pow(-scale as u32);
+            mantissa = factor
+                .and_then(|factor| mantissa.checked_mul(factor))
+                .ok_or_else(|| format!("Number too large for the memory backend: {value}"))?;
+        }
+        Ok(Self {
+            mantissa,
+            scale: scale.max(0) as u32,
+        })
+    }
+
+    fn rescale(self, scale: u32) -> Option<i128> {
+        10i128
+            .checked_pow(scale - self.scale)
+            .and_then(|factor| self.mantissa.checked_mul(factor))
+    }
+
+    fn align(self, other: Self) -> Option<(i128, i128, u32)> {
+        let scale = self.scale.max(other.scale);
+        Some((self.rescale(scale)?, other.rescale(scale)?, scale))
+    }
+
+    fn compare(self, other: Self) -> Ordering {
+        match self.align(other) {
+            Some((a, b, _)) => a.cmp(&b),
+            None => self.to_f64().total_cmp(&other.to_f64()),
+        }
+    }
+
+    fn add(self, other: Self) -> EvalResult<Self> {
+        self.align(other)
+            .and_then(|(a, b, scale)| a.checked_add(b).map(|mantissa| Self { mantissa, scale }))
+            .ok_or_else(|| "Number overflow".into())
+    }
+
+    fn negate(self) -> Self {
+        Self {
+            mantissa: -self.mantissa,
+            ..self
+        }
+    }
+
+    fn to_f64(self) -> f64 {
+        self.mantissa as f64 / 10f64.powi(self.scale as i32)
+    }
+}
+
+impl std::fmt::Display for Number {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        if scale == 0 {
+            return write!(f, "{sign}{digits}");
+        }
+
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        let frac = frac.trim_end_matches('0');
+        if frac.is_empty() {
+            write!(f, "{sign}{int}")
+        } else {
+            write!(f, "{sign}{int}.{frac}")
+        }
+    }
+}
+
+fn number(value: &str) -> EvalResult<Number> {
+    Number::parse(value)
+}
+
+/// Compare two scalar values of the same type. Return None if they are not comparable.
+pub(super) fn compare_values(a: &AttributeValue, b: &AttributeValue) -> Option<Ordering> {
+    match (a, b) {
+        (AttributeValue::S(a), AttributeValue::S(b)) => Some(a.as_bytes().cmp(b.as_bytes())),
+        (AttributeValue::N(a), AttributeValue::N(b)) => {
+            Some(number(a).ok()?.compare(number(b).ok()?))
+        }
+        (AttributeValue::B(a), AttributeValue::B(b)) => Some(a.as_ref().cmp(b.as_ref())),
+        _ => None,
+    }
+}
+
+/// Compare two values for equality as DynamoDB does. Numbers are compared by their values
+/// and sets regardless of the order of their elements.
+pub(super) fn values_equal(a: &AttributeValue, b: &AttributeValue) -> bool {
+    fn set_equal<T>(a: &[T], b: &[T], eq: impl Fn(&T, &T) -> bool) -> bool {
+        a.len() == b.len() && a.iter().all(|x| b.iter().any(|y| eq(x, y)))
+    }
+
+    match (a, b) {
+        (AttributeValue::N(_), AttributeValue::N(_)) => {
+            compare_values(a, b) == Some(Ordering::Equal)
+        }
+        (AttributeValue::Ss(a), AttributeValue::Ss(b)) => set_equal(a, b, |x, y| x == y),
+        (AttributeValue::Bs(a), AttributeValue::Bs(b)) => set_equal(a, b, |x, y| x == y),
+        (AttributeValue::Ns(a), AttributeValue::Ns(b)) => set_equal(a, b, |x, y| {
+            values_equal(&AttributeValue::N(x.clone()), &AttributeValue::N(y.clone()))
+        }),
+        (AttributeValue::L(a), AttributeValue::L(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(x, y)| values_equal(x, y))
+        }
+        (AttributeValue::M(a), AttributeValue::M(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(k, x)| b.get(k).map(|y| values_equal(x, y)).unwrap_or(false))
+        }
+        _ => a == b,
+    }
+}
+
+fn type_code(value: &AttributeValue) -> &'static str {
+    match value {
+        AttributeValue::S(_) => "S",
+        AttributeValue::N(_) => "N",
+        AttributeValue::B(_) => "B",
+        AttributeValue::Ss(_) => "SS",
+        AttributeValue::Ns(_) => "NS",
+        AttributeValue::Bs(_) => "BS",
+        AttributeValue::M(_) => "M",
+        AttributeValue::L(_) => "L",
+        AttributeValue::Null(_) => "NULL",
+        AttributeValue::Bool(_) => "BOOL",
+        _ => "UNKNOWN",
+    }
+}
+
+pub(super) fn get_path<'a>(item: &'a Item, path: &Path) -> Option<&'a AttributeValue> {
+    let mut elements = path.0.iter();
+    let mut value = item.get(path.top())?;
+    elements.next();
+    for element in elements {
+        value = match (element, value) {
+            (PathElement::Attribute(name), AttributeValue::M(map)) => map.get(name)?,
+            (PathElement::Index(index), AttributeValue::L(list)) => list.get(*index)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+fn set_path(item: &mut Item, path: &Path, new_value: AttributeValue) -> EvalResult<()> {
+    let invalid = || "The document path provided in the update expression is invalid for update";
+    let (last, parents) = path.0.split_last().expect("a path is not empty");
+    if parents.is_empty() {
+        item.insert(path.top().into(), new_value);
+        return Ok(());
+    }
+
+    let mut value = item.get_mut(path.top()).ok_or_else(invalid)?;
+    for element in &parents[1..] {
+        value = match (element, value) {
+            (PathElement::Attribute(name), AttributeValue::M(map)) => {
+                map.get_mut(name).ok_or_else(invalid)?
+            }
+            (PathElement::Index(index), AttributeValue::L(list)) => {
+                list.get_mut(*index).ok_or_else(invalid)?
+            }
+            _ => return Err(invalid().into()),
+        };
+    }
+
+    match (last, value) {
+        (PathElement::Attribute(name), AttributeValue::M(map)) => {
+            map.insert(name.clone(), new_value);
+        }
+        (PathElement::Index(index), AttributeValue::L(list)) => {
+            if *index < list.len() {
+                list[*index] = new_value;
+            } else {
+                list.push(new_value);
+            }
+        }
+        _ => return Err(invalid().into()),
+    }
+    Ok(())
+}
+
+fn remove_path(item: &mut Item, path: &Path) {
+    let (last, parents) = path.0.split_last().expect("a path is not empty");
+    if parents.is_empty() {
+        item.remove(path.top());
+        return;
+    }
+
+    let Some(mut value) = item.get_mut(path.top()) else {
+        return;
+    };
+    for element in &parents[1..] {
+        let next = match (element, value) {
+            (PathElement::Attribute(name), AttributeValue::M(map)) => map.get_mut(name),
+            (PathElement::Index(index), AttributeValue::L(list)) => list.get_mut(*index),
+            _ => None,
+        };
+        match next {
+            Some(next) => value = next,
+            None => return,
+        }
+    }
+
+    match (last, value) {
+        (PathElement::Attribute(name), AttributeValue::M(map)) => {
+            map.remove(name);
+        }
+        (PathElement::Index(index), AttributeValue::L(list)) if *index < list.len() => {
+            list.remove(*index);
+        }
+        _ => {}
+    }
+}
+
+fn resolve(operand: &Operand, item: &Item) -> EvalResult<Option<AttributeValue>> {
+    match operand {
+        Operand::Path(path) => Ok(get_path(item, path).cloned()),
+        Operand::Value(value) => Ok(Some(value.clone())),
+        Operand::Size(path) => {
+            let size = match get_path(item, path) {
+                None => return Ok(None),
+                Some(AttributeValue::S(s)) => s.len(),
+                Some(AttributeValue::B(b)) => b.as_ref().len(),
+                Some(AttributeValue::Ss(set)) => set.len(),
+                Some(AttributeValue::Ns(set)) => set.len(),
+                Some(AttributeValue::Bs(set)) => set.len(),
+                Some(AttributeValue::L(list)) => list.len(),
+                Some(AttributeValue::M(map)) => map.len(),
+                Some(value) => {
+                    return Err(format!(
+                        "Invalid operand type for size function: {}",
+                        type_code(value)
+                    ))
+                }
+            };
+            Ok(Some(AttributeValue::N(size.to_string())))
+        }
+    }
+}
+
+fn compare(left: &AttributeValue, comparator: Comparator, right: &AttributeValue) -> bool {
+    match comparator {
+        Comparator::Eq => values_equal(left, right),
+        Comparator::Ne => !values_equal(left, right),
+        _ => match compare_values(left, right) {
+            Some(ordering) => match comparator {
+                Comparator::Lt => ordering.is_lt(),
+                Comparator::Le => ordering.is_le(),
+                Comparator::Gt => ordering.is_gt(),
+                Comparator::Ge => ordering.is_ge(),
+                Comparator::Eq | Comparator::Ne => unreachable!(),
+            },
+            None => false,
+        },
+    }
+}
+
+/// Evaluate the condition against the item. An absent item is evaluated as an empty item.
+pub(super) fn evaluate(condition: &Condition, item: &Item) -> EvalResult<bool> {
+    let result = match condition {
+        Condition::Compare(left, comparator, right) => {
+            match (resolve(left, item)?, resolve(right, item)?) {
+                (Some(left), Some(right)) => compare(&left, *comparator, &right),
+                _ => *comparator == Comparator::Ne,
+            }
+        }
+        Condition::Between(operand, from, to) => {
+            match (
+                resolve(operand, item)?,
+                resolve(from, item)?,
+                resolve(to, item)?,
+            ) {
+                (Some(value), Some(from), Some(to)) => {
+                    compare(&value, Comparator::Ge, &from) && compare(&value, Comparator::Le, &to)
+                }
+                _ => false,
+            }
+        }
+        Condition::In(operand, candidates) => match resolve(operand, item)? {
+            Some(value) => {
+                let mut found = false;
+                for candidate in candidates {
+                    if let Some(candidate) = resolve(candidate, item)? {
+                        found |= values_equal(&value, &candidate);
+                    }
+                }
+                found
+            }
+            None => false,
+        },
+        Condition::AttributeExists(path) => get_path(item, path).is_some(),
+        Condition::AttributeNotExists(path) => get_path(item, path).is_none(),
+        Condition::AttributeType(path, operand) => {
+            match (get_path(item, path), resolve(operand, item)?) {
+                (Some(value), Some(AttributeValue::S(code))) => type_code(value) == code,
+                (_, Some(AttributeValue::S(_))) => false,
+                _ => return Err("The type of attribute_type function must be a string".into()),
+            }
+        }
+        Condition::BeginsWith(operand, prefix) => {
+            match (resolve(operand, item)?, resolve(prefix, item)?) {
+                (Some(AttributeValue::S(value)), Some(AttributeValue::S(prefix))) => {
+                    value.starts_with(&prefix)
+                }
+                (Some(AttributeValue::B(value)), Some(AttributeValue::B(prefix))) => {
+                    value.as_ref().starts_with(prefix.as_ref())
+                }
+                _ => false,
+            }
+        }
+        Condition::Contains(operand, element) => {
+            match (resolve(operand, item)?, resolve(element, item)?) {
+                (Some(AttributeValue::S(value)), Some(AttributeValue::S(substr))) => {
+                    value.contains(&substr)
+                }
+                (Some(AttributeValue::Ss(set)), Some(AttributeValue::S(element))) => {
+                    set.contains(&element)
+                }
+                (Some(AttributeValue::Bs(set)), Some(AttributeValue::B(element))) => {
+                    set.contains(&element)
+                }
+                (Some(AttributeValue::Ns(set)), Some(element @ AttributeValue::N(_))) => set
+                    .iter()
+                    .any(|n| values_equal(&AttributeValue::N(n.clone()), &element)),
+                (Some(AttributeValue::L(list)), Some(element)) => {
+                    list.iter().any(|value| values_equal(value, &element))
+                }
+                _ => false,
+            }
+        }
+        Condition::And(left, right) => evaluate(left, item)? && evaluate(right, item)?,
+        Condition::Or(left, right) => evaluate(left, item)? || evaluate(right, item)?,
+        Condition::Not(condition) => !evaluate(condition, item)?,
+    };
+    Ok(result)
+}
+
+fn add_numbers(a: &str, b: &str, subtract: bool) -> EvalResult<AttributeValue> {
+    let b = number(b)?;
+    let b = if subtract { b.negate() } else { b };
+    Ok(AttributeValue::N(number(a)?.add(b)?.to_string()))
+}
+
+fn set_value(value: &SetValue, item: &Item) -> EvalResult<AttributeValue> {
+    let missing =
+        || "The provided expression refers to an attribute that does not exist in the item";
+    match value {
+        SetValue::Operand(operand) => resolve(operand, item)?.ok_or_else(|| missing().into()),
+        SetValue::Plus(left, right) | SetValue::Minus(left, right) => {
+            let subtract = matches!(value, SetValue::Minus(..));
+            match (set_value(left, item)?, set_value(right, item)?) {
+                (AttributeValue::N(a), AttributeValue::N(b)) => add_numbers(&a, &b, subtract),
+                _ => Err("An operand in the update expression has an incorrect data type".into()),
+            }
+        }
+        SetValue::IfNotExists(path, default) => match get_path(item, path) {
+            Some(value) => Ok(value.clone()),
+            None => set_value(default, item),
+        },
+        SetValue::ListAppend(left, right) => {
+            match (set_value(left, item)?, set_value(right, item)?) {
+                (AttributeValue::L(mut left), AttributeValue::L(right)) => {
+                    left.extend(right);
+                    Ok(AttributeValue::L(left))
+                }
+                _ => Err("list_append function requires lists".into()),
+            }
+        }
+    }
+}
+
+fn union<T: Clone + PartialEq>(mut set: Vec<T>, other: Vec<T>) -> Vec<T> {
+    for element in other {
+        if !set.contains(&element) {
+            set.push(element);
+        }
+    }
+    set
+}
+
+fn add_value(
+    current: Option<&AttributeValue>,
+    value: AttributeValue,
+) -> EvalResult<AttributeValue> {
+    let value = match (current, value) {
+        (None, value @ (AttributeValue::N(_) | AttributeValue::Ss(_))) => value,
+        (None, value @ (AttributeValue::Ns(_) | AttributeValue::Bs(_))) => value,
+        (Some(AttributeValue::N(a)), AttributeValue::N(b)) => add_numbers(a, &b, false)?,
+        (Some(AttributeValue::Ss(a)), AttributeValue::Ss(b)) => {
+            AttributeValue::Ss(union(a.clone(), b))
+        }
+        (Some(AttributeValue::Bs(a)), AttributeValue::Bs(b)) => {
+            AttributeValue::Bs(union(a.clone(), b))
+        }
+        (Some(AttributeValue::Ns(a)), AttributeValue::Ns(b)) => {
+            let mut set = a.clone();
+            for n in b {
+                let exists = set.iter().any(|m| {
+                    values_equal(&AttributeValue::N(m.clone()), &AttributeValue::N(n.clone()))
+                });
+                if !exists {
+                    set.push(n);
+                }
+            }
+            AttributeValue::Ns(set)
+        }
+        _ => return Err("An operand in the update expression has an incorrect data type".into()),
+    };
+    Ok(value)
+}
+
+fn delete_value(
+    current: Option<&AttributeValue>,
+    value: AttributeValue,
+) -> EvalResult<Option<AttributeValue>> {
+    let value = match (current, value) {
+        (None, _) => return Ok(None),
+        (Some(AttributeValue::Ss(a)), AttributeValue::Ss(b)) => {
+            AttributeValue::Ss(a.iter().filter(|x| !b.contains(x)).cloned().collect())
+        }
+        (Some(AttributeValue::Bs(a)), AttributeValue::Bs(b)) => {
+            AttributeValue::Bs(a.iter().filter(|x| !b.contains(x)).cloned().collect())
+        }
+        (Some(AttributeValue::Ns(a)), AttributeValue::Ns(b)) => AttributeValue::Ns(
+            a.iter()
+                .filter(|x| {
+                    !b.iter().any(|y| {
+                        values_equal(
+                            &AttributeValue::N((*x).clone()),
+                            &AttributeValue::N(y.clone()),
+                        )
+                    })
+                })
+                .cloned()
+                .collect(),
+        ),
+        _ => return Err("An operand in the update expression has an incorrect data type".into()),
+    };
+    let empty = match &value {
+        AttributeValue::Ss(set) | AttributeValue::Ns(set) => set.is_empty(),
+        AttributeValue::Bs(set) => set.is_empty(),
+        _ => false,
+    };
+    Ok((!empty).then_some(value))
+}
+
+/// Apply the update actions to the item. All the values are evaluated against the item
+/// before the update as DynamoDB does.
+pub(super) fn apply_update(item: &mut Item, actions: &[UpdateAction]) -> EvalResult<()> {
+    let paths: Vec<&Path> = actions.iter().map(UpdateAction::path).collect();
+    for (i, a) in paths.iter().enumerate() {
+        for b in &paths[i + 1..] {
+            if a.0.starts_with(&b.0) || b.0.starts_with(&a.0) {
+                return Err("Two document paths overlap with each other".into());
+            }
+        }
+    }
+
+    let original = item.clone();
+    for action in actions {
+        match action {
+            UpdateAction::Set(path, value) => {
+                let value = set_value(value, &original)?;
+                set_path(item, path, value)?;
+            }
+            UpdateAction::Remove(path) => remove_path(item, path),
+            UpdateAction::Add(path, operand) => {
+                let value = resolve(operand, &original)?.ok_or("ADD requires a value")?;
+                let value = add_value(get_path(&original, path), value)?;
+                set_path(item, path, value)?;
+            }
+            UpdateAction::Delete(path, operand) => {
+                let value = resolve(operand, &original)?.ok_or("DELETE requires a value")?;
+                match delete_value(get_path(&original, path), value)? {
+                    Some(value) => set_path(item, path, value)?,
+                    None => remove_path(item, path),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Return the top level attribute names the update actions touch.
+pub(super) fn updated_attributes(actions: &[UpdateAction]) -> Vec<&str> {
+    actions.iter().map(|action| action.path().top()).collect()
+}
+
+fn nest(elements: &[PathElement], value: AttributeValue) -> AttributeValue {
+    match elements.split_first() {
+        None => value,
+        Some((PathElement::Attribute(name), rest)) => {
+            AttributeValue::M(HashMap::from([(name.clone(), nest(rest, value))]))
+        }
+        Some((PathElement::Index(_), rest)) => AttributeValue::L(vec![nest(rest, value)]),
+    }
+}
+
+fn merge(
+    target: &mut HashMap<String, AttributeValue>,
+    elements: &[PathElement],
+    value: AttributeValue,
+) {
+    let Some((PathElement::Attribute(name), rest)) = elements.split_first() else {
+        return;
+    };
+    match rest.first() {
+        None => {
+            target.insert(name.clone(), value);
+        }
+        Some(PathElement::Attribute(_)) => {
+            let entry = target
+                .entry(name.clone())
+                .or_insert_with(|| AttributeValue::M(HashMap::new()));
+            if let AttributeValue::M(map) = entry {
+                merge(map, rest, value);
+            }
+        }
+        Some(PathElement::Index(_)) => {
+            let entry = target
+                .entry(name.clone())
+                .or_insert_with(|| AttributeValue::L(vec![]));
+            if let AttributeValue::L(list) = entry {
+                list.push(nest(&rest[1..], value));
+            }
+        }
+    }
+}
+
+/// Return the item only with the attributes in the paths.
+pub(super) fn project(item: &Item, paths: &[Path]) -> Item {
+    let mut projected = Item::new();
+    for path in paths {
+        if let Some(value) = get_path(item, path) {
+            merge(&mut projected, &path.0, value.clone());
+        }
+    }
+    projected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::expression::{parse_condition, parse_update, Placeholders};
+    use super::*;
+
+    fn values(pairs: &[(&str, AttributeValue)]) -> HashMap<String, AttributeValue> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    fn check(expr: &str, item: &Item, values: &HashMap<String, AttributeValue>) -> bool {
+        let placeholders = Placeholders {
+            names: None,
+            values: Some(values),
+        };
+        evaluate(&parse_condition(expr, placeholders).unwrap(), item).unwrap()
+    }
+
+    fn update(expr: &str, item: &mut Item, values: &HashMap<String, AttributeValue>) {
+        let placeholders = Placeholders {
+            names: None,
+            values: Some(values),
+        };
+        apply_update(item, &parse_update(expr, placeholders).unwrap()).unwrap();
+    }
+
+    fn person() -> Item {
+        HashMap::from([
+            ("name".to_string(), AttributeValue::S("Tanaka".into())),
+            ("age".to_string(), AttributeValue::N("20".into())),
+            (
+                "tags".to_string(),
+                AttributeValue::Ss(vec!["a".into(), "b".into()]),
+            ),
+            (
+                "address".to_string(),
+                AttributeValue::M(HashMap::from([(
+                    "city".to_string(),
+                    AttributeValue::S("Tokyo".into()),
+                )])),
+            ),
+        ])
+    }
+
+    #[test]
+    fn it_parses_and_formats_numbers() {
+        let cases = [
+            ("1", "1"),
+            ("-1.50", "-1.5"),
+            ("0.05", "0.05"),
+            ("1e3", "1000"),
+            ("2.5E-1", "0.25"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(Number::parse(input).unwrap().to_string(), expected);
+        }
+        assert!(Number::parse("abc").is_err());
+    }
+
+    #[test]
+    fn it_rejects_numbers_out_of_range() {
+        assert_eq!(Number::parse("0e2000000000").unwrap().to_string(), "0");
+        assert_eq!(
+            Number::parse("1e-130").unwrap().to_string(),
+            format!("0.{}1", "0".repeat(129))
+        );
+        assert!(Number::parse("1e-2147483648").is_err());
+        assert!(Number::parse("1e-131").is_err());
+        assert!(Number::parse("1e126").is_err());
+        assert!(Number::parse(&"1".repeat(39)).is_err());
+    }
+
+    #[test]
+    fn it_compares_numbers_by_value() {
+        let a = AttributeValue::N("10".into());
+        let b = AttributeValue::N("9.5".into());
+        assert_eq!(compare_values(&a, &b), Some(Ordering::Greater));
+        assert!(values_equal(
+            &AttributeValue::N("1.0".into()),
+            &AttributeValue::N("1".into())
+        ));
+        assert_eq!(compare_values(&a, &AttributeValue::S("10".into())), None);
+    }
+
+    #[test]
+    fn it_evaluates_conditions() {
+        let item = person();
+        let values = values(&[
+            (":age", AttributeValue::N("18".into())),
+            (":name", AttributeValue::S("Tan".into())),
+            (":tag", AttributeValue::S("b".into())),
+            (":city", AttributeValue::S("Tokyo".into())),
+            (":type", AttributeValue::S("SS".into())),
+            (":two", AttributeValue::N("2".into())),
+        ]);
+
+        assert!(check("age > :age", &item, &values));
+        assert!(!check("age < :age", &item, &values));
+        assert!(check("begins_with(name, :name)", &item, &values));
+        assert!(check("contains(tags, :tag)", &item, &values));
+        assert!(check("address.city = :city", &item, &values));
+        assert!(check("attribute_type(tags, :type)", &item, &values));
+        assert!(check("size(tags) = :two", &item, &values));
+        assert!(!check(
+            "age IN (:two, :age) OR age BETWEEN :age AND :age",
+            &item,
+            &values
+        ));
+        assert!(check(
+            "attribute_not_exists(missing) AND NOT age = :age",
+            &item,
+            &values
+        ));
+        assert!(!check("missing = :age", &item, &values));
+        assert!(check("missing <> :age", &item, &values));
+    }
+
+    #[test]
+    fn it_applies_update_actions() {
+        let mut item = person();
+        let values = values(&[
+            (":one", AttributeValue::N("1".into())),
+            (":c", AttributeValue::Ss(vec!["c".into()])),
+            (":a", AttributeValue::Ss(vec!["a".into()])),
+            (
+                ":list",
+                AttributeValue::L(vec![AttributeValue::S("x".into())]),
+            ),
+            (":osaka", AttributeValue::S("Osaka".into())),
+        ]);
+        update(
+            "SET age = age + :one, visits = if_not_exists(visits, :one), items = list_append(:list, :list), address.city = :osaka REMOVE name ADD tags :c",
+            &mut item,
+            &values,
+        );
+
+        assert_eq!(item.get("age"), Some(&AttributeValue::N("21".into())));
+        assert_eq!(item.get("visits"), Some(&AttributeValue::N("1".into())));
+        assert_eq!(
+            item.get("items"),
+            Some(&AttributeValue::L(vec![
+                AttributeValue::S("x".into()),
+                AttributeValue::S("x".into())
+            ]))
+        );
+        assert_eq!(
+            get_path(
+                &item,
+                &Path(vec![
+                    PathElement::Attribute("address".into()),
+                    PathElement::Attribute("city".into())
+                ])
+            ),
+            Some(&AttributeValue::S("Osaka".into()))
+        );
+        assert_eq!(item.get("name"), None);
+        assert_eq!(
+            item.get("tags"),
+            Some(&AttributeValue::Ss(vec![
+                "a".into(),
+                "b".into(),
+                "c".into()
+            ]))
+        );
+
+        update("DELETE tags :a", &mut item, &values);
+        assert_eq!(
+            item.get("tags"),
+            Some(&AttributeValue::Ss(vec!["b".into(), "c".into()]))
+        );
+    }
+
+    #[test]
+    fn it_rejects_overlapping_paths() {
+        let mut item = person();
+        let values = values(&[(":one", AttributeValue::N("1".into()))]);
+        let placeholders = Placeholders {
+            names: None,
+            values: Some(&values),
+        };
+        let actions = parse_update("SET address.zip = :one REMOVE address", placeholders).unwrap();
+        assert!(apply_update(&mut item, &actions).is_err());
+    }
+
+    #[test]
+    fn it_projects_nested_attributes() {
+        let item = person();
+        let paths = vec![
+            Path::attribute("name"),
+            Path(vec![
+                PathElement::Attribute("address".into()),
+                PathElement::Attribute("city".into()),
+            ]),
+        ];
+        let projected = project(&item, &paths);
+        assert_eq!(projected.len(), 2);
+        assert_eq!(projected.get("address"), item.get("address"));
+    }
+}
@@ -0,0 +1,688 @@
+//! Parser of the DynamoDB expressions: condition, key condition, filter, update and projection.
+//!
+//! Expression attribute names and values are resolved while parsing, so the parsed
+//! expressions don't refer to the request anymore.
+use aws_sdk_dynamodb::types::AttributeValue;
+use std::collections::HashMap;
+
+type ParseResult<T> = Result<T, String>;
+
+/// An element of a document path.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum PathElement {
+    Attribute(String),
+    Index(usize),
+}
+
+/// A document path like `a.b[0].c`.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Path(pub(super) Vec<PathElement>);
+
+impl Path {
+    #[cfg(test)]
+    pub(super) fn attribute(name: impl Into<String>) -> Self {
+        Self(vec![PathElement::Attribute(name.into())])
+    }
+
+    /// Return the top level attribute name of the path.
+    pub(super) fn top(&self) -> &str {
+        match self.0.first() {
+            Some(PathElement::Attribute(name)) => name,
+            _ => unreachable!("a path always starts with an attribute name"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Operand {
+    Path(Path),
+    Value(AttributeValue),
+    Size(Path),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Comparator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Condition {
+    Compare(Operand, Comparator, Operand),
+    Between(Operand, Operand, Operand),
+    In(Operand, Vec<Operand>),
+    AttributeExists(Path),
+    AttributeNotExists(Path),
+    AttributeType(Path, Operand),
+    BeginsWith(Operand, Operand),
+    Contains(Operand, Operand),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+/// The right hand side of a SET action.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum SetValue {
+    Operand(Operand),
+    Plus(Box<SetValue>, Box<SetValue>),
+    Minus(Box<SetValue>, Box<SetValue>),
+    IfNotExists(Path, Box<SetValue>),
+    ListAppend(Box<SetValue>, Box<SetValue>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum UpdateAction {
+    Set(Path, SetValue),
+    Remove(Path),
+    Add(Path, Operand),
+    Delete(Path, Operand),
+}
+
+impl UpdateAction {
+    pub(super) fn path(&self) -> &Path {
+        match self {
+            Self::Set(path, _)
+            | Self::Remove(path)
+            | Self::Add(path, _)
+            | Self::Delete(path, _) => path,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Name(String),
+    Value(String),
+    Number(usize),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Dot,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Plus,
+    Minus,
+}
+
+fn tokenize(expr: &str) -> ParseResult<Vec<Token>> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let read_word = |start: usize| {
+        let mut end = start;
+        while end < chars.len() && is_word(chars[end]) {
+            end += 1;
+        }
+        (chars[start..end].iter().collect::<String>(), end)
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let token = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '#' | ':' => {
+                let (word, end) = read_word(i + 1);
+                if word.is_empty() {
+                    return Err(format!("Invalid token `{c}` at position {i}"));
+                }
+                i = end;
+                let placeholder = format!("{c}{word}");
+                if c == '#' {
+                    Token::Name(placeholder)
+                } else {
+                    Token::Value(placeholder)
+                }
+            }
+            c if c.is_ascii_digit() => {
+                let (word, end) = read_word(i);
+                i = end;
+                Token::Number(
+                    word.parse()
+                        .map_err(|_| format!("Invalid number `{word}`"))?,
+                )
+            }
+            c if is_word(c) => {
+                let (word, end) = read_word(i);
+                i = end;
+                Token::Ident(word)
+            }
+            _ => {
+                let next = chars.get(i + 1).copied();
+                let (token, len) = match (c, next) {
+                    ('<', Some('>')) => (Token::Ne, 2),
+                    ('<', Some('=')) => (Token::Le, 2),
+                    ('>', Some('=')) => (Token::Ge, 2),
+                    ('<', _) => (Token::Lt, 1),
+                    ('>', _) => (Token::Gt, 1),
+                    ('=', _) => (Token::Eq, 1),
+                    ('(', _) => (Token::LParen, 1),
+                    (')', _) => (Token::RParen, 1),
+                    ('[', _) => (Token::LBracket, 1),
+                    (']', _) => (Token::RBracket, 1),
+                    (',', _) => (Token::Comma, 1),
+                    ('.', _) => (Token::Dot, 1),
+                    ('+', _) => (Token::Plus, 1),
+                    ('-', _) => (Token::Minus, 1),
+                    _ => return Err(format!("Invalid token `{c}` at position {i}")),
+                };
+                i += len;
+                token
+            }
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Expression attribute names and values of a request.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Placeholders<'a> {
+    pub(super) names: Option<&'a HashMap<String, String>>,
+    pub(super) values: Option<&'a HashMap<String, AttributeValue>>,
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    placeholders: Placeholders<'a>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(expr: &str, placeholders: Placeholders<'a>) -> ParseResult<Self> {
+        Ok(Self {
+            tokens: tokenize(expr)?,
+            pos: 0,
+            placeholders,
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> ParseResult<()> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            other => Err(format!("Expected {expected:?} but found {other:?}")),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn finish(&self) -> ParseResult<()> {
+        match self.peek() {
+            None => Ok(()),
+            Some(token) => Err(format!("Unexpected token {token:?}")),
+        }
+    }
+
+    fn name(&self, placeholder: &str) -> ParseResult<String> {
+        self.placeholders
+            .names
+            .and_then(|names| names.get(placeholder))
+            .cloned()
+            .ok_or_else(|| format!("An expression attribute name is not defined: {placeholder}"))
+    }
+
+    fn value(&self, placeholder: &str) -> ParseResult<AttributeValue> {
+        self.placeholders
+            .values
+            .and_then(|values| values.get(placeholder))
+            .cloned()
+            .ok_or_else(|| format!("An expression attribute value is not defined: {placeholder}"))
+    }
+
+    fn path(&mut self) -> ParseResult<Path> {
+        let mut elements = vec![PathElement::Attribute(self.path_name()?)];
+        loop {
+            match self.peek() {
+                Some(Token::Dot) => {
+                    self.pos += 1;
+                    elements.push(PathElement::Attribute(self.path_name()?));
+                }
+                Some(Token::LBracket) => {
+                    self.pos += 1;
+                    match self.next() {
+                        Some(Token::Number(index)) => elements.push(PathElement::Index(index)),
+                        other => return Err(format!("Expected a list index but found {other:?}")),
+                    }
+                    self.expect(Token::RBracket)?;
+                }
+                _ => return Ok(Path(elements)),
+            }
+        }
+    }
+
+    fn path_name(&mut self) -> ParseResult<String> {
+        match self.next() {
+            Some(Token::Ident(name)) => Ok(name),
+            Some(Token::Name(placeholder)) => self.name(&placeholder),
+            other => Err(format!("Expected an attribute name but found {other:?}")),
+        }
+    }
+
+    fn is_function(&self) -> bool {
+        matches!(self.peek(), Some(Token::Ident(_))) && self.peek_at(1) == Some(&Token::LParen)
+    }
+
+    fn function_name(&mut self) -> ParseResult<String> {
+        match self.next() {
+            Some(Token::Ident(name)) => {
+                self.expect(Token::LParen)?;
+                Ok(name)
+            }
+            other => Err(format!("Expected a function but found {other:?}")),
+        }
+    }
+
+    fn operand(&mut self) -> ParseResult<Operand> {
+        if self.is_function() {
+            let name = self.function_name()?;
+            if name != "size" {
+                return Err(format!("Invalid function name in an operand: {name}"));
+            }
+            let path = self.path()?;
+            self.expect(Token::RParen)?;
+            return Ok(Operand::Size(path));
+        }
+
+        match self.peek() {
+            Some(Token::Value(placeholder)) => {
+                let value = self.value(placeholder)?;
+                self.pos += 1;
+                Ok(Operand::Value(value))
+            }
+            _ => self.path().map(Operand::Path),
+        }
+    }
+
+    fn condition(&mut self) -> ParseResult<Condition> {
+        let mut left = self.and_condition()?;
+        while self.eat_keyword("OR") {
+            let right = self.and_condition()?;
+            left = Condition::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn and_condition(&mut self) -> ParseResult<Condition> {
+        let mut left = self.not_condition()?;
+        while self.eat_keyword("AND") {
+            let right = self.not_condition()?;
+            left = Condition::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn not_condition(&mut self) -> ParseResult<Condition> {
+        if self.eat_keyword("NOT") {
+            let condition = self.not_condition()?;
+            Ok(Condition::Not(Box::new(condition)))
+        } else {
+            self.primary_condition()
+        }
+    }
+
+    fn primary_condition(&mut self) -> ParseResult<Condition> {
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let condition = self.condition()?;
+            self.expect(Token::RParen)?;
+            return Ok(condition);
+        }
+
+        if self.is_function() && !self.is_keyword("size") {
+            return self.function_condition();
+        }
+
+        let left = self.operand()?;
+
+        if self.eat_keyword("BETWEEN") {
+            let from = self.operand()?;
+            if !self.eat_keyword("AND") {
+                return Err("Expected AND in BETWEEN condition".into());
+            }
+            let to = self.operand()?;
+            return Ok(Condition::Between(left, from, to));
+        }
+
+        if self.eat_keyword("IN") {
+            self.expect(Token::LParen)?;
+            let mut operands = vec![self.operand()?];
+            while self.peek() == Some(&Token::Comma) {
+                self.pos += 1;
+                operands.push(self.operand()?);
+            }
+            self.expect(Token::RParen)?;
+            return Ok(Condition::In(left, operands));
+        }
+
+        let comparator = match self.next() {
+            Some(Token::Eq) => Comparator::Eq,
+            Some(Token::Ne) => Comparator::Ne,
+            Some(Token::Lt) => Comparator::Lt,
+            Some(Token::Le) => Comparator::Le,
+            Some(Token::Gt) => Comparator::Gt,
+            Some(Token::Ge) => Comparator::Ge,
+            other => return Err(format!("Expected a comparator but found {other:?}")),
+        };
+        let right = self.operand()?;
+        Ok(Condition::Compare(left, comparator, right))
+    }
+
+    fn function_condition(&mut self) -> ParseResult<Condition> {
+        let name = self.function_name()?;
+        let condition = match name.as_str() {
+            "attribute_exists" => Condition::AttributeExists(self.path()?),
+            "attribute_not_exists" => Condition::AttributeNotExists(self.path()?),
+            "attribute_type" => {
+                let path = self.path()?;
+                self.expect(Token::Comma)?;
+                Condition::AttributeType(path, self.operand()?)
+            }
+            "begins_with" => {
+                let operand = self.operand()?;
+                self.expect(Token::Comma)?;
+                Condition::BeginsWith(operand, self.operand()?)
+            }
+            "contains" => {
+                let operand = self.operand()?;
+                self.expect(Token::Comma)?;
+                Condition::Contains(operand, self.operand()?)
+            }
+            _ => return Err(format!("Invalid function name: {name}")),
+        };
+        self.expect(Token::RParen)?;
+        Ok(condition)
+    }
+
+    fn update(&mut self) -> ParseResult<Vec<UpdateAction>> {
+        let mut actions = vec![];
+        while self.peek().is_some() {
+            let clause = match self.next() {
+                Some(Token::Ident(word)) => word.to_ascii_uppercase(),
+                other => return Err(format!("Expected an update clause but found {other:?}")),
+            };
+            loop {
+                let path = self.path()?;
+                let action = match clause.as_str() {
+                    "SET" => {
+                        self.expect(Token::Eq)?;
+                        UpdateAction::Set(path, self.set_value()?)
+                    }
+                    "REMOVE" => UpdateAction::Remove(path),
+                    "ADD" => UpdateAction::Add(path, self.operand()?),
+                    "DELETE" => UpdateAction::Delete(path, self.operand()?),
+                    _ => return Err(format!("Invalid update clause: {clause}")),
+                };
+                actions.push(action);
+
+                if self.peek() == Some(&Token::Comma) {
+                    self.pos += 1;
+                } else {
+                    break;
+                }
+            }
+        }
+
+        if actions.is_empty() {
+            Err("The update expression is empty".into())
+        } else {
+            Ok(actions)
+        }
+    }
+
+    fn set_value(&mut self) -> ParseResult<SetValue> {
+        let left = self.set_operand()?;
+        match self.peek() {
+            Some(Token::Plus) => {
+                self.pos += 1;
+                let right = self.set_operand()?;
+                Ok(SetValue::Plus(Box::new(left), Box::new(right)))
+            }
+            Some(Token::Minus) => {
+                self.pos += 1;
+                let right = self.set_operand()?;
+                Ok(SetValue::Minus(Box::new(left), Box::new(right)))
+            }
+            _ => Ok(left),
+        }
+    }
+
+    fn set_operand(&mut self) -> ParseResult<SetValue> {
+        if !self.is_function() {
+            return self.operand().map(SetValue::Operand);
+        }
+
+        let name = self.function_name()?;
+        let value = match name.as_str() {
+            "if_not_exists" => {
+                let path = self.path()?;
+                self.expect(Token::Comma)?;
+                SetValue::IfNotExists(path, Box::new(self.set_operand()?))
+            }
+            "list_append" => {
+                let left = self.set_operand()?;
+                self.expect(Token::Comma)?;
+                let right = self.set_operand()?;
+                SetValue::ListAppend(Box::new(left), Box::new(right))
+            }
+            _ => return Err(format!("Invalid function name in a SET action: {name}")),
+        };
+        self.expect(Token::RParen)?;
+        Ok(value)
+    }
+
+    fn projection(&mut self) -> ParseResult<Vec<Path>> {
+        let mut paths = vec![self.path()?];
+        while self.peek() == Some(&Token::Comma) {
+            self.pos += 1;
+            paths.push(self.path()?);
+        }
+        Ok(paths)
+    }
+}
+
+/// Parse a condition expression, which is also used for key conditions and filters.
+pub(super) fn parse_condition(expr: &str, placeholders: Placeholders) -> ParseResult<Condition> {
+    let mut parser = Parser::new(expr, placeholders)?;
+    let condition = parser.condition()?;
+    parser.finish()?;
+    Ok(condition)
+}
+
+pub(super) fn parse_update(
+    expr: &str,
+    placeholders: Placeholders,
+) -> ParseResult<Vec<UpdateAction>> {
+    let mut parser = Parser::new(expr, placeholders)?;
+    parser.update()
+}
+
+pub(super) fn parse_projection(expr: &str, placeholders: Placeholders) -> ParseResult<Vec<Path>> {
+    let mut parser = Parser::new(expr, placeholders)?;
+    let paths = parser.projection()?;
+    parser.finish()?;
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders<'a>(
+        names: &'a HashMap<String, String>,
+        values: &'a HashMap<String, AttributeValue>,
+    ) -> Placeholders<'a> {
+        Placeholders {
+            names: Some(names),
+            values: Some(values),
+        }
+    }
+
+    fn path(name: &str) -> Path {
+        Path::attribute(name)
+    }
+
+    #[test]
+    fn it_parses_conditions_with_precedence() {
+        let names = HashMap::from([("#PK".to_string(), "pk".to_string())]);
+        let values = HashMap::from([
+            (":PK".to_string(), AttributeValue::S("a".into())),
+            (":from".to_string(), AttributeValue::N("1".into())),
+            (":to".to_string(), AttributeValue::N("2".into())),
+        ]);
+        let condition = parse_condition(
+            "#PK = :PK AND age BETWEEN :from AND :to OR NOT attribute_exists(x.y[1])",
+            placeholders(&names, &values),
+        )
+        .unwrap();
+
+        let expected = Condition::Or(
+            Box::new(Condition::And(
+                Box::new(Condition::Compare(
+                    Operand::Path(path("pk")),
+                    Comparator::Eq,
+                    Operand::Value(AttributeValue::S("a".into())),
+                )),
+                Box::new(Condition::Between(
+                    Operand::Path(path("age")),
+                    Operand::Value(AttributeValue::N("1".into())),
+                    Operand::Value(AttributeValue::N("2".into())),
+                )),
+            )),
+            Box::new(Condition::Not(Box::new(Condition::AttributeExists(Path(
+                vec![
+                    PathElement::Attribute("x".into()),
+                    PathElement::Attribute("y".into()),
+                    PathElement::Index(1),
+                ],
+            ))))),
+        );
+        assert_eq!(condition, expected);
+    }
+
+    #[test]
+    fn it_parses_functions_and_size() {
+        let names = HashMap::new();
+        let values = HashMap::from([(":v".to_string(), AttributeValue::N("3".into()))]);
+        let condition = parse_condition(
+            "(size(tags) > :v) and begins_with(name, :v)",
+            placeholders(&names, &values),
+        )
+        .unwrap();
+
+        let expected = Condition::And(
+            Box::new(Condition::Compare(
+                Operand::Size(path("tags")),
+                Comparator::Gt,
+                Operand::Value(AttributeValue::N("3".into())),
+            )),
+            Box::new(Condition::BeginsWith(
+                Operand::Path(path("name")),
+                Operand::Value(AttributeValue::N("3".into())),
+            )),
+        );
+        assert_eq!(condition, expected);
+    }
+
+    #[test]
+    fn it_rejects_undefined_placeholders() {
+        let names = HashMap::new();
+        let values = HashMap::new();
+        let result = parse_condition("#a = :b", placeholders(&names, &values));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn it_parses_update_expressions() {
+        let names = HashMap::new();
+        let values = HashMap::from([
+            (":one".to_string(), AttributeValue::N("1".into())),
+            (":tags".to_string(), AttributeValue::Ss(vec!["a".into()])),
+        ]);
+        let actions = parse_update(
+            "SET a = a + :one, b = if_not_exists(b, :one) REMOVE c ADD d :one DELETE e :tags",
+            placeholders(&names, &values),
+        )
+        .unwrap();
+
+        let one = || Operand::Value(AttributeValue::N("1".into()));
+        let expected = vec![
+            UpdateAction::Set(
+                path("a"),
+                SetValue::Plus(
+                    Box::new(SetValue::Operand(Operand::Path(path("a")))),
+                    Box::new(SetValue::Operand(one())),
+                ),
+            ),
+            UpdateAction::Set(
+                path("b"),
+                SetValue::IfNotExists(path("b"), Box::new(SetValue::Operand(one()))),
+            ),
+            UpdateAction::Remove(path("c")),
+            UpdateAction::Add(path("d"), one()),
+            UpdateAction::Delete(
+                path("e"),
+                Operand::Value(AttributeValue::Ss(vec!["a".into()])),
+            ),
+        ];
+        assert_eq!(actions, expected);
+    }
+
+    #[test]
+    fn it_parses_projection_expressions() {
+        let names = HashMap::from([("#n".to_string(), "name".to_string())]);
+        let values = HashMap::new();
+        let paths = parse_projection("id, #n, a.b", placeholders(&names, &values)).unwrap();
+        assert_eq!(
+            paths,
+            vec![
+                path("id"),
+                path("name"),
+                Path(vec![
+                    PathElement::Attribute("a".into()),
+                    PathElement::Attribute("b".into()),
+                ]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,661 @@
+//! An in-memory DynamoDB backend for tests with the `memory` feature.
+//!
+//! [`MemoryBackend`] implements [`DynamoBackend`] with an in-process table engine, so you can
+//! test your objects without DynamoDB or DynamoDB Local.
+//!
+//! ```
+//! use dynamo_mapper::{memory::MemoryBackend, schema::Schema};
+//! # use dynamo_mapper::{DynamodbTable, Key};
+//! # use aws_sdk_dynamodb::types::AttributeValue;
+//! # struct Person;
+//! # struct PersonKey;
+//! # impl<'a> Key<'a> for PersonKey {
+//! #     const PARTITION_KEY: &'a str = "pk";
+//! #     const SORT_KEY: Option<&'a str> = None;
+//! #     type PartitionInput = String;
+//! #     type SortInput = ();
+//! #     fn partition_key(input: String) -> AttributeValue { AttributeValue::S(input) }
+//! #     fn sort_key(_: ()) -> Option<AttributeValue> { None }
+//! # }
+//! # impl<'a> DynamodbTable<'a> for Person {
+//! #     const TABLE_NAME: &'a str = "People";
+//! #     type Key = PersonKey;
+//! #     fn key_inputs(&self) -> (String, ()) { unimplemented!() }
+//! # }
+//! # impl<'a> Schema<'a> for Person {}
+//!
+//! let backend = MemoryBackend::new();
+//! backend.create_table(Person::schema()).unwrap();
+//!
+//! // Then pass the backend to the operations instead of the client.
+//! // let person = Person::get_item().set_key(id, ()).send(&backend).await?;
+//! ```
+//!
+//! The engine supports the features this crate uses: the primary keys with sorted sort keys,
+//! the secondary indexes, the condition, key condition, filter, update and projection
+//! expressions, pagination with `Limit` and `LastEvaluatedKey`, and the batch and transaction
+//! operations. The conditional check failures are returned as the same
+//! [`aws_sdk_dynamodb::Error`] variants as DynamoDB returns, and the other invalid requests
+//! are returned as errors whose messages start with `ValidationException`.
+//!
+//! Capacity, item size limits, reserved words and TTL deletion are not emulated.
+mod eval;
+mod expression;
+mod table;
+
+use self::expression::Placeholders;
+use self::table::{validation, Page, ReadOptions, Table, WriteError};
+use crate::{schema::TableSchema, BoxError, DynamoBackend, Item};
+
+use aws_sdk_dynamodb::{
+    operation::{
+        batch_get_item::{builders::BatchGetItemInputBuilder, BatchGetItemOutput},
+        batch_write_item::{builders::BatchWriteItemInputBuilder, BatchWriteItemOutput},
+        delete_item::{builders::DeleteItemInputBuilder, DeleteItemOutput},
+        get_item::{builders::GetItemInputBuilder, GetItemOutput},
+        put_item::{builders::PutItemInputBuilder, PutItemOutput},
+        query::{builders::QueryInputBuilder, QueryOutput},
+        scan::{builders::ScanInputBuilder, ScanOutput},
+        transact_get_items::{builders::TransactGetItemsInputBuilder, TransactGetItemsOutput},
+        transact_write_items::{
+            builders::TransactWriteItemsInputBuilder, TransactWriteItemsOutput,
+        },
+        update_item::{builders::UpdateItemInputBuilder, UpdateItemOutput},
+        update_time_to_live::{builders::UpdateTimeToLiveInputBuilder, UpdateTimeToLiveOutput},
+    },
+    types::{
+        error::{
+            ConditionalCheckFailedException, ResourceInUseException, ResourceNotFoundException,
+            TransactionCanceledException,
+        },
+        CancellationReason, ItemResponse, ReturnValue, ReturnValuesOnConditionCheckFailure, Select,
+    },
+};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+type Tables = HashMap<String, Table>;
+
+/// A [`DynamoBackend`] which stores the items in memory.
+///
+/// Create the tables with [`MemoryBackend::create_table`] before sending requests.
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    tables: Mutex<Tables>,
+}
+
+impl MemoryBackend {
+    /// Create a backend without tables.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a table from the schema. Returns `ResourceInUseException` if it already exists.
+    pub fn create_table(&self, schema: TableSchema) -> Result<(), BoxError> {
+        let mut tables = self.lock();
+        if tables.contains_key(&schema.table_name) {
+            let err = ResourceInUseException::builder()
+                .message(format!("Table already exists: {}", schema.table_name))
+                .build();
+            return Err(Box::new(aws_sdk_dynamodb::Error::ResourceInUseException(
+                err,
+            )));
+        }
+        tables.insert(schema.table_name.clone(), Table::from(schema));
+        Ok(())
+    }
+
+    /// Delete the table with its items.
+    pub fn delete_table(&self, table_name: &str) -> Result<(), BoxError> {
+        self.lock()
+            .remove(table_name)
+            .map(|_| ())
+            .ok_or_else(|| resource_not_found(table_name))
+    }
+
+    /// Return all the items of the table in the order of their primary keys.
+    pub fn items(&self, table_name: &str) -> Result<Vec<Item>, BoxError> {
+        let tables = self.lock();
+        Ok(table(&tables, Some(table_name))?.items().cloned().collect())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Tables> {
+        self.tables.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn resource_not_found(table_name: &str) -> BoxError {
+    let err = ResourceNotFoundException::builder()
+        .message(format!("Requested resource not found: {table_name}"))
+        .build();
+    Box::new(aws_sdk_dynamodb::Error::ResourceNotFoundException(err))
+}
+
+fn table<'a>(tables: &'a Tables, table_name: Option<&str>) -> Result<&'a Table, BoxError> {
+    let table_name = table_name.unwrap_or_default();
+    tables
+        .get(table_name)
+        .ok_or_else(|| resource_not_found(table_name))
+}
+
+fn table_mut<'a>(
+    tables: &'a mut Tables,
+    table_name: Option<&str>,
+) -> Result<&'a mut Table, BoxError> {
+    let table_name = table_name.unwrap_or_default();
+    tables
+        .get_mut(table_name)
+        .ok_or_else(|| resource_not_found(table_name))
+}
+
+/// Convert the write error of a single item request into the DynamoDB error.
+fn write_error(
+    err: WriteError,
+    return_values: Option<&ReturnValuesOnConditionCheckFailure>,
+) -> BoxError {
+    match err {
+        WriteError::ConditionalCheckFailed(item) => {
+            let item = item
+                .filter(|_| return_values == Some(&ReturnValuesOnConditionCheckFailure::AllOld));
+            let err = ConditionalCheckFailedException::builder()
+                .message("The conditional request failed")
+                .set_item(item)
+                .build();
+            Box::new(aws_sdk_dynamodb::Error::ConditionalCheckFailedException(
+                err,
+            ))
+        }
+        WriteError::Other(err) => err,
+    }
+}
+
+fn projection(
+    item: Item,
+    expr: Option<&str>,
+    placeholders: Placeholders,
+) -> Result<Item, BoxError> {
+    match expr {
+        Some(expr) => {
+            let paths = expression::parse_projection(expr, placeholders).map_err(validation)?;
+            Ok(eval::project(&item, &paths))
+        }
+        None => Ok(item),
+    }
+}
+
+fn limit(limit: Option<i32>) -> Result<Option<usize>, BoxError> {
+    match limit {
+        Some(limit) if limit < 1 => Err(validation("Limit must be greater than or equal to 1")),
+        limit => Ok(limit.map(|limit| limit as usize)),
+    }
+}
+
+/// The result of a query or a scan.
+struct ReadOutput {
+    items: Option<Vec<Item>>,
+    count: i32,
+    scanned_count: i32,
+    last_evaluated_key: Option<Item>,
+}
+
+/// Apply the filter, the projection and `Select` to the evaluated items.
+fn read_output(
+    page: Page,
+    filter: Option<&str>,
+    projection_expression: Option<&str>,
+    select: Option<&Select>,
+    placeholders: Placeholders,
+) -> Result<ReadOutput, BoxError> {
+    let scanned_count = page.items.len() as i32;
+    let filter = filter
+        .map(|expr| expression::parse_condition(expr, placeholders).map_err(validation))
+        .transpose()?;
+
+    let mut items = vec![];
+    for item in page.items {
+        if let Some(filter) = &filter {
+            if !eval::evaluate(filter, &item).map_err(validation)? {
+                continue;
+            }
+        }
+        items.push(projection(item, projection_expression, placeholders)?);
+    }
+
+    Ok(ReadOutput {
+        count: items.len() as i32,
+        items: (select != Some(&Select::Count)).then_some(items),
+        scanned_count,
+        last_evaluated_key: page.last_evaluated_key,
+    })
+}
+
+impl DynamoBackend for MemoryBackend {
+    async fn get_item(&self, input: GetItemInputBuilder) -> Result<GetItemOutput, BoxError> {
+        let input = input.build()?;
+        let placeholders = Placeholders {
+            names: input.expression_attribute_names(),
+            values: None,
+        };
+        let tables = self.lock();
+        let item = table(&tables, input.table_name())?
+            .get(input.key().ok_or_else(|| validation("Key is required"))?)?
+            .cloned()
+            .map(|item| projection(item, input.projection_expression(), placeholders))
+            .transpose()?;
+        Ok(GetItemOutput::builder().set_item(item).build())
+    }
+
+    async fn put_item(&self, input: PutItemInputBuilder) -> Result<PutItemOutput, BoxError> {
+        let input = input.build()?;
+        let placeholders = Placeholders {
+            names: input.expression_attribute_names(),
+            values: input.expression_attribute_values(),
+        };
+        let item = input
+            .item()
+            .cloned()
+            .ok_or_else(|| validation("Item is required"))?;
+
+        let mut tables = self.lock();
+        let old = table_mut(&mut tables, input.table_name())?
+            .put(item, input.condition_expression(), placeholders)
+            .map_err(|err| write_error(err, input.return_values_on_condition_check_failure()))?;
+
+        let attributes = match input.return_values() {
+            Some(ReturnValue::AllOld) => old,
+            None | Some(ReturnValue::None) => None,
+            Some(other) => {
+                return Err(validation(format!(
+                    "ReturnValues can only be ALL_OLD or NONE for PutItem: {other}"
+                )))
+            }
+        };
+        Ok(PutItemOutput::builder().set_attributes(attributes).build())
+    }
+
+    async fn update_item(
+        &self,
+        input: UpdateItemInputBuilder,
+    ) -> Result<UpdateItemOutput, BoxError> {
+        let input = input.build()?;
+        let placeholders = Placeholders {
+            names: input.expression_attribute_names(),
+            values: input.expression_attribute_values(),
+        };
+        let key = input.key().ok_or_else(|| validation("Key is required"))?;
+
+        let mut tables = self.lock();
+        let (old, new, updated) = table_mut(&mut tables, input.table_name())?
+            .update(
+                key,
+                input.update_expression(),
+                input.condition_expression(),
+                placeholders,
+            )
+            .map_err(|err| write_error(err, input.return_values_on_condition_check_failure()))?;
+
+        let updated_attributes = |item: Option<Item>| {
+            item.map(|item| {
+                item.into_iter()
+                    .filter(|(name, _)| updated.contains(name))
+                    .collect::<Item>()
+            })
+            .filter(|item| !item.is_empty())
+        };
+        let attributes = match input.return_values() {
+            None | Some(ReturnValue::None) => None,
+            Some(ReturnValue::AllOld) => old,
+            Some(ReturnValue::AllNew) => Some(new),
+            Some(ReturnValue::UpdatedOld) => updated_attributes(old),
+            Some(ReturnValue::UpdatedNew) => updated_attributes(Some(new)),
+            Some(other) => return Err(validation(format!("Unknown ReturnValues: {other}"))),
+        };
+        Ok(UpdateItemOutput::builder()
+            .set_attributes(attributes)
+            .build())
+    }
+
+    async fn delete_item(
+        &self,
+        input: DeleteItemInputBuilder,
+    ) -> Result<DeleteItemOutput, BoxError> {
+        let input = input.build()?;
+        let placeholders = Placeholders {
+            names: input.expression_attribute_names(),
+            values: input.expression_attribute_values(),
+        };
+        let key = input.key().ok_or_else(|| validation("Key is required"))?;
+
+        let mut tables = self.lock();
+        let old = table_mut(&mut tables, input.table_name())?
+            .delete(key, input.condition_expression(), placeholders)
+            .map_err(|err| write_error(err, input.return_values_on_condition_check_failure()))?;
+
+        let attributes = match input.return_values() {
+            Some(ReturnValue::AllOld) => old,
+            None | Some(ReturnValue::None) => None,
+            Some(other) => {
+                return Err(validation(format!(
+                    "ReturnValues can only be ALL_OLD or NONE for DeleteItem: {other}"
+                )))
+            }
+        };
+        Ok(DeleteItemOutput::builder()
+            .set_attributes(attributes)
+            .build())
+    }
+
+    async fn query(&self, input: QueryInputBuilder) -> Result<QueryOutput, BoxError> {
+        let input = input.build()?;
+        let placeholders = Placeholders {
+            names: input.expression_attribute_names(),
+            values: input.expression_attribute_values(),
+        };
+        let options = ReadOptions {
+            index_name: input.index_name(),
+            key_condition: input.key_condition_expression(),
+            consistent_read: input.consistent_read().unwrap_or(false),
+            scan_index_forward: input.scan_index_forward().unwrap_or(true),
+            exclusive_start_key: input.exclusive_start_key(),
+            limit: limit(input.limit())?,
+        };
+
+        let tables = self.lock();
+        let page = table(&tables, input.table_name())?.read(options, placeholders, true)?;
+        let output = read_output(
+            page,
+            input.filter_expression(),
+            input.projection_expression(),
+            input.select(),
+            placeholders,
+        )?;
+
+        Ok(QueryOutput::builder()
+            .set_items(output.items)
+            .count(output.count)
+            .scanned_count(output.scanned_count)
+            .set_last_evaluated_key(output.last_evaluated_key)
+            .build())
+    }
+
+    async fn scan(&self, input: ScanInputBuilder) -> Result<ScanOutput, BoxError> {
+        let input = input.build()?;
+        let placeholders = Placeholders {
+            names: input.expression_attribute_names(),
+            values: input.expression_attribute_values(),
+        };
+        let options = ReadOptions {
+            index_name: input.index_name(),
+            key_condition: None,
+            consistent_read: input.consistent_read().unwrap_or(false),
+            scan_index_forward: true,
+            exclusive_start_key: input.exclusive_start_key(),
+            limit: limit(input.limit())?,
+        };
+
+        let tables = self.lock();
+        let page = table(&tables, input.table_name())?.read(options, placeholders, false)?;
+        let output = read_output(
+            page,
+            input.filter_expression(),
+            input.projection_expression(),
+            input.select(),
+            placeholders,
+        )?;
+
+        Ok(ScanOutput::builder()
+            .set_items(output.items)
+            .count(output.count)
+            .scanned_count(output.scanned_count)
+            .set_last_evaluated_key(output.last_evaluated_key)
+            .build())
+    }
+
+    async fn batch_get_item(
+        &self,
+        input: BatchGetItemInputBuilder,
+    ) -> Result<BatchGetItemOutput, BoxError> {
+        let input = input.build()?;
+        let tables = self.lock();
+
+        let mut responses = HashMap::new();
+        for (table_name, request) in input.request_items().into_iter().flatten() {
+            let table = table(&tables, Some(table_name))?;
+            let placeholders = Placeholders {
+                names: request.expression_attribute_names(),
+                values: None,
+            };
+            let mut items = vec![];
+            for key in request.keys() {
+                if let Some(item) = table.get(key)? {
+                    items.push(projection(
+                        item.clone(),
+                        request.projection_expression(),
+                        placeholders,
+                    )?);
+                }
+            }
+            responses.insert(table_name.clone(), items);
+        }
+
+        Ok(BatchGetItemOutput::builder()
+            .set_responses(Some(responses))
+            .set_unprocessed_keys(Some(HashMap::new()))
+            .build())
+    }
+
+    async fn batch_write_item(
+        &self,
+        input: BatchWriteItemInputBuilder,
+    ) -> Result<BatchWriteItemOutput, BoxError> {
+        let input = input.build()?;
+        let placeholders = Placeholders {
+            names: None,
+            values: None,
+        };
+
+        let mut tables = self.lock();
+        // Apply the requests to a copy so that an invalid request doesn't leave partial writes.
+        let mut staged = tables.clone();
+        for (table_name, requests) in input.request_items().into_iter().flatten() {
+            let table = table_mut(&mut staged, Some(table_name))?;
+            for request in requests {
+                let result = match (request.put_request(), request.delete_request()) {
+                    (Some(put), None) => table.put(put.item().clone(), None, placeholders),
+                    (None, Some(delete)) => table.delete(delete.key(), None, placeholders),
+                    _ => Err(
+                        validation("Either PutRequest or DeleteRequest must be specified").into(),
+                    ),
+                };
+                result.map_err(|err| write_error(err, None))?;
+            }
+        }
+        *tables = staged;
+
+        Ok(BatchWriteItemOutput::builder()
+            .set_unprocessed_items(Some(HashMap::new()))
+            .build())
+    }
+
+    async fn transact_get_items(
+        &self,
+        input: TransactGetItemsInputBuilder,
+    ) -> Result<TransactGetItemsOutput, BoxError> {
+        let input = input.build()?;
+        let tables = self.lock();
+
+        let mut responses = vec![];
+        for item in input.transact_items() {
+            let get = item
+                .get()
+                .ok_or_else(|| validation("Get must be specified"))?;
+            let placeholders = Placeholders {
+                names: get.expression_attribute_names(),
+                values: None,
+            };
+            let item = table(&tables, Some(get.table_name()))?
+                .get(get.key())?
+                .cloned()
+                .map(|item| projection(item, get.projection_expression(), placeholders))
+                .transpose()?;
+            responses.push(ItemResponse::builder().set_item(item).build());
+        }
+
+        Ok(TransactGetItemsOutput::builder()
+            .set_responses(Some(responses))
+            .build())
+    }
+
+    async fn transact_write_items(
+        &self,
+        input: TransactWriteItemsInputBuilder,
+    ) -> Result<TransactWriteItemsOutput, BoxError> {
+        let input = input.build()?;
+        let mut tables = self.lock();
+
+        // Every action is applied to a copy and its condition is evaluated against the state
+        // before the transaction, because an item can be the target of only one action.
+        let mut staged = tables.clone();
+        let mut targets: Vec<(&str, Item)> = vec![];
+        let mut reasons = vec![];
+        let mut canceled = false;
+
+        for item in input.transact_items() {
+            let (table_name, key, return_values, result) =
+                match (
+                    item.put(),
+                    item.update(),
+                    item.delete(),
+                    item.condition_check(),
+                ) {
+                    (Some(put), None, None, None) => {
+                        let placeholders = Placeholders {
+                            names: put.expression_attribute_names(),
+                            values: put.expression_attribute_values(),
+                        };
+                        let table = table_mut(&mut staged, Some(put.table_name()))?;
+                        let key = table.key_of(put.item());
+                        let result = table
+                            .put(put.item().clone(), put.condition_expression(), placeholders)
+                            .map(|_| ());
+                        (
+                            put.table_name(),
+                            key,
+                            put.return_values_on_condition_check_failure(),
+                            result,
+                        )
+                    }
+                    (None, Some(update), None, None) => {
+                        let placeholders = Placeholders {
+                            names: update.expression_attribute_names(),
+                            values: update.expression_attribute_values(),
+                        };
+                        let result = table_mut(&mut staged, Some(update.table_name()))?
+                            .update(
+                                update.key(),
+                                Some(update.update_expression()),
+                                update.condition_expression(),
+                                placeholders,
+                            )
+                            .map(|_| ());
+                        (
+                            update.table_name(),
+                            update.key().clone(),
+                            update.return_values_on_condition_check_failure(),
+                            result,
+                        )
+                    }
+                    (None, None, Some(delete), None) => {
+                        let placeholders = Placeholders {
+                            names: delete.expression_attribute_names(),
+                            values: delete.expression_attribute_values(),
+                        };
+                        let result = table_mut(&mut staged, Some(delete.table_name()))?
+                            .delete(delete.key(), delete.condition_expression(), placeholders)
+                            .map(|_| ());
+                        (
+                            delete.table_name(),
+                            delete.key().clone(),
+                            delete.return_values_on_condition_check_failure(),
+                            result,
+                        )
+                    }
+                    (None, None, None, Some(check)) => {
+                        let placeholders = Placeholders {
+                            names: check.expression_attribute_names(),
+                            values: check.expression_attribute_values(),
+                        };
+                        let result = table(&staged, Some(check.table_name()))?.condition_check(
+                            check.key(),
+                            check.condition_expression(),
+                            placeholders,
+                        );
+                        (
+                            check.table_name(),
+                            check.key().clone(),
+                            check.return_values_on_condition_check_failure(),
+                            result,
+                        )
+                    }
+                    _ => return Err(validation(
+                        "Exactly one of Put, Update, Delete or ConditionCheck must be specified",
+                    )),
+                };
+
+            if targets.contains(&(table_name, key.clone())) {
+                return Err(validation(
+                    "Transaction request cannot include multiple operations on one item",
+                ));
+            }
+            targets.push((table_name, key));
+
+            let reason = match result {
+                Ok(()) => CancellationReason::builder().code("None").build(),
+                Err(WriteError::ConditionalCheckFailed(item)) => {
+                    canceled = true;
+                    CancellationReason::builder()
+                        .code("ConditionalCheckFailed")
+                        .message("The conditional request failed")
+                        .set_item(item.filter(|_| {
+                            return_values == Some(&ReturnValuesOnConditionCheckFailure::AllOld)
+                        }))
+                        .build()
+                }
+                Err(WriteError::Other(err)) => return Err(err),
+            };
+            reasons.push(reason);
+        }
+
+        if canceled {
+            let err = TransactionCanceledException::builder()
+                .message(
+                    "Transaction cancelled, please refer cancellation reasons for specific reasons",
+                )
+                .set_cancellation_reasons(Some(reasons))
+                .build();
+            return Err(Box::new(
+                aws_sdk_dynamodb::Error::TransactionCanceledException(err),
+            ));
+        }
+
+        *tables = staged;
+        Ok(TransactWriteItemsOutput::builder().build())
+    }
+
+    async fn update_time_to_live(
+        &self,
+        input: UpdateTimeToLiveInputBuilder,
+    ) -> Result<UpdateTimeToLiveOutput, BoxError> {
+        let input = input.build()?;
+        let spec = input
+            .time_to_live_specification()
+            .ok_or_else(|| validation("TimeToLiveSpecification is required"))?;
+
+        let tables = self.lock();
+        table(&tables, input.table_name())?;
+
+        Ok(UpdateTimeToLiveOutput::builder()
+            .time_to_live_specification(spec.clone())
+            .build())
+    }
+}
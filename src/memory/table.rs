@@ -0,0 +1,450 @@
+use super::eval::{self, compare_values};
+use super::expression::{self, Comparator, Condition, Operand, Placeholders};
+use crate::{
+    schema::{IndexSchema, KeyAttribute, TableSchema},
+    BoxError, Item,
+};
+
+use aws_sdk_dynamodb::types::{AttributeValue, ProjectionType, ScalarAttributeType};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+/// A key attribute value ordered as DynamoDB sorts the sort keys.
+#[derive(Debug, Clone)]
+pub(super) struct KeyValue(AttributeValue);
+
+impl Ord for KeyValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        fn rank(value: &AttributeValue) -> u8 {
+            match value {
+                AttributeValue::S(_) => 0,
+                AttributeValue::N(_) => 1,
+                _ => 2,
+            }
+        }
+        compare_values(&self.0, &other.0).unwrap_or_else(|| rank(&self.0).cmp(&rank(&other.0)))
+    }
+}
+
+impl PartialOrd for KeyValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for KeyValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for KeyValue {}
+
+fn type_matches(value: &AttributeValue, attribute_type: &ScalarAttributeType) -> bool {
+    match (value, attribute_type) {
+        (AttributeValue::S(s), ScalarAttributeType::S) => !s.is_empty(),
+        (AttributeValue::N(n), ScalarAttributeType::N) => {
+            compare_values(value, value).is_some() && !n.is_empty()
+        }
+        (AttributeValue::B(b), ScalarAttributeType::B) => !b.as_ref().is_empty(),
+        _ => false,
+    }
+}
+
+/// Partition key and sort key of a table or an index.
+#[derive(Debug, Clone)]
+struct KeySchema {
+    partition_key: KeyAttribute,
+    sort_key: Option<KeyAttribute>,
+}
+
+impl KeySchema {
+    fn attributes(&self) -> impl Iterator<Item = &KeyAttribute> {
+        std::iter::once(&self.partition_key).chain(self.sort_key.as_ref())
+    }
+
+    /// Return the key values of the item, or None if any of the key attributes is missing.
+    /// The values of wrong types are rejected.
+    fn values(&self, item: &Item) -> Result<Option<Vec<KeyValue>>, BoxError> {
+        let mut values = vec![];
+        for attribute in self.attributes() {
+            match item.get(&attribute.name) {
+                Some(value) if type_matches(value, &attribute.attribute_type) => {
+                    values.push(KeyValue(value.clone()))
+                }
+                Some(_) => {
+                    return Err(validation(format!(
+                        "Type mismatch for key attribute {}",
+                        attribute.name
+                    )))
+                }
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(values))
+    }
+
+    fn key_of(&self, item: &Item) -> Item {
+        self.attributes()
+            .filter_map(|attribute| {
+                item.get(&attribute.name)
+                    .map(|value| (attribute.name.clone(), value.clone()))
+            })
+            .collect()
+    }
+
+    /// Return true if the condition contains the equality condition of the partition key.
+    fn is_queried_by(&self, condition: &Condition) -> bool {
+        match condition {
+            Condition::Compare(Operand::Path(path), Comparator::Eq, Operand::Value(_))
+            | Condition::Compare(Operand::Value(_), Comparator::Eq, Operand::Path(path)) => {
+                path.0.len() == 1 && path.top() == self.partition_key.name
+            }
+            Condition::And(left, right) => self.is_queried_by(left) || self.is_queried_by(right),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Index {
+    key: KeySchema,
+    global: bool,
+    projection_type: ProjectionType,
+    non_key_attributes: Vec<String>,
+}
+
+impl Index {
+    fn new(schema: IndexSchema, global: bool) -> Self {
+        Self {
+            global,
+            key: KeySchema {
+                partition_key: schema.partition_key,
+                sort_key: schema.sort_key,
+            },
+            projection_type: schema.projection_type,
+            non_key_attributes: schema.non_key_attributes.unwrap_or_default(),
+        }
+    }
+}
+
+/// The failure of a write request.
+#[derive(Debug)]
+pub(super) enum WriteError {
+    /// The condition expression is evaluated as false. This holds the existing item.
+    ConditionalCheckFailed(Option<Item>),
+    Other(BoxError),
+}
+
+impl From<BoxError> for WriteError {
+    fn from(err: BoxError) -> Self {
+        Self::Other(err)
+    }
+}
+
+pub(super) fn validation(message: impl std::fmt::Display) -> BoxError {
+    format!("ValidationException: {message}").into()
+}
+
+/// Parse the expression attributes and evaluate the condition against the item.
+fn check_condition(
+    condition: Option<&str>,
+    placeholders: Placeholders,
+    item: Option<&Item>,
+) -> Result<(), WriteError> {
+    let Some(condition) = condition else {
+        return Ok(());
+    };
+    let condition = expression::parse_condition(condition, placeholders).map_err(validation)?;
+    let empty = Item::new();
+    if eval::evaluate(&condition, item.unwrap_or(&empty)).map_err(validation)? {
+        Ok(())
+    } else {
+        Err(WriteError::ConditionalCheckFailed(item.cloned()))
+    }
+}
+
+/// Items evaluated by a query or a scan.
+#[derive(Debug, Default)]
+pub(super) struct Page {
+    pub(super) items: Vec<Item>,
+    pub(super) last_evaluated_key: Option<Item>,
+}
+
+/// Options of a query or a scan.
+#[derive(Debug, Default)]
+pub(super) struct ReadOptions<'a> {
+    pub(super) index_name: Option<&'a str>,
+    pub(super) key_condition: Option<&'a str>,
+    pub(super) consistent_read: bool,
+    pub(super) scan_index_forward: bool,
+    pub(super) exclusive_start_key: Option<&'a Item>,
+    pub(super) limit: Option<usize>,
+}
+
+/// An in-memory table. The items are stored in the order of their primary keys.
+#[derive(Debug, Clone)]
+pub(super) struct Table {
+    key: KeySchema,
+    indexes: HashMap<String, Index>,
+    items: BTreeMap<Vec<KeyValue>, Item>,
+}
+
+impl From<TableSchema> for Table {
+    fn from(schema: TableSchema) -> Self {
+        let global = schema
+            .global_secondary_indexes
+            .into_iter()
+            .map(|index| (index, true));
+        let local = schema
+            .local_secondary_indexes
+            .into_iter()
+            .map(|index| (index, false));
+        let indexes = global
+            .chain(local)
+            .map(|(index, global)| (index.name.clone(), Index::new(index, global)))
+            .collect();
+
+        Self {
+            key: KeySchema {
+                partition_key: schema.partition_key,
+                sort_key: schema.sort_key,
+            },
+            indexes,
+            items: BTreeMap::new(),
+        }
+    }
+}
+
+impl Table {
+    pub(super) fn items(&self) -> impl Iterator<Item = &Item> {
+        self.items.values()
+    }
+
+    /// Validate the key of a request and return its values.
+    fn primary_key(&self, key: &Item) -> Result<Vec<KeyValue>, BoxError> {
+        let invalid = || validation("The provided key element does not match the schema");
+        if key.len() != self.key.attributes().count() {
+            return Err(invalid());
+        }
+        self.key
+            .values(key)
+            .map_err(|_| invalid())?
+            .ok_or_else(invalid)
+    }
+
+    /// Validate the item to write and return its primary key values.
+    fn validate_item(&self, item: &Item) -> Result<Vec<KeyValue>, BoxError> {
+        for index in self.indexes.values() {
+            // the items lacking the index keys are just not indexed.
+            index.key.values(item)?;
+        }
+        self.key
+            .values(item)?
+            .ok_or_else(|| validation("One of the required keys was not given a value"))
+    }
+
+    pub(super) fn get(&self, key: &Item) -> Result<Option<&Item>, BoxError> {
+        let key = self.primary_key(key)?;
+        Ok(self.items.get(&key))
+    }
+
+    pub(super) fn put(
+        &mut self,
+        item: Item,
+        condition: Option<&str>,
+        placeholders: Placeholders,
+    ) -> Result<Option<Item>, WriteError> {
+        let key = self.validate_item(&item)?;
+        check_condition(condition, placeholders, self.items.get(&key))?;
+        Ok(self.items.insert(key, item))
+    }
+
+    /// Update the item and return the old and the new items with the updated attribute names.
+    pub(super) fn update(
+        &mut self,
+        key: &Item,
+        update: Option<&str>,
+        condition: Option<&str>,
+        placeholders: Placeholders,
+    ) -> Result<(Option<Item>, Item, Vec<String>), WriteError> {
+        let key_values = self.primary_key(key)?;
+        let old = self.items.get(&key_values).cloned();
+        check_condition(condition, placeholders, old.as_ref())?;
+
+        let mut new = old.clone().unwrap_or_else(|| key.clone());
+        let mut updated = vec![];
+        if let Some(update) = update {
+            let actions = expression::parse_update(update, placeholders).map_err(validation)?;
+            eval::apply_update(&mut new, &actions).map_err(validation)?;
+            updated = eval::updated_attributes(&actions)
+                .into_iter()
+                .map(String::from)
+                .collect();
+        }
+
+        if let Some(name) = updated
+            .iter()
+            .find(|name| self.key.attributes().any(|attr| &attr.name == *name))
+        {
+            return Err(validation(format!(
+                "Cannot update attribute {name}. This attribute is part of the key"
+            ))
+            .into());
+        }
+
+        self.validate_item(&new)?;
+        self.items.insert(key_values, new.clone());
+        Ok((old, new, updated))
+    }
+
+    pub(super) fn delete(
+        &mut self,
+        key: &Item,
+        condition: Option<&str>,
+        placeholders: Placeholders,
+    ) -> Result<Option<Item>, WriteError> {
+        let key = self.primary_key(key)?;
+        check_condition(condition, placeholders, self.items.get(&key))?;
+        Ok(self.items.remove(&key))
+    }
+
+    pub(super) fn condition_check(
+        &self,
+        key: &Item,
+        condition: &str,
+        placeholders: Placeholders,
+    ) -> Result<(), WriteError> {
+        let key = self.primary_key(key)?;
+        check_condition(Some(condition), placeholders, self.items.get(&key))
+    }
+
+    /// Return the primary key of the item as an item.
+    pub(super) fn key_of(&self, item: &Item) -> Item {
+        self.key.key_of(item)
+    }
+
+    /// Read the items of the table or the index in the key order. The key condition is
+    /// required for queries and the items are projected as the index defines.
+    pub(super) fn read(
+        &self,
+        options: ReadOptions,
+        placeholders: Placeholders,
+        query: bool,
+    ) -> Result<Page, BoxError> {
+        let index = options
+            .index_name
+            .map(|name| {
+                self.indexes.get(name).ok_or_else(|| {
+                    validation(format!(
+                        "The table does not have the specified index: {name}"
+                    ))
+                })
+            })
+            .transpose()?;
+        if options.consistent_read && index.is_some_and(|index| index.global) {
+            return Err(validation(
+                "Consistent reads are not supported on global secondary indexes",
+            ));
+        }
+        let schemas: Vec<&KeySchema> = index
+            .map(|index| &index.key)
+            .into_iter()
+            .chain(std::iter::once(&self.key))
+            .collect();
+
+        let key_condition = match (query, options.key_condition) {
+            (true, Some(expr)) => {
+                let condition =
+                    expression::parse_condition(expr, placeholders).map_err(validation)?;
+                if !schemas[0].is_queried_by(&condition) {
+                    return Err(validation(format!(
+                        "Query condition missed key schema element: {}",
+                        schemas[0].partition_key.name
+                    )));
+                }
+                Some(condition)
+            }
+            (true, None) => {
+                return Err(validation("Either the KeyConditions or KeyConditionExpression parameter must be specified in the request"))
+            }
+            (false, _) => None,
+        };
+
+        let sort_key = |item: &Item| -> Result<Option<Vec<KeyValue>>, BoxError> {
+            let mut values = vec![];
+            for schema in &schemas {
+                match schema.values(item)? {
+                    Some(v) => values.extend(v),
+                    None => return Ok(None),
+                }
+            }
+            Ok(Some(values))
+        };
+
+        let mut candidates = vec![];
+        for item in self.items.values() {
+            let Some(order) = sort_key(item)? else {
+                continue;
+            };
+            if let Some(condition) = &key_condition {
+                if !eval::evaluate(condition, item).map_err(validation)? {
+                    continue;
+                }
+            }
+            candidates.push((order, item));
+        }
+        candidates.sort_by(|a, b| a.0.cmp(&b.0));
+        if !options.scan_index_forward {
+            candidates.reverse();
+        }
+
+        if let Some(start) = options.exclusive_start_key {
+            let start = sort_key(start)?
+                .ok_or_else(|| validation("The provided starting key is invalid"))?;
+            candidates.retain(|(order, _)| match options.scan_index_forward {
+                true => *order > start,
+                false => *order < start,
+            });
+        }
+
+        let limit = options.limit.unwrap_or(usize::MAX);
+        let last_evaluated_key = (candidates.len() >= limit && limit > 0).then(|| {
+            let (_, item) = candidates[limit - 1];
+            schemas
+                .iter()
+                .flat_map(|schema| schema.key_of(item))
+                .collect::<Item>()
+        });
+        candidates.truncate(limit);
+
+        let items = candidates
+            .into_iter()
+            .map(|(_, item)| match index {
+                Some(index) => self.project_index(index, item),
+                None => item.clone(),
+            })
+            .collect();
+
+        Ok(Page {
+            items,
+            last_evaluated_key,
+        })
+    }
+
+    fn project_index(&self, index: &Index, item: &Item) -> Item {
+        match index.projection_type {
+            ProjectionType::All => item.clone(),
+            _ => {
+                let mut projected = self.key.key_of(item);
+                projected.extend(index.key.key_of(item));
+                for name in &index.non_key_attributes {
+                    if let Some(value) = item.get(name) {
+                        projected.insert(name.clone(), value.clone());
+                    }
+                }
+                projected
+            }
+        }
+    }
+}
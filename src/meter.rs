@@ -0,0 +1,266 @@
+//! Metrics of the operations emitted with the `metrics` feature.
+//!
+//! The metric names and their labels are documented in the [crate docs](crate#metrics).
+use aws_sdk_dynamodb::types::ReturnConsumedCapacity;
+
+#[cfg(feature = "metrics")]
+use crate::{Error, ErrorKind};
+#[cfg(feature = "metrics")]
+use aws_sdk_dynamodb::types::ConsumedCapacity;
+#[cfg(feature = "metrics")]
+use metrics::{counter, histogram, Label};
+#[cfg(feature = "metrics")]
+use std::time::Duration;
+
+/// Return `ReturnConsumedCapacity` for the request which already sets `current`.
+pub(crate) fn return_consumed_capacity(
+    current: &Option<ReturnConsumedCapacity>,
+) -> Option<ReturnConsumedCapacity> {
+    #[cfg(feature = "metrics")]
+    {
+        current.clone().or(Some(ReturnConsumedCapacity::Total))
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    current.clone()
+}
+
+/// The metrics of an operation.
+#[cfg(feature = "metrics")]
+pub(crate) struct OperationMeter {
+    read: bool,
+    labels: Vec<Label>,
+}
+
+#[cfg(feature = "metrics")]
+impl OperationMeter {
+    pub(crate) fn new(operation: &'static str, table_name: Option<&str>) -> Self {
+        Self {
+            read: matches!(operation, "GetItem" | "Query"),
+            labels: vec![
+                Label::new("table", table_name.unwrap_or_default().to_string()),
+                Label::new("entity_type", ""),
+                Label::new("operation", operation),
+            ],
+        }
+    }
+
+    pub(crate) fn set_entity_type(&mut self, entity_type: Option<&str>) {
+        self.labels[1] = Label::new("entity_type", entity_type.unwrap_or_default().to_string());
+    }
+
+    /// Emit the consumed capacity and the number of the items of the response.
+    pub(crate) fn output(&self, consumed_capacity: Option<&ConsumedCapacity>, item_count: usize) {
+        if let Some(capacity) = consumed_capacity {
+            // `CapacityUnits` is the total of the read and write units.
+            let total = capacity.capacity_units();
+            let read = capacity
+                .read_capacity_units()
+                .or(total.filter(|_| self.read));
+            let write = capacity
+                .write_capacity_units()
+                .or(total.filter(|_| !self.read));
+
+            if let Some(units) = read {
+                histogram!("dynamodb_consumed_read_capacity_units", self.labels.clone())
+                    .record(units);
+            }
+            if let Some(units) = write {
+                histogram!(
+                    "dynamodb_consumed_write_capacity_units",
+                    self.labels.clone()
+                )
+                .record(units);
+            }
+        }
+        counter!("dynamodb_returned_items_total", self.labels.clone()).increment(item_count as u64);
+    }
+
+    /// Emit the latency and the error of the operation.
+    pub(crate) fn finish<T>(&self, elapsed: Duration, result: &Result<T, Error>) {
+        histogram!("dynamodb_operation_duration_seconds", self.labels.clone())
+            .record(elapsed.as_secs_f64());
+
+        match result.as_ref().err().map(Error::kind) {
+            Some(ErrorKind::Throttled) => {
+                counter!("dynamodb_throttled_requests_total", self.labels.clone()).increment(1)
+            }
+            Some(ErrorKind::ConditionalCheckFailed) => counter!(
+                "dynamodb_conditional_check_failures_total",
+                self.labels.clone()
+            )
+            .increment(1),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+
+    use aws_sdk_dynamodb::types::error::ThrottlingException;
+    use metrics::{
+        Counter, CounterFn, Gauge, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder,
+        SharedString, Unit,
+    };
+    use std::sync::{Arc, Mutex};
+
+    /// A recorder which keeps the emitted values with the metric names and the labels.
+    #[derive(Clone, Default)]
+    struct Values(Arc<Mutex<Vec<(String, f64)>>>);
+
+    struct Handle {
+        key: String,
+        values: Values,
+    }
+
+    impl Handle {
+        fn push(&self, value: f64) {
+            self.values
+                .0
+                .lock()
+                .unwrap()
+                .push((self.key.clone(), value));
+        }
+    }
+
+    impl CounterFn for Handle {
+        fn increment(&self, value: u64) {
+            self.push(value as f64);
+        }
+
+        fn absolute(&self, value: u64) {
+            self.push(value as f64);
+        }
+    }
+
+    impl HistogramFn for Handle {
+        fn record(&self, value: f64) {
+            self.push(value);
+        }
+    }
+
+    impl Values {
+        fn handle(&self, key: &Key) -> Arc<Handle> {
+            let labels: Vec<String> = key
+                .labels()
+                .map(|label| format!("{}={}", label.key(), label.value()))
+                .collect();
+            Arc::new(Handle {
+                key: format!("{}{{{}}}", key.name(), labels.join(",")),
+                values: self.clone(),
+            })
+        }
+
+        fn get(&self) -> Vec<(String, f64)> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl Recorder for Values {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::from_arc(self.handle(key))
+        }
+
+        fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::from_arc(self.handle(key))
+        }
+    }
+
+    const LABELS: &str = "table=Orders,entity_type=order,operation";
+
+    #[test]
+    fn request_total_consumed_capacity_by_default() {
+        assert_eq!(
+            return_consumed_capacity(&None),
+            Some(ReturnConsumedCapacity::Total)
+        );
+        assert_eq!(
+            return_consumed_capacity(&Some(ReturnConsumedCapacity::Indexes)),
+            Some(ReturnConsumedCapacity::Indexes)
+        );
+    }
+
+    #[test]
+    fn emit_capacity_and_items() {
+        let values = Values::default();
+        metrics::with_local_recorder(&values, || {
+            let mut meter = OperationMeter::new("Query", Some("Orders"));
+            meter.set_entity_type(Some("order"));
+            let capacity = ConsumedCapacity::builder().capacity_units(1.5).build();
+            meter.output(Some(&capacity), 3);
+            meter.finish(Duration::from_millis(20), &Ok(()));
+        });
+
+        assert_eq!(
+            values.get(),
+            vec![
+                (
+                    format!("dynamodb_consumed_read_capacity_units{{{LABELS}=Query}}"),
+                    1.5
+                ),
+                (
+                    format!("dynamodb_returned_items_total{{{LABELS}=Query}}"),
+                    3.0
+                ),
+                (
+                    format!("dynamodb_operation_duration_seconds{{{LABELS}=Query}}"),
+                    0.02
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn emit_throttles() {
+        let values = Values::default();
+        metrics::with_local_recorder(&values, || {
+            let mut meter = OperationMeter::new("PutItem", Some("Orders"));
+            meter.set_entity_type(Some("order"));
+            let capacity = ConsumedCapacity::builder()
+                .capacity_units(2.0)
+                .write_capacity_units(2.0)
+                .build();
+            meter.output(Some(&capacity), 0);
+
+            let err = aws_sdk_dynamodb::Error::ThrottlingException(
+                ThrottlingException::builder().build(),
+            );
+            let result: Result<(), _> = Err(Error::Sdk(Box::new(err)));
+            meter.finish(Duration::from_secs(1), &result);
+        });
+
+        assert_eq!(
+            values.get(),
+            vec![
+                (
+                    format!("dynamodb_consumed_write_capacity_units{{{LABELS}=PutItem}}"),
+                    2.0
+                ),
+                (
+                    format!("dynamodb_returned_items_total{{{LABELS}=PutItem}}"),
+                    0.0
+                ),
+                (
+                    format!("dynamodb_operation_duration_seconds{{{LABELS}=PutItem}}"),
+                    1.0
+                ),
+                (
+                    format!("dynamodb_throttled_requests_total{{{LABELS}=PutItem}}"),
+                    1.0
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use super::{
+    helpers::{
+        attribute_value::opt_n,
+        expression::{
+            condition::Condition,
+            update::{add, if_not_exists, set, Update},
+        },
+    },
+    meter::return_consumed_capacity,
+    resolve_table_name,
+    retry::{self, RetryPolicy},
+    trace::OperationSpan,
+    DynamoBackend, DynamodbTable, Error, Key,
+};
+
+use aws_sdk_dynamodb::{
+    operation::update_item::{builders::UpdateItemInputBuilder, UpdateItemInput},
+    types::{AttributeValue, ReturnValue},
+};
+use std::fmt;
+use std::marker::PhantomData;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+#[cfg(feature = "blocking")]
+use super::blocking::block_on;
+
+const COUNTER_NAME: &str = "#counter";
+const COUNTER_VALUE: &str = ":by";
+const ET_EXP_NAME: &str = "#et";
+const ET_EXP_VALUE: &str = ":et";
+
+type KeyInputs<'a, T> = (
+    <<T as DynamodbTable<'a>>::Key as Key<'a>>::PartitionInput,
+    <<T as DynamodbTable<'a>>::Key as Key<'a>>::SortInput,
+);
+
+/// An integer type of the counter value.
+///
+/// This trait is sealed and implemented only for the primitive integer types, because
+/// DynamoDB rejects NaN and infinity of the floats.
+pub trait CounterValue: fmt::Display + FromStr<Err = ParseIntError> + sealed::Sealed {}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+macro_rules! impl_counter_value {
+    ($($ty:ty),*) => {
+        $(
+            impl sealed::Sealed for $ty {}
+            impl CounterValue for $ty {}
+        )*
+    };
+}
+
+impl_counter_value!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// A trait enables your objects to update the number attributes atomically as counters.
+///
+/// ```
+/// # use aws_sdk_dynamodb::types::AttributeValue;
+/// # use dynamo_mapper::{
+/// #     memory::MemoryBackend, operations::counter::Counter, schema::Schema, DynamodbTable, Key,
+/// # };
+/// struct Shop {
+///     id: String,
+/// }
+/// struct ShopKey;
+///
+/// impl<'a> DynamodbTable<'a> for Shop {
+///     const TABLE_NAME: &'a str = "Shops";
+///     type Key = ShopKey;
+///
+///     fn key_inputs(&self) -> (String, ()) {
+///         (self.id.clone(), ())
+///     }
+/// }
+/// # impl<'a> Key<'a> for ShopKey {
+/// #     const PARTITION_KEY: &'a str = "id";
+/// #     const SORT_KEY: Option<&'a str> = None;
+/// #     type PartitionInput = String;
+/// #     type SortInput = ();
+/// #     fn partition_key(input: String) -> AttributeValue { AttributeValue::S(input) }
+/// #     fn sort_key(_: ()) -> Option<AttributeValue> { None }
+/// # }
+///
+/// impl<'a> Counter<'a> for Shop {}
+/// # impl<'a> Schema<'a> for Shop {}
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), dynamo_mapper::Error> {
+/// # let backend = MemoryBackend::new();
+/// # backend.create_table(Shop::schema()).unwrap();
+/// let visits: u64 = Shop::increment(("shop-1".into(), ()), "visits", 5)
+///     .send(&backend)
+///     .await?;
+/// assert_eq!(visits, 5);
+///
+/// let visits: u64 = Shop::decrement_if_positive(("shop-1".into(), ()), "visits", 2)
+///     .send(&backend)
+///     .await?;
+/// assert_eq!(visits, 3);
+/// # Ok(())
+/// # }
+/// ```
+pub trait Counter<'a>: DynamodbTable<'a> {
+    /// Return [`CounterOperation`] which adds the value to the attribute with `ADD`.
+    ///
+    /// The attribute starts from zero if it doesn't exist. Note that the item is created with
+    /// only the key and the attribute if it doesn't exist either, and with the entity type if
+    /// [`DynamodbTable::ENTITY_TYPE`] is defined so that the item can be read as the entity.
+    fn increment<N: CounterValue>(
+        key: KeyInputs<'a, Self>,
+        attribute: impl Into<String>,
+        by: N,
+    ) -> CounterOperation<'a, Self, N> {
+        let update_expression = add(op!(COUNTER_NAME), op!(COUNTER_VALUE));
+        let operation = CounterOperation::new(key, attribute.into(), by);
+        match Self::ENTITY_TYPE {
+            Some(entity_type) => {
+                let entity_type_expression =
+                    set(op!(ET_EXP_NAME).value(if_not_exists(op!(ET_EXP_NAME), op!(ET_EXP_VALUE))));
+                operation
+                    .entity_type(entity_type)
+                    .update_expression(update_expression.and(entity_type_expression))
+            }
+            None => operation.update_expression(update_expression),
+        }
+    }
+
+    /// Return [`CounterOperation`] which subtracts the value from the attribute only if the
+    /// attribute is equal to or greater than the value, so that the counter never goes
+    /// negative.
+    ///
+    /// The operation fails with [`ErrorKind::ConditionalCheckFailed`](crate::ErrorKind) if
+    /// the attribute is less than the value or doesn't exist.
+    fn decrement_if_positive<N: CounterValue>(
+        key: KeyInputs<'a, Self>,
+        attribute: impl Into<String>,
+        by: N,
+    ) -> CounterOperation<'a, Self, N> {
+        let update_expression =
+            set(op!(COUNTER_NAME).value(op!(COUNTER_NAME).sub(op!(COUNTER_VALUE))));
+        let condition_expression = op!(COUNTER_NAME).gte(op!(COUNTER_VALUE));
+        CounterOperation::new(key, attribute.into(), by)
+            .update_expression(update_expression)
+            .condition_expression(condition_expression)
+    }
+}
+
+/// Represents the UpdateItem operation of a counter, which returns the new value.
+#[derive(Debug, Clone)]
+pub struct CounterOperation<'a, T, N>
+where
+    T: DynamodbTable<'a> + ?Sized,
+{
+    attribute: String,
+    input_builder: UpdateItemInputBuilder,
+    retry_policy: Option<RetryPolicy>,
+    item: PhantomData<&'a T>,
+    value: PhantomData<N>,
+}
+
+impl<'a, T, N> CounterOperation<'a, T, N>
+where
+    T: DynamodbTable<'a> + ?Sized,
+    N: CounterValue,
+{
+    fn new(key: KeyInputs<'a, T>, attribute: String, by: N) -> Self {
+        let (pk, sk) = key;
+        let input_builder = UpdateItemInput::builder()
+            .table_name(T::table_name())
+            .set_key(Some(T::Key::key(pk, sk)))
+            .expression_attribute_names(COUNTER_NAME, attribute.clone())
+            .expression_attribute_values(COUNTER_VALUE, AttributeValue::N(by.to_string()))
+            .return_values(ReturnValue::UpdatedNew);
+
+        Self {
+            attribute,
+            input_builder,
+            retry_policy: None,
+            item: PhantomData,
+            value: PhantomData,
+        }
+    }
+
+    fn update_expression(self, expr: impl Into<String>) -> Self {
+        Self {
+            input_builder: self.input_builder.update_expression(expr),
+            ..self
+        }
+    }
+
+    fn entity_type(self, entity_type: &str) -> Self {
+        Self {
+            input_builder: self
+                .input_builder
+                .expression_attribute_names(ET_EXP_NAME, T::ENTITY_TYPE_ATTRIBUTE)
+                .expression_attribute_values(ET_EXP_VALUE, AttributeValue::S(entity_type.into())),
+            ..self
+        }
+    }
+
+    fn condition_expression(self, expr: impl Into<String>) -> Self {
+        Self {
+            input_builder: self.input_builder.condition_expression(expr),
+            ..self
+        }
+    }
+
+    /// Retry the operation with the policy instead of the one of the backend.
+    ///
+    /// Note that the value may be added twice if the request succeeded but its response was
+    /// lost.
+    pub fn set_retry_policy(self, policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(policy),
+            ..self
+        }
+    }
+
+    /// Send UpdateItem request and return the new value of the counter.
+    pub async fn send<B: DynamoBackend>(self, backend: &B) -> Result<N, Error> {
+        let return_consumed_capacity =
+            return_consumed_capacity(self.input_builder.get_return_consumed_capacity());
+        let table_name = resolve_table_name(backend, self.input_builder.get_table_name());
+        let span = OperationSpan::new("UpdateItem", table_name.as_deref(), None)
+            .key(self.input_builder.get_key().as_ref(), T::REDACT_KEY)
+            .entity_type(T::ENTITY_TYPE);
+
+        let retry_policy = self.retry_policy.as_ref().or(backend.retry_policy());
+        span.instrument(async {
+            let input = self
+                .input_builder
+                .set_table_name(table_name)
+                .set_return_consumed_capacity(return_consumed_capacity);
+            let output = retry::send(retry_policy, || backend.update_item(input.clone())).await?;
+            span.output(output.consumed_capacity(), output.attributes.iter().count());
+
+            let value = output
+                .attributes
+                .as_ref()
+                .and_then(|attributes| attributes.get(&self.attribute))
+                .and_then(opt_n)
+                .ok_or_else(|| {
+                    Error::Conversion(format!("no number attribute {}", self.attribute).into())
+                })?;
+            value
+                .parse()
+                .map_err(|err: ParseIntError| Error::Conversion(err.into()))
+        })
+        .await
+    }
+
+    /// Blocking version of [`CounterOperation::send`].
+    #[cfg(feature = "blocking")]
+    pub fn send_blocking<B: DynamoBackend>(self, backend: &B) -> Result<N, Error> {
+        block_on(self.send(backend))?
+    }
+}
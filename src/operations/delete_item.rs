@@ -1,26 +1,39 @@
-use super::{BoxError, DynamodbTable, Error, Item, Key};
+use super::{
+    interceptor::{Decoded, Operation},
+    meter::return_consumed_capacity,
+    output::OperationOutput,
+    resolve_table_name,
+    retry::{self, RetryPolicy},
+    trace::OperationSpan,
+    BoxError, DynamoBackend, DynamodbTable, Error, Item, Key,
+};
 
 use aws_sdk_dynamodb::{
     operation::delete_item::{builders::DeleteItemInputBuilder, DeleteItemInput},
-    types::ReturnValue,
-    Client,
+    types::{ReturnConsumedCapacity, ReturnItemCollectionMetrics, ReturnValue},
 };
 use std::collections::HashMap;
 use std::marker::PhantomData;
 
+#[cfg(feature = "blocking")]
+use super::blocking::block_on;
+
 /// A trait enables your objects to execute DynamoDB DeleteItem operation.
 pub trait DeleteItem<'a>: DynamodbTable<'a> + TryFrom<Item, Error = BoxError> {
     fn delete_item() -> DeleteItemOperation<'a, Self, Self::Key> {
         let input_builder = DeleteItemInput::builder()
-            .table_name(Self::TABLE_NAME)
+            .table_name(Self::table_name())
             .set_return_values(Self::return_values())
             .set_condition_expression(Self::condition_expression())
             .set_expression_attribute_names(Self::expression_attribute_names())
-            .set_expression_attribute_values(Self::expression_attribute_values());
+            .set_expression_attribute_values(Self::expression_attribute_values())
+            .set_return_consumed_capacity(Self::return_consumed_capacity())
+            .set_return_item_collection_metrics(Self::return_item_collection_metrics());
 
         DeleteItemOperation {
             key: None,
             input_builder,
+            retry_policy: None,
             item: PhantomData,
             key_builder: PhantomData,
         }
@@ -65,6 +78,24 @@ pub trait DeleteItem<'a>: DynamodbTable<'a> + TryFrom<Item, Error = BoxError> {
     fn expression_attribute_values() -> Option<Item> {
         None
     }
+
+    /// Return value to be passed as `ReturnConsumedCapacity` to [`DeleteItemInput`].
+    /// Default is None.
+    ///
+    /// You should overwrite this method only if you use `ReturnConsumedCapacity` option.
+    /// The consumed capacity is returned by [`DeleteItemOperation::send_output`].
+    fn return_consumed_capacity() -> Option<ReturnConsumedCapacity> {
+        None
+    }
+
+    /// Return value to be passed as `ReturnItemCollectionMetrics` to [`DeleteItemInput`].
+    /// Default is None.
+    ///
+    /// You should overwrite this method only if you use `ReturnItemCollectionMetrics` option.
+    /// The metrics are returned by [`DeleteItemOperation::send_output`].
+    fn return_item_collection_metrics() -> Option<ReturnItemCollectionMetrics> {
+        None
+    }
 }
 
 /// Represents the DynamoDB DeleteItem operation.
@@ -76,6 +107,7 @@ where
 {
     key: Option<Item>,
     input_builder: DeleteItemInputBuilder,
+    retry_policy: Option<RetryPolicy>,
     item: PhantomData<&'a T>,
     key_builder: PhantomData<&'a K>,
 }
@@ -129,27 +161,106 @@ where
         }
     }
 
-    pub async fn send(self, client: &Client) -> Result<Option<T>, Error> {
+    /// Set `ReturnConsumedCapacity`.
+    pub fn set_return_consumed_capacity(self, value: ReturnConsumedCapacity) -> Self {
+        Self {
+            input_builder: self.input_builder.return_consumed_capacity(value),
+            ..self
+        }
+    }
+
+    /// Set `ReturnItemCollectionMetrics`.
+    pub fn set_return_item_collection_metrics(self, value: ReturnItemCollectionMetrics) -> Self {
+        Self {
+            input_builder: self.input_builder.return_item_collection_metrics(value),
+            ..self
+        }
+    }
+
+    /// Retry the operation with the policy instead of the one of the backend.
+    pub fn set_retry_policy(self, policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(policy),
+            ..self
+        }
+    }
+
+    pub async fn send<B: DynamoBackend>(self, backend: &B) -> Result<Option<T>, Error>
+    where
+        T: 'static,
+    {
+        self.send_output(backend).await.map(|output| output.value)
+    }
+
+    /// Send DeleteItem request like [`DeleteItemOperation::send`] and return the value with the
+    /// consumed capacity and the item collection metrics.
+    pub async fn send_output<B: DynamoBackend>(
+        self,
+        backend: &B,
+    ) -> Result<OperationOutput<Option<T>>, Error>
+    where
+        T: 'static,
+    {
         let return_value = matches!(
             self.input_builder.get_return_values(),
             Some(ReturnValue::AllOld)
         );
 
-        let output = self
-            .input_builder
-            .set_key(self.key)
-            .send_with(client)
-            .await
-            .map_err(|err| Error::Sdk(Box::new(err)))?;
-
-        if return_value {
-            output
-                .attributes
-                .map(T::try_from)
-                .transpose()
-                .map_err(Error::Conversion)
-        } else {
-            Ok(None)
-        }
+        let return_consumed_capacity =
+            return_consumed_capacity(self.input_builder.get_return_consumed_capacity());
+        let table_name = resolve_table_name(backend, self.input_builder.get_table_name());
+        let span = OperationSpan::new("DeleteItem", table_name.as_deref(), None)
+            .key(self.key.as_ref(), T::REDACT_KEY)
+            .entity_type(T::ENTITY_TYPE);
+
+        let retry_policy = self.retry_policy.as_ref().or(backend.retry_policy());
+        span.instrument(async {
+            let input = self
+                .input_builder
+                .set_table_name(table_name)
+                .set_key(self.key)
+                .set_return_consumed_capacity(return_consumed_capacity);
+            let output = retry::send(retry_policy, || backend.delete_item(input.clone())).await?;
+            span.output(output.consumed_capacity(), output.attributes.iter().count());
+
+            let value = if return_value {
+                output
+                    .attributes
+                    .map(T::try_from)
+                    .transpose()
+                    .map_err(Error::Conversion)?
+            } else {
+                None
+            };
+            backend.after_decode(&Decoded::new(Operation::DeleteItem, &value));
+
+            Ok(OperationOutput {
+                value,
+                consumed_capacity: output.consumed_capacity.map(Into::into),
+                item_collection_metrics: output.item_collection_metrics,
+            })
+        })
+        .await
+    }
+
+    /// Blocking version of [`DeleteItemOperation::send`].
+    #[cfg(feature = "blocking")]
+    pub fn send_blocking<B: DynamoBackend>(self, backend: &B) -> Result<Option<T>, Error>
+    where
+        T: 'static,
+    {
+        block_on(self.send(backend))?
+    }
+
+    /// Blocking version of [`DeleteItemOperation::send_output`].
+    #[cfg(feature = "blocking")]
+    pub fn send_output_blocking<B: DynamoBackend>(
+        self,
+        backend: &B,
+    ) -> Result<OperationOutput<Option<T>>, Error>
+    where
+        T: 'static,
+    {
+        block_on(self.send_output(backend))?
     }
 }
@@ -1,24 +1,40 @@
-use super::{BoxError, DynamodbTable, Error, Item, Key};
+use super::{
+    check_entity_type,
+    helpers::ttl,
+    interceptor::{Decoded, Operation},
+    meter::return_consumed_capacity,
+    output::OperationOutput,
+    projects_entity_type, resolve_table_name,
+    retry::{self, RetryPolicy},
+    trace::OperationSpan,
+    BoxError, DynamoBackend, DynamodbTable, Error, Item, Key,
+};
 
 use aws_sdk_dynamodb::{
     operation::get_item::{builders::GetItemInputBuilder, GetItemInput},
-    Client,
+    types::ReturnConsumedCapacity,
 };
 use std::collections::HashMap;
 use std::marker::PhantomData;
 
+#[cfg(feature = "blocking")]
+use super::blocking::block_on;
+
 /// A trait enables your objects to execute DynamoDB GetItem operation.
 pub trait GetItem<'a>: DynamodbTable<'a> + TryFrom<Item, Error = BoxError> {
     fn get_item() -> GetItemOperation<'a, Self, Self::Key> {
         let input_builder = GetItemInput::builder()
-            .table_name(Self::TABLE_NAME)
+            .table_name(Self::table_name())
             .set_consistent_read(Self::consistent_read())
             .set_projection_expression(Self::projection_expression())
-            .set_expression_attribute_names(Self::expression_attribute_names());
+            .set_expression_attribute_names(Self::expression_attribute_names())
+            .set_return_consumed_capacity(Self::return_consumed_capacity());
 
         GetItemOperation {
             key: None,
+            entity_filter: Self::ENTITY_TYPE.is_some(),
             input_builder,
+            retry_policy: None,
             item: PhantomData,
             key_builder: PhantomData,
         }
@@ -47,6 +63,15 @@ pub trait GetItem<'a>: DynamodbTable<'a> + TryFrom<Item, Error = BoxError> {
     fn expression_attribute_names() -> Option<HashMap<String, String>> {
         None
     }
+
+    /// Return value to be passed as `ReturnConsumedCapacity` to [`GetItemInput`].
+    /// Default is None.
+    ///
+    /// You should overwrite this method only if you use `ReturnConsumedCapacity` option.
+    /// The consumed capacity is returned by [`GetItemOperation::send_output`].
+    fn return_consumed_capacity() -> Option<ReturnConsumedCapacity> {
+        None
+    }
 }
 
 /// Represents the DynamoDB GetItem operation.
@@ -57,7 +82,9 @@ where
     K: Key<'a>,
 {
     key: Option<Item>,
+    entity_filter: bool,
     input_builder: GetItemInputBuilder,
+    retry_policy: Option<RetryPolicy>,
     item: PhantomData<&'a T>,
     key_builder: PhantomData<&'a K>,
 }
@@ -75,16 +102,116 @@ where
         }
     }
 
-    /// Send GetItem request with given client object.
-    pub async fn send(self, client: &Client) -> Result<Option<T>, Error> {
-        self.input_builder
-            .set_key(self.key)
-            .send_with(client)
-            .await
-            .map_err(|err| Error::Sdk(Box::new(err)))?
-            .item
-            .map(T::try_from)
-            .transpose()
-            .map_err(Error::Conversion)
+    /// Set whether to reject the item of another entity. It is enabled by default if the table
+    /// defines [`DynamodbTable::ENTITY_TYPE`].
+    ///
+    /// Disable it to read the items written before the entity type was defined. The check is
+    /// also skipped if the `ProjectionExpression` doesn't include the entity type attribute.
+    pub fn set_entity_filter(self, enabled: bool) -> Self {
+        Self {
+            entity_filter: enabled && T::ENTITY_TYPE.is_some(),
+            ..self
+        }
+    }
+
+    /// Set `ReturnConsumedCapacity`.
+    pub fn set_return_consumed_capacity(self, value: ReturnConsumedCapacity) -> Self {
+        Self {
+            input_builder: self.input_builder.return_consumed_capacity(value),
+            ..self
+        }
+    }
+
+    /// Retry the operation with the policy instead of the one of the backend.
+    pub fn set_retry_policy(self, policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(policy),
+            ..self
+        }
+    }
+
+    /// Send GetItem request with given backend like the client object.
+    ///
+    /// If the table defines [`DynamodbTable::TTL_ATTRIBUTE`], an expired item is returned as None.
+    /// If the table defines [`DynamodbTable::ENTITY_TYPE`], an item of another entity fails
+    /// unless disabled by [`GetItemOperation::set_entity_filter`].
+    pub async fn send<B: DynamoBackend>(self, backend: &B) -> Result<Option<T>, Error>
+    where
+        T: 'static,
+    {
+        self.send_output(backend).await.map(|output| output.value)
+    }
+
+    /// Send GetItem request like [`GetItemOperation::send`] and return the item with the
+    /// consumed capacity.
+    pub async fn send_output<B: DynamoBackend>(
+        self,
+        backend: &B,
+    ) -> Result<OperationOutput<Option<T>>, Error>
+    where
+        T: 'static,
+    {
+        let return_consumed_capacity =
+            return_consumed_capacity(self.input_builder.get_return_consumed_capacity());
+        let table_name = resolve_table_name(backend, self.input_builder.get_table_name());
+        let span = OperationSpan::new("GetItem", table_name.as_deref(), None)
+            .key(self.key.as_ref(), T::REDACT_KEY)
+            .entity_type(T::ENTITY_TYPE);
+
+        let entity_filter = self.entity_filter
+            && projects_entity_type::<T>(
+                self.input_builder.get_projection_expression().as_deref(),
+                self.input_builder.get_expression_attribute_names().as_ref(),
+            );
+        let retry_policy = self.retry_policy.as_ref().or(backend.retry_policy());
+        span.instrument(async {
+            let input = self
+                .input_builder
+                .set_table_name(table_name)
+                .set_key(self.key)
+                .set_return_consumed_capacity(return_consumed_capacity);
+            let output = retry::send(retry_policy, || backend.get_item(input.clone())).await?;
+            span.output(output.consumed_capacity(), output.item.iter().count());
+
+            let value = output
+                .item
+                .filter(|item| !T::TTL_ATTRIBUTE.is_some_and(|attr| ttl::is_expired(item, attr)))
+                .map(|item| {
+                    if entity_filter {
+                        check_entity_type::<T>(&item)?;
+                    }
+                    T::try_from(item).map_err(Error::Conversion)
+                })
+                .transpose()?;
+            backend.after_decode(&Decoded::new(Operation::GetItem, &value));
+
+            Ok(OperationOutput {
+                value,
+                consumed_capacity: output.consumed_capacity.map(Into::into),
+                item_collection_metrics: None,
+            })
+        })
+        .await
+    }
+
+    /// Blocking version of [`GetItemOperation::send`].
+    #[cfg(feature = "blocking")]
+    pub fn send_blocking<B: DynamoBackend>(self, backend: &B) -> Result<Option<T>, Error>
+    where
+        T: 'static,
+    {
+        block_on(self.send(backend))?
+    }
+
+    /// Blocking version of [`GetItemOperation::send_output`].
+    #[cfg(feature = "blocking")]
+    pub fn send_output_blocking<B: DynamoBackend>(
+        self,
+        backend: &B,
+    ) -> Result<OperationOutput<Option<T>>, Error>
+    where
+        T: 'static,
+    {
+        block_on(self.send_output(backend))?
     }
 }
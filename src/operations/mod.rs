@@ -1,7 +1,10 @@
+pub mod counter;
 pub mod delete_item;
 pub mod get_item;
+pub mod output;
 pub mod put_item;
 pub mod query;
 pub mod update_item;
+pub mod update_time_to_live;
 
 use super::*;
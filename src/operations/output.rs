@@ -0,0 +1,113 @@
+use aws_sdk_dynamodb::types::{Capacity, ItemCollectionMetrics};
+use std::collections::HashMap;
+
+/// The result of an operation with the metadata of the response.
+///
+/// Returned by `send_output` of the operations. The metadata is only present if the
+/// request sets `ReturnConsumedCapacity` or `ReturnItemCollectionMetrics`.
+#[derive(Debug, Clone)]
+pub struct OperationOutput<T> {
+    /// The value `send` returns.
+    pub value: T,
+    pub consumed_capacity: Option<ConsumedCapacity>,
+    pub item_collection_metrics: Option<ItemCollectionMetrics>,
+}
+
+/// The capacity units consumed by a request.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConsumedCapacity {
+    pub table_name: Option<String>,
+    /// The total of the table and the indexes.
+    pub total: CapacityUnits,
+    /// The units of the table only. Present if `ReturnConsumedCapacity` is `INDEXES`.
+    pub table: Option<CapacityUnits>,
+    /// The units of each local secondary index. Filled if `ReturnConsumedCapacity` is `INDEXES`.
+    pub local_secondary_indexes: HashMap<String, CapacityUnits>,
+    /// The units of each global secondary index. Filled if `ReturnConsumedCapacity` is `INDEXES`.
+    pub global_secondary_indexes: HashMap<String, CapacityUnits>,
+}
+
+/// Read and write capacity units. The absent units in the response are zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CapacityUnits {
+    pub total: f64,
+    pub read: f64,
+    pub write: f64,
+}
+
+impl From<&Capacity> for CapacityUnits {
+    fn from(capacity: &Capacity) -> Self {
+        Self {
+            total: capacity.capacity_units().unwrap_or_default(),
+            read: capacity.read_capacity_units().unwrap_or_default(),
+            write: capacity.write_capacity_units().unwrap_or_default(),
+        }
+    }
+}
+
+impl From<aws_sdk_dynamodb::types::ConsumedCapacity> for ConsumedCapacity {
+    fn from(capacity: aws_sdk_dynamodb::types::ConsumedCapacity) -> Self {
+        let indexes = |indexes: Option<&HashMap<String, Capacity>>| {
+            indexes
+                .into_iter()
+                .flatten()
+                .map(|(name, capacity)| (name.clone(), CapacityUnits::from(capacity)))
+                .collect()
+        };
+
+        Self {
+            total: CapacityUnits {
+                total: capacity.capacity_units().unwrap_or_default(),
+                read: capacity.read_capacity_units().unwrap_or_default(),
+                write: capacity.write_capacity_units().unwrap_or_default(),
+            },
+            table: capacity.table().map(CapacityUnits::from),
+            local_secondary_indexes: indexes(capacity.local_secondary_indexes()),
+            global_secondary_indexes: indexes(capacity.global_secondary_indexes()),
+            table_name: capacity.table_name,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_consumed_capacity_with_indexes() {
+        let capacity = aws_sdk_dynamodb::types::ConsumedCapacity::builder()
+            .table_name("Orders")
+            .capacity_units(3.0)
+            .write_capacity_units(3.0)
+            .table(
+                Capacity::builder()
+                    .capacity_units(1.0)
+                    .write_capacity_units(1.0)
+                    .build(),
+            )
+            .global_secondary_indexes(
+                "OrdersByStatus",
+                Capacity::builder()
+                    .capacity_units(2.0)
+                    .write_capacity_units(2.0)
+                    .build(),
+            )
+            .build();
+
+        let units = |units: f64| CapacityUnits {
+            total: units,
+            read: 0.0,
+            write: units,
+        };
+        assert_eq!(
+            ConsumedCapacity::from(capacity),
+            ConsumedCapacity {
+                table_name: Some("Orders".into()),
+                total: units(3.0),
+                table: Some(units(1.0)),
+                local_secondary_indexes: HashMap::new(),
+                global_secondary_indexes: HashMap::from([("OrdersByStatus".into(), units(2.0))]),
+            }
+        );
+    }
+}
@@ -1,13 +1,22 @@
-use super::{DynamodbTable, Error, Item};
+use super::{
+    meter::return_consumed_capacity,
+    output::OperationOutput,
+    resolve_table_name,
+    retry::{self, RetryPolicy},
+    trace::OperationSpan,
+    DynamoBackend, DynamodbTable, Error, Item,
+};
 
 use aws_sdk_dynamodb::{
     operation::put_item::{builders::PutItemInputBuilder, PutItemInput, PutItemOutput},
-    types::{AttributeValue, ReturnValue},
-    Client,
+    types::{AttributeValue, ReturnConsumedCapacity, ReturnItemCollectionMetrics, ReturnValue},
 };
 use std::collections::HashMap;
 use std::marker::PhantomData;
 
+#[cfg(feature = "blocking")]
+use super::blocking::block_on;
+
 /// A trait enables your objects to execute DynamoDB PutItem operation.
 ///
 /// You have to implement Into<HashMap<String, AttributeValue>> trait to the target type,
@@ -16,15 +25,18 @@ use std::marker::PhantomData;
 pub trait PutItem<'a>: DynamodbTable<'a> + Into<Item> {
     fn put_item() -> PutItemOperation<'a, Self> {
         let input_builder = PutItemInput::builder()
-            .table_name(Self::TABLE_NAME)
+            .table_name(Self::table_name())
             .set_return_values(Self::return_values())
             .set_condition_expression(Self::condition_expression())
             .set_expression_attribute_names(Self::expression_attribute_names())
-            .set_expression_attribute_values(Self::expression_attribute_values());
+            .set_expression_attribute_values(Self::expression_attribute_values())
+            .set_return_consumed_capacity(Self::return_consumed_capacity())
+            .set_return_item_collection_metrics(Self::return_item_collection_metrics());
 
         PutItemOperation {
             item: None,
             input_builder,
+            retry_policy: None,
             phantom: PhantomData,
         }
     }
@@ -68,6 +80,24 @@ pub trait PutItem<'a>: DynamodbTable<'a> + Into<Item> {
     fn expression_attribute_values() -> Option<HashMap<String, AttributeValue>> {
         None
     }
+
+    /// Return value to be passed as `ReturnConsumedCapacity` to [`PutItemInput`].
+    /// Default is None.
+    ///
+    /// You should overwrite this method only if you use `ReturnConsumedCapacity` option.
+    /// The consumed capacity is returned by [`PutItemOperation::send_output`].
+    fn return_consumed_capacity() -> Option<ReturnConsumedCapacity> {
+        None
+    }
+
+    /// Return value to be passed as `ReturnItemCollectionMetrics` to [`PutItemInput`].
+    /// Default is None.
+    ///
+    /// You should overwrite this method only if you use `ReturnItemCollectionMetrics` option.
+    /// The metrics are returned by [`PutItemOperation::send_output`].
+    fn return_item_collection_metrics() -> Option<ReturnItemCollectionMetrics> {
+        None
+    }
 }
 
 /// Represents the DynamoDB PutItem operation.
@@ -78,6 +108,7 @@ where
 {
     item: Option<T>,
     input_builder: PutItemInputBuilder,
+    retry_policy: Option<RetryPolicy>,
     phantom: PhantomData<&'a T>,
 }
 
@@ -92,18 +123,94 @@ where
         }
     }
 
-    pub async fn send(self, client: &Client) -> Result<PutItemOutput, Error> {
+    /// Set `ReturnConsumedCapacity`.
+    pub fn set_return_consumed_capacity(self, value: ReturnConsumedCapacity) -> Self {
+        Self {
+            input_builder: self.input_builder.return_consumed_capacity(value),
+            ..self
+        }
+    }
+
+    /// Set `ReturnItemCollectionMetrics`.
+    pub fn set_return_item_collection_metrics(self, value: ReturnItemCollectionMetrics) -> Self {
+        Self {
+            input_builder: self.input_builder.return_item_collection_metrics(value),
+            ..self
+        }
+    }
+
+    /// Retry the operation with the policy instead of the one of the backend.
+    pub fn set_retry_policy(self, policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(policy),
+            ..self
+        }
+    }
+
+    /// Send PutItem request like [`PutItemOperation::send`] and return the old attributes
+    /// with the consumed capacity and the item collection metrics.
+    ///
+    /// The old attributes are returned only if `ReturnValues` is `ALL_OLD`.
+    pub async fn send_output<B: DynamoBackend>(
+        self,
+        backend: &B,
+    ) -> Result<OperationOutput<Option<Item>>, Error> {
+        let output = self.send(backend).await?;
+        Ok(OperationOutput {
+            value: output.attributes,
+            consumed_capacity: output.consumed_capacity.map(Into::into),
+            item_collection_metrics: output.item_collection_metrics,
+        })
+    }
+
+    pub async fn send<B: DynamoBackend>(self, backend: &B) -> Result<PutItemOutput, Error> {
+        let key = self.item.as_ref().map(|v| v.key());
+        let return_consumed_capacity =
+            return_consumed_capacity(self.input_builder.get_return_consumed_capacity());
+        let table_name = resolve_table_name(backend, self.input_builder.get_table_name());
+        let span = OperationSpan::new("PutItem", table_name.as_deref(), None)
+            .key(key.as_ref(), T::REDACT_KEY)
+            .entity_type(T::ENTITY_TYPE);
+
         let item = self.item.map(|v| {
             let key = v.key();
             let mut item: Item = v.into();
             item.extend(key);
+            if let Some(entity_type) = T::ENTITY_TYPE {
+                item.insert(
+                    T::ENTITY_TYPE_ATTRIBUTE.into(),
+                    AttributeValue::S(entity_type.into()),
+                );
+            }
             item
         });
 
-        self.input_builder
-            .set_item(item)
-            .send_with(client)
-            .await
-            .map_err(|err| Error::Sdk(Box::new(err)))
+        let retry_policy = self.retry_policy.as_ref().or(backend.retry_policy());
+        span.instrument(async {
+            let input = self
+                .input_builder
+                .set_table_name(table_name)
+                .set_item(item)
+                .set_return_consumed_capacity(return_consumed_capacity);
+            let output = retry::send(retry_policy, || backend.put_item(input.clone())).await?;
+            span.output(output.consumed_capacity(), output.attributes.iter().count());
+            Ok(output)
+        })
+        .await
+    }
+
+    /// Blocking version of [`PutItemOperation::send`].
+    #[cfg(feature = "blocking")]
+    pub fn send_blocking<B: DynamoBackend>(self, backend: &B) -> Result<PutItemOutput, Error> {
+        block_on(self.send(backend))?
+    }
+
+    /// Blocking version of [`PutItemOperation::send_output`].
+    #[cfg(feature = "blocking")]
+    pub fn send_output_blocking<B: DynamoBackend>(
+        self,
+        backend: &B,
+    ) -> Result<OperationOutput<Option<Item>>, Error> {
+        block_on(self.send_output(backend))?
     }
 }
@@ -1,24 +1,40 @@
 use super::{
+    check_entity_type,
     helpers::{
         attribute_value::AttributeMap,
         expression::condition::{begins_with, Condition as ConditionExt},
+        ttl,
     },
-    op, BoxError, DynamodbTable, Error, Item, Key,
+    interceptor::{Decoded, Operation},
+    meter::return_consumed_capacity,
+    output::{ConsumedCapacity, OperationOutput},
+    projects_entity_type, resolve_table_name,
+    retry::{self, RetryPolicy},
+    trace::OperationSpan,
+    BoxError, CollectionEnum, DynamoBackend, DynamodbTable, Error, Index, Item, ItemCollection,
+    Key, SortKeyPrefix,
 };
 
 use aws_sdk_dynamodb::{
     operation::query::{builders::QueryInputBuilder, QueryInput, QueryOutput},
-    types::{AttributeValue, Condition, ConditionalOperator, ReturnConsumedCapacity, Select},
-    Client,
+    types::{
+        AttributeValue, Condition, ConditionalOperator, ProjectionType, ReturnConsumedCapacity,
+        Select,
+    },
 };
 use std::collections::HashMap;
 use std::marker::PhantomData;
 
+#[cfg(feature = "blocking")]
+use super::blocking::block_on;
+#[cfg(feature = "cursor")]
+use super::cursor::CursorCodec;
+
 /// A trait enables your objects to execute DynamoDB Query operation.
 pub trait Query<'a>: DynamodbTable<'a> + TryFrom<Item, Error = BoxError> {
     fn query() -> QueryOperation<'a, Self, Self::Key> {
         let input_builder = QueryInput::builder()
-            .table_name(Self::TABLE_NAME)
+            .table_name(Self::table_name())
             .set_index_name(Self::index_name())
             .set_select(Self::select())
             .set_attributes_to_get(Self::attribute_to_get())
@@ -34,15 +50,26 @@ pub trait Query<'a>: DynamodbTable<'a> + TryFrom<Item, Error = BoxError> {
             .set_expression_attribute_names(Self::expression_attribute_names())
             .set_expression_attribute_values(Self::expression_attribute_values());
 
-        QueryOperation {
-            pk_attr: Self::Key::PARTITION_KEY,
-            sk_attr: Self::Key::SORT_KEY,
-            pk: None,
-            sk: None,
-            input_builder,
-            item: PhantomData,
-            key_builder: PhantomData,
-        }
+        QueryOperation::new(input_builder)
+    }
+
+    /// Return [`QueryOperation`] for the secondary index.
+    ///
+    /// The key conditions are built against the key attributes of the index. The
+    /// [`Query::consistent_read`] of the table is applied only if [`Index::LOCAL`] is set,
+    /// because global secondary indexes don't support it.
+    ///
+    /// The entity filter is disabled if the index doesn't project
+    /// [`DynamodbTable::ENTITY_TYPE_ATTRIBUTE`], since the filter would reject every item.
+    fn query_index<I: Index<'a>>() -> QueryOperation<'a, Self, I::Key> {
+        let QueryOperation { input_builder, .. } = Self::query();
+        let consistent_read = input_builder.get_consistent_read().filter(|_| I::LOCAL);
+        QueryOperation::new(
+            input_builder
+                .index_name(I::INDEX_NAME)
+                .set_consistent_read(consistent_read),
+        )
+        .set_entity_filter(index_projects::<Self, I>(Self::ENTITY_TYPE_ATTRIBUTE))
     }
 
     /// Return values to be passed as `IndexName` to [`QueryInput`].
@@ -164,6 +191,8 @@ const SK_EXP_NAME: &str = "#SK";
 const SK_EXP_VALUE: &str = ":SK";
 const BETWEEN_FROM: &str = ":SK_FROM";
 const BETWEEN_TO: &str = ":SK_TO";
+const ET_EXP_NAME: &str = "#ET";
+const ET_EXP_VALUE: &str = ":ET";
 
 #[derive(Debug, Clone, PartialEq)]
 enum SkCondition {
@@ -205,6 +234,13 @@ where
 {
     pub items: Vec<T>,
     pub last_evaluated_key: Option<Item>,
+    /// The number of the items after the filter, as DynamoDB returned.
+    pub count: i32,
+    /// The number of the items evaluated before the filter. Compare it with `count` to see
+    /// how efficient the filter is.
+    pub scanned_count: i32,
+    /// Present only if the request sets `ReturnConsumedCapacity`.
+    pub consumed_capacity: Option<ConsumedCapacity>,
 }
 
 impl<T> TryFrom<QueryOutput> for QueryOperationOutput<T>
@@ -223,31 +259,142 @@ where
         Ok(QueryOperationOutput {
             items,
             last_evaluated_key: output.last_evaluated_key,
+            count: output.count,
+            scanned_count: output.scanned_count,
+            consumed_capacity: output.consumed_capacity.map(Into::into),
         })
     }
 }
 
+impl<T> From<OperationOutput<QueryPage<T>>> for QueryOperationOutput<T>
+where
+    T: TryFrom<Item, Error = BoxError>,
+{
+    fn from(output: OperationOutput<QueryPage<T>>) -> Self {
+        let page = output.value;
+        QueryOperationOutput {
+            items: page.items,
+            last_evaluated_key: page.last_evaluated_key,
+            count: page.count,
+            scanned_count: page.scanned_count,
+            consumed_capacity: output.consumed_capacity,
+        }
+    }
+}
+
+/// A page of the Query, which is the value of [`OperationOutput`] returned by
+/// [`QueryOperation::send_output`].
+#[derive(Debug, Clone)]
+pub struct QueryPage<T> {
+    pub items: Vec<T>,
+    pub last_evaluated_key: Option<Item>,
+    /// The number of the items after the filter, as DynamoDB returned.
+    pub count: i32,
+    /// The number of the items evaluated before the filter. Compare it with `count` to see
+    /// how efficient the filter is.
+    pub scanned_count: i32,
+}
+
+#[cfg(feature = "cursor")]
+impl<T> QueryOperationOutput<T>
+where
+    T: TryFrom<Item, Error = BoxError>,
+{
+    /// Encode `last_evaluated_key` into the cursor for the next page.
+    ///
+    /// Return None if this is the last page.
+    pub fn next_cursor(&self, codec: &CursorCodec) -> Result<Option<String>, Error> {
+        self.last_evaluated_key
+            .as_ref()
+            .map(|key| codec.encode(key))
+            .transpose()
+    }
+}
+
+impl<E> QueryOperationOutput<E>
+where
+    E: CollectionEnum + TryFrom<Item, Error = BoxError>,
+{
+    /// Assemble the items into the parent and its children.
+    ///
+    /// Query the collection with [`QueryOperation::project`] to decode the items into the
+    /// [`CollectionEnum`].
+    pub fn into_collection(self) -> Result<ItemCollection<E::Parent, E::Child>, Error> {
+        ItemCollection::assemble(self.items).map_err(Error::Conversion)
+    }
+}
+
 /// Represents the DynamoDB Query operation.
+///
+/// The items are decoded into `P`, which is the table type `T` itself by default.
+/// Use [`QueryOperation::project`] to decode them into another type, for example when the
+/// index projects only some of the attributes.
 #[derive(Debug, Clone)]
-pub struct QueryOperation<'a, T, K>
+pub struct QueryOperation<'a, T, K, P = T>
 where
     T: DynamodbTable<'a> + TryFrom<Item, Error = BoxError>,
     K: Key<'a>,
+    P: TryFrom<Item, Error = BoxError>,
 {
     pk_attr: &'a str,
     sk_attr: Option<&'a str>,
     pk: Option<AttributeValue>,
     sk: Option<SkCondition>,
+    entity_filter: bool,
     input_builder: QueryInputBuilder,
+    retry_policy: Option<RetryPolicy>,
+    page: Option<u32>,
     item: PhantomData<T>,
     key_builder: PhantomData<K>,
+    projection: PhantomData<P>,
 }
 
-impl<'a, T, K> QueryOperation<'a, T, K>
+impl<'a, T, K, P> QueryOperation<'a, T, K, P>
 where
     T: DynamodbTable<'a> + TryFrom<Item, Error = BoxError>,
     K: Key<'a>,
+    P: TryFrom<Item, Error = BoxError>,
 {
+    fn new(input_builder: QueryInputBuilder) -> Self {
+        Self {
+            pk_attr: K::PARTITION_KEY,
+            sk_attr: K::SORT_KEY,
+            pk: None,
+            sk: None,
+            entity_filter: T::ENTITY_TYPE.is_some(),
+            input_builder,
+            retry_policy: None,
+            page: None,
+            item: PhantomData,
+            key_builder: PhantomData,
+            projection: PhantomData,
+        }
+    }
+
+    /// Decode the items into the given type instead.
+    ///
+    /// To query the items of different entities sharing a partition, decode them into an
+    /// [`EntityEnum`](crate::EntityEnum). If the table defines [`DynamodbTable::ENTITY_TYPE`],
+    /// disable the entity filter with [`QueryOperation::set_entity_filter`] as well.
+    pub fn project<U>(self) -> QueryOperation<'a, T, K, U>
+    where
+        U: TryFrom<Item, Error = BoxError>,
+    {
+        QueryOperation {
+            pk_attr: self.pk_attr,
+            sk_attr: self.sk_attr,
+            pk: self.pk,
+            sk: self.sk,
+            entity_filter: self.entity_filter,
+            input_builder: self.input_builder,
+            retry_policy: self.retry_policy,
+            page: self.page,
+            item: PhantomData,
+            key_builder: PhantomData,
+            projection: PhantomData,
+        }
+    }
+
     /// Set partition key value
     pub fn pk_eq(self, input: K::PartitionInput) -> Self {
         Self {
@@ -308,9 +455,12 @@ where
     }
 
     /// Set sort key `begins_with` condition
-    pub fn sk_begins_with(self, value: AttributeValue) -> Self {
+    ///
+    /// The prefix is either a type implementing [`SortKeyPrefix`] for the key or a raw
+    /// [`AttributeValue`].
+    pub fn sk_begins_with<S: SortKeyPrefix<'a, K>>(self, prefix: S) -> Self {
         Self {
-            sk: Some(SkCondition::BeginsWith(value)),
+            sk: Some(SkCondition::BeginsWith(prefix.sort_key_prefix())),
             ..self
         }
     }
@@ -323,6 +473,42 @@ where
         }
     }
 
+    /// Set `ConsistentRead`.
+    pub fn set_consistent_read(self, consistent: bool) -> Self {
+        Self {
+            input_builder: self.input_builder.consistent_read(consistent),
+            ..self
+        }
+    }
+
+    /// Set `ReturnConsumedCapacity`.
+    pub fn set_return_consumed_capacity(self, value: ReturnConsumedCapacity) -> Self {
+        Self {
+            input_builder: self.input_builder.return_consumed_capacity(value),
+            ..self
+        }
+    }
+
+    /// Retry the operation with the policy instead of the one of the backend.
+    pub fn set_retry_policy(self, policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(policy),
+            ..self
+        }
+    }
+
+    /// Set the page number counting from 1, which is recorded in the span of the `tracing`
+    /// feature.
+    ///
+    /// The request without `ExclusiveStartKey` is recorded as the first page, so set this for
+    /// the following pages.
+    pub fn set_page(self, page: u32) -> Self {
+        Self {
+            page: Some(page),
+            ..self
+        }
+    }
+
     /// Set `index name`
     pub fn set_index(self, name: impl Into<String>) -> Self {
         Self {
@@ -339,11 +525,24 @@ where
         }
     }
 
+    /// Set whether the items are filtered by [`DynamodbTable::ENTITY_TYPE`].
+    /// Default is enabled if the table defines the entity type.
+    ///
+    /// When enabled, the items of other entities are also rejected on decoding. Note that the
+    /// index has to project the entity type attribute to be queried with the filter, so
+    /// [`Query::query_index`] disables it for the indexes which don't.
+    pub fn set_entity_filter(self, enabled: bool) -> Self {
+        Self {
+            entity_filter: enabled && T::ENTITY_TYPE.is_some(),
+            ..self
+        }
+    }
+
     /// Set `filter expression`
     ///
     /// **Caution**
-    /// You can't use keyword `#PK`, `#SK`, `:PK`, `:SK`, `:SK_FROM` or `:SK_TO` as
-    /// ExpressionAttributeNames because these words are used in inner logic of this struct.
+    /// You can't use keyword `#PK`, `#SK`, `#ET`, `:PK`, `:SK`, `:SK_FROM`, `:SK_TO` or `:ET`
+    /// as ExpressionAttributeNames because these words are used in inner logic of this struct.
     pub fn set_filter_expression(self, expr: impl Into<String>) -> Self {
         Self {
             input_builder: self.input_builder.filter_expression(expr),
@@ -354,8 +553,8 @@ where
     /// Set `expression attribute names` for filter expression.
     ///
     /// **Caution**
-    /// You can't use keyword `#PK`, `#SK`, `:PK`, `:SK`, `:SK_FROM` or `:SK_TO` as
-    /// ExpressionAttributeNames because these words are used in inner logic of this struct.
+    /// You can't use keyword `#PK`, `#SK`, `#ET`, `:PK`, `:SK`, `:SK_FROM`, `:SK_TO` or `:ET`
+    /// as ExpressionAttributeNames because these words are used in inner logic of this struct.
     pub fn set_expression_attribute_names(self, names: HashMap<String, String>) -> Self {
         Self {
             input_builder: self
@@ -368,8 +567,8 @@ where
     /// Set `expression attribute values` for filter expression.
     ///
     /// **Caution**
-    /// You can't use keyword `#PK`, `#SK`, `:PK`, `:SK`, `:SK_FROM` or `:SK_TO` as
-    /// ExpressionAttributeNames because these words are used in inner logic of this struct.
+    /// You can't use keyword `#PK`, `#SK`, `#ET`, `:PK`, `:SK`, `:SK_FROM`, `:SK_TO` or `:ET`
+    /// as ExpressionAttributeNames because these words are used in inner logic of this struct.
     pub fn set_expression_attribute_values(self, values: Item) -> Self {
         Self {
             input_builder: self
@@ -379,25 +578,131 @@ where
         }
     }
 
-    /// Send Query request with given client and pagination key.
-    pub async fn send(
+    /// Send Query request with given backend and cursor made by
+    /// [`QueryOperationOutput::next_cursor`].
+    ///
+    /// Fail without sending the request if the cursor is invalid.
+    #[cfg(feature = "cursor")]
+    pub async fn send_with_cursor<B: DynamoBackend>(
+        self,
+        backend: &B,
+        codec: &CursorCodec,
+        cursor: Option<&str>,
+    ) -> Result<QueryOperationOutput<P>, Error>
+    where
+        P: 'static,
+    {
+        let exclusive_start_key = cursor.map(|cursor| codec.decode(cursor)).transpose()?;
+        self.send(backend, exclusive_start_key).await
+    }
+
+    /// Send Query request with given backend like the client object and pagination key.
+    ///
+    /// If the table defines [`DynamodbTable::TTL_ATTRIBUTE`], expired items are removed from
+    /// the output, so a page may contain fewer items than the `limit`.
+    pub async fn send<B: DynamoBackend>(
         self,
-        client: &Client,
+        backend: &B,
         exclusive_start_key: Option<Item>,
-    ) -> Result<QueryOperationOutput<T>, Error> {
+    ) -> Result<QueryOperationOutput<P>, Error>
+    where
+        P: 'static,
+    {
+        self.send_output(backend, exclusive_start_key)
+            .await
+            .map(QueryOperationOutput::from)
+    }
+
+    /// Send Query request like [`QueryOperation::send`] and return the page with the
+    /// consumed capacity in the same envelope as the other operations.
+    pub async fn send_output<B: DynamoBackend>(
+        self,
+        backend: &B,
+        exclusive_start_key: Option<Item>,
+    ) -> Result<OperationOutput<QueryPage<P>>, Error>
+    where
+        P: 'static,
+    {
+        let entity_filter = self.entity_filter;
+        let key = self
+            .pk
+            .as_ref()
+            .map(|pk| Item::from([(self.pk_attr.to_string(), pk.clone())]));
+        let table_name = resolve_table_name(backend, self.input_builder.get_table_name());
+        let span = OperationSpan::new(
+            "Query",
+            table_name.as_deref(),
+            self.input_builder.get_index_name().as_deref(),
+        )
+        .key(key.as_ref(), T::REDACT_KEY)
+        .entity_type(T::ENTITY_TYPE)
+        .page(self.page.or(exclusive_start_key.is_none().then_some(1)));
+
         let key_condition_expression = self.key_condition_expression();
+        let filter_expression = self.filter_expression();
         let expression_attribute_names = self.expression_attribute_names();
         let expression_attribute_values = self.expression_attribute_values();
+        let return_consumed_capacity =
+            return_consumed_capacity(self.input_builder.get_return_consumed_capacity());
 
-        self.input_builder
+        let input_builder = self
+            .input_builder
+            .set_table_name(table_name)
             .key_condition_expression(key_condition_expression)
+            .set_filter_expression(filter_expression)
             .set_exclusive_start_key(exclusive_start_key)
             .set_expression_attribute_names(Some(expression_attribute_names))
             .set_expression_attribute_values(Some(expression_attribute_values))
-            .send_with(client)
-            .await
-            .map_err(|err| Error::Sdk(Box::new(err)))
-            .and_then(QueryOperationOutput::try_from)
+            .set_return_consumed_capacity(return_consumed_capacity);
+
+        let retry_policy = self.retry_policy.as_ref().or(backend.retry_policy());
+        span.instrument(async {
+            let mut output =
+                retry::send(retry_policy, || backend.query(input_builder.clone())).await?;
+            span.output(output.consumed_capacity(), output.items().len());
+
+            if let (Some(attr), Some(items)) = (T::TTL_ATTRIBUTE, output.items.as_mut()) {
+                items.retain(|item| !ttl::is_expired(item, attr));
+            }
+            // the filter expression has already excluded other entities, so the items are
+            // checked only if the entity type attribute is projected.
+            let projected = projects_entity_type::<T>(
+                input_builder.get_projection_expression().as_deref(),
+                input_builder.get_expression_attribute_names().as_ref(),
+            );
+            if entity_filter && projected {
+                for item in output.items.iter().flatten() {
+                    check_entity_type::<T>(item)?;
+                }
+            }
+            let output = QueryOperationOutput::try_from(output)?;
+            backend.after_decode(&Decoded::new(Operation::Query, &output.items));
+            Ok(OperationOutput {
+                value: QueryPage {
+                    items: output.items,
+                    last_evaluated_key: output.last_evaluated_key,
+                    count: output.count,
+                    scanned_count: output.scanned_count,
+                },
+                consumed_capacity: output.consumed_capacity,
+                item_collection_metrics: None,
+            })
+        })
+        .await
+    }
+
+    fn filter_expression(&self) -> Option<String> {
+        let filter_expression = self.input_builder.get_filter_expression().clone();
+
+        if !self.entity_filter {
+            return filter_expression;
+        }
+
+        let et_expr: String = op!(ET_EXP_NAME).equal(op!(ET_EXP_VALUE)).into();
+        match filter_expression {
+            Some(expr) => Some(format!("({expr}) AND {et_expr}")),
+            None => Some(et_expr),
+        }
     }
 
     fn key_condition_expression(&self) -> String {
@@ -469,6 +774,9 @@ where
             .clone()
             .unwrap_or_default();
         names.extend(self.key_expression_attribute_names());
+        if self.entity_filter {
+            names.insert(ET_EXP_NAME.into(), T::ENTITY_TYPE_ATTRIBUTE.into());
+        }
         names
     }
 
@@ -479,6 +787,71 @@ where
             .clone()
             .unwrap_or_default();
         values.extend(self.key_expression_attribute_values());
+        if let Some(entity_type) = T::ENTITY_TYPE.filter(|_| self.entity_filter) {
+            values.insert(ET_EXP_VALUE.into(), AttributeValue::S(entity_type.into()));
+        }
         values
     }
+
+    /// Blocking version of [`QueryOperation::send`].
+    #[cfg(feature = "blocking")]
+    pub fn send_blocking<B: DynamoBackend>(
+        self,
+        backend: &B,
+        exclusive_start_key: Option<Item>,
+    ) -> Result<QueryOperationOutput<P>, Error>
+    where
+        P: 'static,
+    {
+        block_on(self.send(backend, exclusive_start_key))?
+    }
+
+    /// Blocking version of [`QueryOperation::send_output`].
+    #[cfg(feature = "blocking")]
+    pub fn send_output_blocking<B: DynamoBackend>(
+        self,
+        backend: &B,
+        exclusive_start_key: Option<Item>,
+    ) -> Result<OperationOutput<QueryPage<P>>, Error>
+    where
+        P: 'static,
+    {
+        block_on(self.send_output(backend, exclusive_start_key))?
+    }
+
+    /// Blocking version of [`QueryOperation::send_with_cursor`].
+    #[cfg(all(feature = "blocking", feature = "cursor"))]
+    pub fn send_with_cursor_blocking<B: DynamoBackend>(
+        self,
+        backend: &B,
+        codec: &CursorCodec,
+        cursor: Option<&str>,
+    ) -> Result<QueryOperationOutput<P>, Error>
+    where
+        P: 'static,
+    {
+        block_on(self.send_with_cursor(backend, codec, cursor))?
+    }
+}
+
+fn index_projects<'a, T, I>(attribute: &str) -> bool
+where
+    T: DynamodbTable<'a>,
+    I: Index<'a>,
+{
+    let keys = [
+        Some(T::Key::PARTITION_KEY),
+        T::Key::SORT_KEY,
+        Some(I::Key::PARTITION_KEY),
+        I::Key::SORT_KEY,
+    ];
+    if keys.contains(&Some(attribute)) {
+        return true;
+    }
+
+    match I::PROJECTION_TYPE {
+        ProjectionType::KeysOnly => false,
+        ProjectionType::Include => I::NON_KEY_ATTRIBUTES.contains(&attribute),
+        _ => true,
+    }
 }
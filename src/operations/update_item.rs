@@ -1,27 +1,41 @@
-use super::{BoxError, DynamodbTable, Error, Item, Key};
+use super::{
+    helpers::expression::{diff::ItemDiff, patch::PatchItem},
+    interceptor::{Decoded, Operation},
+    meter::return_consumed_capacity,
+    output::OperationOutput,
+    resolve_table_name,
+    retry::{self, RetryPolicy},
+    trace::OperationSpan,
+    BoxError, DynamoBackend, DynamodbTable, Error, Item, Key,
+};
 
 use aws_sdk_dynamodb::{
     operation::update_item::{builders::UpdateItemInputBuilder, UpdateItemInput},
-    types::ReturnValue,
-    Client,
+    types::{ReturnConsumedCapacity, ReturnItemCollectionMetrics, ReturnValue},
 };
 use std::collections::HashMap;
 use std::marker::PhantomData;
 
+#[cfg(feature = "blocking")]
+use super::blocking::block_on;
+
 /// A trait enables your objects to execute DynamoDB UpdateItem operation.
 pub trait UpdateItem<'a>: DynamodbTable<'a> + TryFrom<Item, Error = BoxError> {
     fn update_item() -> UpdateItemOperation<'a, Self, Self::Key> {
         let input_builder = UpdateItemInput::builder()
-            .table_name(Self::TABLE_NAME)
+            .table_name(Self::table_name())
             .set_return_values(Self::return_values())
             .set_update_expression(Self::update_expression())
             .set_condition_expression(Self::condition_expression())
             .set_expression_attribute_names(Self::expression_attribute_names())
-            .set_expression_attribute_values(Self::expression_attribute_values());
+            .set_expression_attribute_values(Self::expression_attribute_values())
+            .set_return_consumed_capacity(Self::return_consumed_capacity())
+            .set_return_item_collection_metrics(Self::return_item_collection_metrics());
 
         UpdateItemOperation {
             key: None,
             input_builder,
+            retry_policy: None,
             item: PhantomData,
             key_builder: PhantomData,
         }
@@ -32,6 +46,31 @@ pub trait UpdateItem<'a>: DynamodbTable<'a> + TryFrom<Item, Error = BoxError> {
         Self::update_item().set_key_from(self)
     }
 
+    /// Return [`UpdateItemOperation`] which updates only the attributes of self changed from
+    /// the original, instead of rewriting the whole item with PutItem.
+    ///
+    /// The key attributes are not compared, so the original should be the same item as self
+    /// before the modification. See [`UpdateItemOperation::set_diff`].
+    ///
+    /// Return None if nothing has changed, so that no request is sent. An UpdateItem request
+    /// without the update expression would consume a write and create the item with only the
+    /// key if it had been deleted.
+    fn save_changes(&self, original: &Self) -> Option<UpdateItemOperation<'a, Self, Self::Key>>
+    where
+        Self: Clone + Into<Item>,
+    {
+        let into_attributes = |object: &Self| {
+            let mut item: Item = object.clone().into();
+            item.remove(Self::Key::PARTITION_KEY);
+            if let Some(sort_key) = Self::Key::SORT_KEY {
+                item.remove(sort_key);
+            }
+            item
+        };
+        let diff = ItemDiff::new(&into_attributes(original), &into_attributes(self));
+        (!diff.is_empty()).then(|| self.update().set_diff(diff))
+    }
+
     /// Return value to be passed as `ReturnValues` to [`UpdateItemInput`].
     /// Default is None.
     ///
@@ -74,6 +113,24 @@ pub trait UpdateItem<'a>: DynamodbTable<'a> + TryFrom<Item, Error = BoxError> {
     fn expression_attribute_values() -> Option<Item> {
         None
     }
+
+    /// Return value to be passed as `ReturnConsumedCapacity` to [`UpdateItemInput`].
+    /// Default is None.
+    ///
+    /// You should overwrite this method only if you use `ReturnConsumedCapacity` option.
+    /// The consumed capacity is returned by [`UpdateItemOperation::send_output`].
+    fn return_consumed_capacity() -> Option<ReturnConsumedCapacity> {
+        None
+    }
+
+    /// Return value to be passed as `ReturnItemCollectionMetrics` to [`UpdateItemInput`].
+    /// Default is None.
+    ///
+    /// You should overwrite this method only if you use `ReturnItemCollectionMetrics` option.
+    /// The metrics are returned by [`UpdateItemOperation::send_output`].
+    fn return_item_collection_metrics() -> Option<ReturnItemCollectionMetrics> {
+        None
+    }
 }
 
 /// Represents the DynamoDB UpdateItem operation.
@@ -85,6 +142,7 @@ where
 {
     key: Option<Item>,
     input_builder: UpdateItemInputBuilder,
+    retry_policy: Option<RetryPolicy>,
     item: PhantomData<&'a T>,
     key_builder: PhantomData<&'a K>,
 }
@@ -118,6 +176,45 @@ where
         }
     }
 
+    /// Set the update expression computed by [`ItemDiff`] with its names and values.
+    ///
+    /// The names and values are added to the ones already set, and the update expression is
+    /// replaced. Nothing is set if the diff is empty, so check [`ItemDiff::is_empty`] to skip
+    /// the request which updates no attribute.
+    pub fn set_diff(self, diff: ItemDiff) -> Self {
+        if diff.is_empty() {
+            return self;
+        }
+
+        let mut names = self
+            .input_builder
+            .get_expression_attribute_names()
+            .clone()
+            .unwrap_or_default();
+        names.extend(diff.names);
+        let mut values = self
+            .input_builder
+            .get_expression_attribute_values()
+            .clone()
+            .unwrap_or_default();
+        values.extend(diff.values);
+
+        Self {
+            input_builder: self
+                .input_builder
+                .update_expression(diff.expression)
+                .set_expression_attribute_names(Some(names))
+                .set_expression_attribute_values(Some(values)),
+            ..self
+        }
+    }
+
+    /// Set the update of the patch, like the body of a REST PATCH request.
+    /// See [`UpdateItemOperation::set_diff`].
+    pub fn set_patch(self, patch: impl PatchItem) -> Self {
+        self.set_diff(patch.into_diff())
+    }
+
     /// Set condition expression
     pub fn set_condition_expression(self, expr: impl Into<String>) -> Self {
         Self {
@@ -146,27 +243,132 @@ where
         }
     }
 
-    pub async fn send(self, client: &Client) -> Result<Option<T>, Error> {
+    /// Add expression attribute names to the ones already set, like the names of
+    /// [`UpdateItemOperation::set_diff`].
+    pub fn add_expression_attribute_names(self, names: HashMap<String, String>) -> Self {
+        let mut input_builder = self.input_builder;
+        for (placeholder, name) in names {
+            input_builder = input_builder.expression_attribute_names(placeholder, name);
+        }
+        Self {
+            input_builder,
+            ..self
+        }
+    }
+
+    /// Add expression attribute values to the ones already set, like the values of
+    /// [`UpdateItemOperation::set_diff`].
+    pub fn add_expression_attribute_values(self, values: Item) -> Self {
+        let mut input_builder = self.input_builder;
+        for (placeholder, value) in values {
+            input_builder = input_builder.expression_attribute_values(placeholder, value);
+        }
+        Self {
+            input_builder,
+            ..self
+        }
+    }
+
+    /// Set `ReturnConsumedCapacity`.
+    pub fn set_return_consumed_capacity(self, value: ReturnConsumedCapacity) -> Self {
+        Self {
+            input_builder: self.input_builder.return_consumed_capacity(value),
+            ..self
+        }
+    }
+
+    /// Set `ReturnItemCollectionMetrics`.
+    pub fn set_return_item_collection_metrics(self, value: ReturnItemCollectionMetrics) -> Self {
+        Self {
+            input_builder: self.input_builder.return_item_collection_metrics(value),
+            ..self
+        }
+    }
+
+    /// Retry the operation with the policy instead of the one of the backend.
+    pub fn set_retry_policy(self, policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy: Some(policy),
+            ..self
+        }
+    }
+
+    pub async fn send<B: DynamoBackend>(self, backend: &B) -> Result<Option<T>, Error>
+    where
+        T: 'static,
+    {
+        self.send_output(backend).await.map(|output| output.value)
+    }
+
+    /// Send UpdateItem request like [`UpdateItemOperation::send`] and return the value with the
+    /// consumed capacity and the item collection metrics.
+    pub async fn send_output<B: DynamoBackend>(
+        self,
+        backend: &B,
+    ) -> Result<OperationOutput<Option<T>>, Error>
+    where
+        T: 'static,
+    {
         let return_value = matches!(
             self.input_builder.get_return_values(),
             Some(ReturnValue::AllNew) | Some(ReturnValue::AllOld)
         );
 
-        let output = self
-            .input_builder
-            .set_key(self.key)
-            .send_with(client)
-            .await
-            .map_err(|err| Error::Sdk(Box::new(err)))?;
-
-        if return_value {
-            output
-                .attributes
-                .map(T::try_from)
-                .transpose()
-                .map_err(Error::Conversion)
-        } else {
-            Ok(None)
-        }
+        let return_consumed_capacity =
+            return_consumed_capacity(self.input_builder.get_return_consumed_capacity());
+        let table_name = resolve_table_name(backend, self.input_builder.get_table_name());
+        let span = OperationSpan::new("UpdateItem", table_name.as_deref(), None)
+            .key(self.key.as_ref(), T::REDACT_KEY)
+            .entity_type(T::ENTITY_TYPE);
+
+        let retry_policy = self.retry_policy.as_ref().or(backend.retry_policy());
+        span.instrument(async {
+            let input = self
+                .input_builder
+                .set_table_name(table_name)
+                .set_key(self.key)
+                .set_return_consumed_capacity(return_consumed_capacity);
+            let output = retry::send(retry_policy, || backend.update_item(input.clone())).await?;
+            span.output(output.consumed_capacity(), output.attributes.iter().count());
+
+            let value = if return_value {
+                output
+                    .attributes
+                    .map(T::try_from)
+                    .transpose()
+                    .map_err(Error::Conversion)?
+            } else {
+                None
+            };
+            backend.after_decode(&Decoded::new(Operation::UpdateItem, &value));
+
+            Ok(OperationOutput {
+                value,
+                consumed_capacity: output.consumed_capacity.map(Into::into),
+                item_collection_metrics: output.item_collection_metrics,
+            })
+        })
+        .await
+    }
+
+    /// Blocking version of [`UpdateItemOperation::send`].
+    #[cfg(feature = "blocking")]
+    pub fn send_blocking<B: DynamoBackend>(self, backend: &B) -> Result<Option<T>, Error>
+    where
+        T: 'static,
+    {
+        block_on(self.send(backend))?
+    }
+
+    /// Blocking version of [`UpdateItemOperation::send_output`].
+    #[cfg(feature = "blocking")]
+    pub fn send_output_blocking<B: DynamoBackend>(
+        self,
+        backend: &B,
+    ) -> Result<OperationOutput<Option<T>>, Error>
+    where
+        T: 'static,
+    {
+        block_on(self.send_output(backend))?
     }
 }
@@ -0,0 +1,82 @@
+use super::{resolve_table_name, trace::OperationSpan, DynamoBackend, DynamodbTable, Error};
+
+use aws_sdk_dynamodb::{
+    operation::update_time_to_live::{UpdateTimeToLiveInput, UpdateTimeToLiveOutput},
+    types::TimeToLiveSpecification,
+};
+use std::marker::PhantomData;
+
+#[cfg(feature = "blocking")]
+use super::blocking::block_on;
+
+/// A trait enables your objects to execute DynamoDB UpdateTimeToLive operation.
+///
+/// The TTL attribute is taken from [`DynamodbTable::TTL_ATTRIBUTE`], so you have to overwrite
+/// it before enabling Time to Live on the table.
+pub trait UpdateTimeToLive<'a>: DynamodbTable<'a> {
+    fn update_time_to_live() -> UpdateTimeToLiveOperation<'a, Self> {
+        UpdateTimeToLiveOperation {
+            enabled: true,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Represents the DynamoDB UpdateTimeToLive operation.
+#[derive(Debug, Clone)]
+pub struct UpdateTimeToLiveOperation<'a, T>
+where
+    T: DynamodbTable<'a> + ?Sized,
+{
+    enabled: bool,
+    phantom: PhantomData<&'a T>,
+}
+
+impl<'a, T> UpdateTimeToLiveOperation<'a, T>
+where
+    T: DynamodbTable<'a> + ?Sized,
+{
+    /// Set whether Time to Live is enabled or disabled. Default is enabled.
+    pub fn set_enabled(self, enabled: bool) -> Self {
+        Self { enabled, ..self }
+    }
+
+    /// Send UpdateTimeToLive request with given backend like the client object.
+    ///
+    /// Fail if [`DynamodbTable::TTL_ATTRIBUTE`] is not defined.
+    pub async fn send<B: DynamoBackend>(
+        self,
+        backend: &B,
+    ) -> Result<UpdateTimeToLiveOutput, Error> {
+        let table_name = resolve_table_name(backend, &Some(T::table_name()));
+        let span = OperationSpan::new("UpdateTimeToLive", table_name.as_deref(), None)
+            .entity_type(T::ENTITY_TYPE);
+
+        let specification = TimeToLiveSpecification::builder()
+            .set_attribute_name(T::TTL_ATTRIBUTE.map(String::from))
+            .enabled(self.enabled)
+            .build()
+            .map_err(|err| Error::Sdk(Box::new(err)))?;
+
+        let input_builder = UpdateTimeToLiveInput::builder()
+            .set_table_name(table_name)
+            .time_to_live_specification(specification);
+
+        span.instrument(async {
+            backend
+                .update_time_to_live(input_builder)
+                .await
+                .map_err(Error::Sdk)
+        })
+        .await
+    }
+
+    /// Blocking version of [`UpdateTimeToLiveOperation::send`].
+    #[cfg(feature = "blocking")]
+    pub fn send_blocking<B: DynamoBackend>(
+        self,
+        backend: &B,
+    ) -> Result<UpdateTimeToLiveOutput, Error> {
+        block_on(self.send(backend))?
+    }
+}
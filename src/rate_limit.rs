@@ -0,0 +1,376 @@
+//! Client-side limit of the capacity units the requests consume.
+//!
+//! [`RateLimited`] wraps a backend and waits for a shared [`CapacityLimiter`] before sending
+//! every request, so bulk jobs like backfills stay within a budget of read and write capacity
+//! units per second and leave the rest of a provisioned table to the production traffic.
+//!
+//! DynamoDB tells the consumed capacity only after the request, so each request takes one unit
+//! from the bucket first and is charged the rest when the response returns it. A large scan
+//! page can leave the bucket in debt, and the next requests wait until it is paid back.
+//!
+//! ```
+//! use dynamo_mapper::{
+//!     memory::MemoryBackend,
+//!     rate_limit::{CapacityLimiter, RateLimited},
+//! };
+//!
+//! // Share the limiter among the tasks of the job.
+//! let limiter = CapacityLimiter::new()
+//!     .with_read_capacity(50.0)
+//!     .with_write_capacity(25.0);
+//! let backend = RateLimited::new(MemoryBackend::new(), limiter.clone());
+//! ```
+use crate::{
+    interceptor::{Decoded, Response},
+    retry::RetryPolicy,
+    BoxError, DynamoBackend, TableNameResolver,
+};
+
+use aws_sdk_dynamodb::{
+    config::{AsyncSleep, SharedAsyncSleep},
+    operation::{
+        batch_get_item::{builders::BatchGetItemInputBuilder, BatchGetItemOutput},
+        batch_write_item::{builders::BatchWriteItemInputBuilder, BatchWriteItemOutput},
+        delete_item::{builders::DeleteItemInputBuilder, DeleteItemOutput},
+        get_item::{builders::GetItemInputBuilder, GetItemOutput},
+        put_item::{builders::PutItemInputBuilder, PutItemOutput},
+        query::{builders::QueryInputBuilder, QueryOutput},
+        scan::{builders::ScanInputBuilder, ScanOutput},
+        transact_get_items::{builders::TransactGetItemsInputBuilder, TransactGetItemsOutput},
+        transact_write_items::{
+            builders::TransactWriteItemsInputBuilder, TransactWriteItemsOutput,
+        },
+        update_item::{builders::UpdateItemInputBuilder, UpdateItemOutput},
+        update_time_to_live::{builders::UpdateTimeToLiveInputBuilder, UpdateTimeToLiveOutput},
+    },
+    types::ReturnConsumedCapacity,
+};
+use aws_smithy_async::rt::sleep::TokioSleep;
+use std::{
+    fmt,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// The units a request takes from the bucket before it is sent.
+const ESTIMATED_UNITS: f64 = 1.0;
+
+/// The kind of the capacity units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CapacityKind {
+    Read,
+    Write,
+}
+
+/// A token bucket refilled with the capacity units per second.
+///
+/// The bucket holds up to one second of the units, and the tokens go negative when the
+/// requests consume more than the bucket has.
+#[derive(Debug)]
+struct Bucket {
+    units_per_second: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(units_per_second: f64) -> Self {
+        assert!(
+            units_per_second > 0.0,
+            "capacity units per second must be positive"
+        );
+        Self {
+            units_per_second,
+            tokens: units_per_second,
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.units_per_second).min(self.units_per_second);
+        self.updated = now;
+    }
+
+    /// Take the units, or return how long to wait until the bucket has them.
+    fn take(&mut self, units: f64, now: Instant) -> Result<(), Duration> {
+        self.refill(now);
+
+        let required = units.min(self.units_per_second);
+        if self.tokens < required {
+            let seconds = (required - self.tokens) / self.units_per_second;
+            return Err(Duration::from_secs_f64(seconds));
+        }
+        self.tokens -= units;
+        Ok(())
+    }
+}
+
+/// Limits of the read and write capacity units per second shared by the clones.
+///
+/// The kind without a limit is not limited. Waiting uses the tokio timer unless another
+/// sleep implementation is given by [`CapacityLimiter::with_sleep`].
+#[derive(Clone)]
+pub struct CapacityLimiter {
+    read: Option<Arc<Mutex<Bucket>>>,
+    write: Option<Arc<Mutex<Bucket>>>,
+    sleep: SharedAsyncSleep,
+}
+
+impl fmt::Debug for CapacityLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CapacityLimiter")
+            .field("read", &self.read)
+            .field("write", &self.write)
+            .finish()
+    }
+}
+
+impl Default for CapacityLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CapacityLimiter {
+    /// Create a limiter without limits.
+    pub fn new() -> Self {
+        Self {
+            read: None,
+            write: None,
+            sleep: SharedAsyncSleep::new(TokioSleep::new()),
+        }
+    }
+
+    /// Limit the read capacity units per second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `units_per_second` is not positive.
+    pub fn with_read_capacity(self, units_per_second: f64) -> Self {
+        Self {
+            read: Some(Arc::new(Mutex::new(Bucket::new(units_per_second)))),
+            ..self
+        }
+    }
+
+    /// Limit the write capacity units per second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `units_per_second` is not positive.
+    pub fn with_write_capacity(self, units_per_second: f64) -> Self {
+        Self {
+            write: Some(Arc::new(Mutex::new(Bucket::new(units_per_second)))),
+            ..self
+        }
+    }
+
+    /// Wait with the sleep implementation instead of the tokio timer.
+    pub fn with_sleep(self, sleep: impl AsyncSleep + 'static) -> Self {
+        Self {
+            sleep: SharedAsyncSleep::new(sleep),
+            ..self
+        }
+    }
+
+    fn bucket(&self, kind: CapacityKind) -> Option<MutexGuard<'_, Bucket>> {
+        let bucket = match kind {
+            CapacityKind::Read => self.read.as_ref(),
+            CapacityKind::Write => self.write.as_ref(),
+        };
+        bucket.map(|bucket| bucket.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Wait until the units are available and take them.
+    pub async fn acquire(&self, kind: CapacityKind, units: f64) {
+        loop {
+            let wait = match self.bucket(kind) {
+                Some(mut bucket) => bucket.take(units, Instant::now()),
+                None => Ok(()),
+            };
+            match wait {
+                Ok(()) => return,
+                Err(wait) => self.sleep.sleep(wait).await,
+            }
+        }
+    }
+
+    /// Take the units if they are available without waiting.
+    pub fn try_acquire(&self, kind: CapacityKind, units: f64) -> bool {
+        self.bucket(kind)
+            .is_none_or(|mut bucket| bucket.take(units, Instant::now()).is_ok())
+    }
+
+    /// Charge the units consumed beyond the acquired ones. Negative units are refunded.
+    pub fn consume(&self, kind: CapacityKind, units: f64) {
+        if let Some(mut bucket) = self.bucket(kind) {
+            bucket.tokens -= units;
+        }
+    }
+}
+
+/// A [`DynamoBackend`] which waits for the [`CapacityLimiter`] before sending every request.
+///
+/// The requests without `ReturnConsumedCapacity` are sent with `TOTAL` to charge the
+/// consumed capacity. UpdateTimeToLive is not limited.
+#[derive(Debug, Clone)]
+pub struct RateLimited<B> {
+    inner: B,
+    limiter: CapacityLimiter,
+}
+
+impl<B: DynamoBackend> RateLimited<B> {
+    /// Wrap the backend with the limiter.
+    pub fn new(inner: B, limiter: CapacityLimiter) -> Self {
+        Self { inner, limiter }
+    }
+
+    /// Return the limiter.
+    pub fn limiter(&self) -> &CapacityLimiter {
+        &self.limiter
+    }
+
+    /// Return the inner backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+macro_rules! impl_rate_limited_backend {
+    ($($method:ident: $kind:ident, $variant:ident($input:ty => $output:ty),)*) => {
+        impl<B: DynamoBackend> DynamoBackend for RateLimited<B> {
+            $(
+                async fn $method(&self, mut input: $input) -> Result<$output, BoxError> {
+                    if input.get_return_consumed_capacity().is_none() {
+                        input = input.return_consumed_capacity(ReturnConsumedCapacity::Total);
+                    }
+
+                    let kind = CapacityKind::$kind;
+                    self.limiter.acquire(kind, ESTIMATED_UNITS).await;
+                    let output = self.inner.$method(input).await?;
+
+                    let response = Response::$variant(&output);
+                    let capacity = response.consumed_capacity();
+                    if !capacity.is_empty() {
+                        let units: f64 = capacity
+                            .iter()
+                            .filter_map(|capacity| capacity.capacity_units())
+                            .sum();
+                        self.limiter.consume(kind, units - ESTIMATED_UNITS);
+                    }
+                    Ok(output)
+                }
+            )*
+
+            async fn update_time_to_live(
+                &self,
+                input: UpdateTimeToLiveInputBuilder,
+            ) -> Result<UpdateTimeToLiveOutput, BoxError> {
+                self.inner.update_time_to_live(input).await
+            }
+
+            fn retry_policy(&self) -> Option<&RetryPolicy> {
+                self.inner.retry_policy()
+            }
+
+            fn table_name_resolver(&self) -> Option<&dyn TableNameResolver> {
+                self.inner.table_name_resolver()
+            }
+
+            fn after_decode(&self, decoded: &Decoded<'_>) {
+                self.inner.after_decode(decoded)
+            }
+        }
+    };
+}
+
+impl_rate_limited_backend! {
+    get_item: Read, GetItem(GetItemInputBuilder => GetItemOutput),
+    put_item: Write, PutItem(PutItemInputBuilder => PutItemOutput),
+    update_item: Write, UpdateItem(UpdateItemInputBuilder => UpdateItemOutput),
+    delete_item: Write, DeleteItem(DeleteItemInputBuilder => DeleteItemOutput),
+    query: Read, Query(QueryInputBuilder => QueryOutput),
+    scan: Read, Scan(ScanInputBuilder => ScanOutput),
+    batch_get_item: Read, BatchGetItem(BatchGetItemInputBuilder => BatchGetItemOutput),
+    batch_write_item: Write, BatchWriteItem(BatchWriteItemInputBuilder => BatchWriteItemOutput),
+    transact_get_items: Read, TransactGetItems(TransactGetItemsInputBuilder => TransactGetItemsOutput),
+    transact_write_items: Write, TransactWriteItems(TransactWriteItemsInputBuilder => TransactWriteItemsOutput),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        helpers::attribute_value::AttributeMap,
+        memory::MemoryBackend,
+        schema::{KeyAttribute, TableSchema},
+    };
+    use aws_sdk_dynamodb::{
+        operation::put_item::PutItemInput,
+        types::{BillingMode, ScalarAttributeType},
+    };
+
+    #[test]
+    fn bucket_refills_per_second() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(10.0);
+        bucket.updated = start;
+
+        assert_eq!(bucket.take(10.0, start), Ok(()));
+        assert_eq!(bucket.take(1.0, start), Err(Duration::from_secs_f64(0.1)));
+        assert_eq!(bucket.take(1.0, start + Duration::from_millis(100)), Ok(()));
+
+        // The bucket does not hold more than one second of the units.
+        bucket.refill(start + Duration::from_secs(60));
+        assert_eq!(bucket.tokens, 10.0);
+    }
+
+    #[test]
+    fn bucket_waits_to_pay_back_debt() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(10.0);
+        bucket.updated = start;
+
+        // A scan page consumes much more than the estimate.
+        assert_eq!(bucket.take(1.0, start), Ok(()));
+        bucket.tokens -= 48.0;
+        assert_eq!(bucket.take(1.0, start), Err(Duration::from_secs(4)));
+        assert_eq!(bucket.take(1.0, start + Duration::from_secs(4)), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn charge_requests_to_shared_limiter() {
+        let backend = MemoryBackend::new();
+        backend
+            .create_table(TableSchema {
+                table_name: "Jobs".into(),
+                partition_key: KeyAttribute::new("id", ScalarAttributeType::S),
+                sort_key: None,
+                global_secondary_indexes: vec![],
+                local_secondary_indexes: vec![],
+                billing_mode: BillingMode::PayPerRequest,
+                provisioned_throughput: None,
+                stream_specification: None,
+                ttl_attribute: None,
+            })
+            .unwrap();
+
+        let limiter = CapacityLimiter::new().with_write_capacity(2.0);
+        let backend = RateLimited::new(backend, limiter.clone());
+        for id in ["1", "2"] {
+            let item = AttributeMap::new().set_s("id", id).into_item();
+            backend
+                .put_item(
+                    PutItemInput::builder()
+                        .table_name("Jobs")
+                        .set_item(Some(item)),
+                )
+                .await
+                .unwrap();
+        }
+
+        assert!(!limiter.try_acquire(CapacityKind::Write, 1.0));
+        assert!(limiter.try_acquire(CapacityKind::Read, 1.0));
+    }
+}
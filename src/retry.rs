@@ -0,0 +1,423 @@
+//! Retries of the operations failed by throttling or transaction conflicts.
+//!
+//! The SDK retries the transport errors of each HTTP request, but it gives up on throttling
+//! after a few attempts and doesn't retry transaction conflicts. A [`RetryPolicy`] retries the
+//! whole operation with decorrelated jitter backoff until the error is gone or the policy
+//! gives up.
+//!
+//! Only these errors are retried, because the request had no effect:
+//!
+//! - `ProvisionedThroughputExceededException`, `RequestLimitExceeded` and
+//!   `ThrottlingException`.
+//! - `TransactionConflictException`.
+//! - `TransactionCanceledException` whose cancellation reasons are only the conflicts or
+//!   throttling. A canceled transaction with a failed condition is never retried.
+//!
+//! The policy is set to each operation with `set_retry_policy`, or to all the operations sent
+//! to a backend by wrapping the backend with [`Retrying`].
+//!
+//! ```
+//! use dynamo_mapper::{
+//!     memory::MemoryBackend,
+//!     retry::{RetryPolicy, Retrying},
+//! };
+//! use std::time::Duration;
+//!
+//! let policy = RetryPolicy::new().with_max_elapsed_time(Duration::from_secs(10));
+//! let backend = Retrying::new(MemoryBackend::new(), policy);
+//! ```
+use crate::{
+    error::sdk_error_kind, interceptor::Decoded, BoxError, DynamoBackend, Error, ErrorKind,
+    TableNameResolver,
+};
+
+use aws_sdk_dynamodb::{
+    config::{AsyncSleep, SharedAsyncSleep},
+    operation::{
+        batch_get_item::{builders::BatchGetItemInputBuilder, BatchGetItemOutput},
+        batch_write_item::{builders::BatchWriteItemInputBuilder, BatchWriteItemOutput},
+        delete_item::{builders::DeleteItemInputBuilder, DeleteItemOutput},
+        get_item::{builders::GetItemInputBuilder, GetItemOutput},
+        put_item::{builders::PutItemInputBuilder, PutItemOutput},
+        query::{builders::QueryInputBuilder, QueryOutput},
+        scan::{builders::ScanInputBuilder, ScanOutput},
+        transact_get_items::{builders::TransactGetItemsInputBuilder, TransactGetItemsOutput},
+        transact_write_items::{
+            builders::TransactWriteItemsInputBuilder, TransactWriteItemsOutput,
+        },
+        update_item::{builders::UpdateItemInputBuilder, UpdateItemOutput},
+        update_time_to_live::{builders::UpdateTimeToLiveInputBuilder, UpdateTimeToLiveOutput},
+    },
+};
+use aws_smithy_async::rt::sleep::TokioSleep;
+use std::{
+    fmt,
+    future::Future,
+    time::{Duration, Instant},
+};
+
+/// How many times and how long the operations are retried.
+///
+/// The delay before each retry is a random duration between the base delay and three times
+/// the previous delay, capped by the max delay. The retries stop at the max attempts, or when
+/// the next retry would end after the max elapsed time.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    max_elapsed_time: Duration,
+    sleep: SharedAsyncSleep,
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("base_delay", &self.base_delay)
+            .field("max_delay", &self.max_delay)
+            .field("max_elapsed_time", &self.max_elapsed_time)
+            .finish()
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RetryPolicy {
+    /// Create the policy of 8 attempts at most, from 50 milliseconds to 5 seconds of the delay
+    /// and 30 seconds of the max elapsed time.
+    pub fn new() -> Self {
+        Self {
+            max_attempts: 8,
+            base_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(5),
+            max_elapsed_time: Duration::from_secs(30),
+            sleep: SharedAsyncSleep::new(TokioSleep::new()),
+        }
+    }
+
+    /// Create the policy which never retries. Set this to an operation to opt out of the
+    /// policy of the backend.
+    pub fn never() -> Self {
+        Self::new().with_max_attempts(1)
+    }
+
+    /// Set the max attempts including the first one.
+    pub fn with_max_attempts(self, max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..self
+        }
+    }
+
+    /// Set the delay before the first retry, which is also the minimum delay.
+    pub fn with_base_delay(self, base_delay: Duration) -> Self {
+        Self { base_delay, ..self }
+    }
+
+    /// Set the maximum delay between the retries.
+    pub fn with_max_delay(self, max_delay: Duration) -> Self {
+        Self { max_delay, ..self }
+    }
+
+    /// Set the time after which the operation is not retried anymore.
+    pub fn with_max_elapsed_time(self, max_elapsed_time: Duration) -> Self {
+        Self {
+            max_elapsed_time,
+            ..self
+        }
+    }
+
+    /// Wait with the sleep implementation instead of the tokio timer.
+    pub fn with_sleep(self, sleep: impl AsyncSleep + 'static) -> Self {
+        Self {
+            sleep: SharedAsyncSleep::new(sleep),
+            ..self
+        }
+    }
+
+    /// Return whether the error of the backend is retried.
+    pub fn is_retryable(err: &BoxError) -> bool {
+        use aws_sdk_dynamodb::Error as SdkError;
+
+        if let Some(SdkError::TransactionCanceledException(err)) = err.downcast_ref::<SdkError>() {
+            let codes: Vec<&str> = err
+                .cancellation_reasons()
+                .iter()
+                .filter_map(|reason| reason.code())
+                .filter(|code| *code != "None")
+                .collect();
+            return !codes.is_empty()
+                && codes.iter().all(|code| {
+                    matches!(
+                        *code,
+                        "TransactionConflict" | "ProvisionedThroughputExceeded" | "ThrottlingError"
+                    )
+                });
+        }
+
+        matches!(
+            sdk_error_kind(err),
+            ErrorKind::Throttled | ErrorKind::TransactionConflict
+        )
+    }
+
+    /// Return the delay before the next retry after the previous delay.
+    fn next_delay(&self, previous: Duration) -> Duration {
+        let low = self.base_delay.as_secs_f64();
+        let high = (previous.as_secs_f64() * 3.0).max(low);
+        let delay = low + (high - low) * fastrand::f64();
+        Duration::from_secs_f64(delay).min(self.max_delay)
+    }
+
+    /// Send the request until it succeeds or fails with an error which is not retried.
+    pub async fn retry<T, F, Fut>(&self, mut request: F) -> Result<T, BoxError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, BoxError>>,
+    {
+        let started = Instant::now();
+        let mut delay = self.base_delay;
+        let mut attempts = 1;
+
+        loop {
+            let err = match request().await {
+                Ok(output) => return Ok(output),
+                Err(err) => err,
+            };
+
+            delay = self.next_delay(delay);
+            if attempts >= self.max_attempts
+                || started.elapsed() + delay > self.max_elapsed_time
+                || !Self::is_retryable(&err)
+            {
+                return Err(err);
+            }
+
+            self.sleep.sleep(delay).await;
+            attempts += 1;
+        }
+    }
+}
+
+/// Send the request with the policy, or once without it.
+pub(crate) async fn send<T, F, Fut>(
+    policy: Option<&RetryPolicy>,
+    mut request: F,
+) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, BoxError>>,
+{
+    match policy {
+        Some(policy) => policy.retry(request).await,
+        None => request().await,
+    }
+    .map_err(Error::Sdk)
+}
+
+/// A [`DynamoBackend`] which gives the [`RetryPolicy`] to the operations sent to it.
+///
+/// The operations retry with this policy unless they set their own. The requests sent to this
+/// backend directly are not retried.
+#[derive(Debug, Clone)]
+pub struct Retrying<B> {
+    inner: B,
+    policy: RetryPolicy,
+}
+
+impl<B: DynamoBackend> Retrying<B> {
+    /// Wrap the backend with the default policy of the operations.
+    pub fn new(inner: B, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// Return the inner backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+macro_rules! impl_retrying_backend {
+    ($($method:ident: $input:ty => $output:ty,)*) => {
+        impl<B: DynamoBackend> DynamoBackend for Retrying<B> {
+            $(
+                async fn $method(&self, input: $input) -> Result<$output, BoxError> {
+                    self.inner.$method(input).await
+                }
+            )*
+
+            fn retry_policy(&self) -> Option<&RetryPolicy> {
+                Some(&self.policy)
+            }
+
+            fn table_name_resolver(&self) -> Option<&dyn TableNameResolver> {
+                self.inner.table_name_resolver()
+            }
+
+            fn after_decode(&self, decoded: &Decoded<'_>) {
+                self.inner.after_decode(decoded)
+            }
+        }
+    };
+}
+
+impl_retrying_backend! {
+    get_item: GetItemInputBuilder => GetItemOutput,
+    put_item: PutItemInputBuilder => PutItemOutput,
+    update_item: UpdateItemInputBuilder => UpdateItemOutput,
+    delete_item: DeleteItemInputBuilder => DeleteItemOutput,
+    query: QueryInputBuilder => QueryOutput,
+    scan: ScanInputBuilder => ScanOutput,
+    batch_get_item: BatchGetItemInputBuilder => BatchGetItemOutput,
+    batch_write_item: BatchWriteItemInputBuilder => BatchWriteItemOutput,
+    transact_get_items: TransactGetItemsInputBuilder => TransactGetItemsOutput,
+    transact_write_items: TransactWriteItemsInputBuilder => TransactWriteItemsOutput,
+    update_time_to_live: UpdateTimeToLiveInputBuilder => UpdateTimeToLiveOutput,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_dynamodb::{
+        config::Sleep,
+        types::{
+            error::{
+                ConditionalCheckFailedException, ThrottlingException, TransactionCanceledException,
+                TransactionConflictException,
+            },
+            CancellationReason,
+        },
+        Error as SdkError,
+    };
+    use std::sync::{Arc, Mutex};
+
+    /// A sleep which records the delays without waiting.
+    #[derive(Debug, Clone, Default)]
+    struct Delays(Arc<Mutex<Vec<Duration>>>);
+
+    impl AsyncSleep for Delays {
+        fn sleep(&self, duration: Duration) -> Sleep {
+            self.0.lock().unwrap().push(duration);
+            Sleep::new(async {})
+        }
+    }
+
+    fn sdk(err: SdkError) -> BoxError {
+        Box::new(err)
+    }
+
+    fn throttling() -> BoxError {
+        sdk(SdkError::ThrottlingException(
+            ThrottlingException::builder().build(),
+        ))
+    }
+
+    fn canceled(codes: &[&str]) -> BoxError {
+        let reasons = codes
+            .iter()
+            .map(|code| CancellationReason::builder().code(*code).build())
+            .collect();
+        sdk(SdkError::TransactionCanceledException(
+            TransactionCanceledException::builder()
+                .set_cancellation_reasons(Some(reasons))
+                .build(),
+        ))
+    }
+
+    #[test]
+    fn retry_only_throttling_and_conflicts() {
+        assert!(RetryPolicy::is_retryable(&throttling()));
+        assert!(RetryPolicy::is_retryable(&sdk(
+            SdkError::TransactionConflictException(TransactionConflictException::builder().build())
+        )));
+        assert!(RetryPolicy::is_retryable(&canceled(&[
+            "None",
+            "TransactionConflict"
+        ])));
+
+        assert!(!RetryPolicy::is_retryable(&sdk(
+            SdkError::ConditionalCheckFailedException(
+                ConditionalCheckFailedException::builder().build()
+            )
+        )));
+        assert!(!RetryPolicy::is_retryable(&canceled(&[
+            "TransactionConflict",
+            "ConditionalCheckFailed"
+        ])));
+        assert!(!RetryPolicy::is_retryable(&"connection refused".into()));
+    }
+
+    #[tokio::test]
+    async fn retry_until_success_with_jitter() {
+        let delays = Delays::default();
+        let policy = RetryPolicy::new()
+            .with_base_delay(Duration::from_millis(100))
+            .with_max_delay(Duration::from_millis(250))
+            .with_sleep(delays.clone());
+
+        let mut attempts = 0;
+        let result = policy
+            .retry(|| {
+                attempts += 1;
+                let result = if attempts < 4 {
+                    Err(throttling())
+                } else {
+                    Ok(attempts)
+                };
+                async move { result }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 4);
+
+        let delays = delays.0.lock().unwrap();
+        assert_eq!(delays.len(), 3);
+        assert!(delays.iter().all(|delay| (Duration::from_millis(100)
+            ..=Duration::from_millis(250))
+            .contains(delay)));
+    }
+
+    #[tokio::test]
+    async fn give_up_at_max_attempts_or_non_retryable_error() {
+        let policy = RetryPolicy::new()
+            .with_max_attempts(3)
+            .with_sleep(Delays::default());
+
+        let mut attempts = 0;
+        let result: Result<(), _> = policy
+            .retry(|| {
+                attempts += 1;
+                async { Err(throttling()) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+
+        let mut attempts = 0;
+        let result: Result<(), _> = policy
+            .retry(|| {
+                attempts += 1;
+                async { Err(canceled(&["ConditionalCheckFailed"])) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn give_up_after_max_elapsed_time() {
+        let delays = Delays::default();
+        let policy = RetryPolicy::new()
+            .with_base_delay(Duration::from_secs(1))
+            .with_max_elapsed_time(Duration::from_millis(500))
+            .with_sleep(delays.clone());
+
+        let result: Result<(), _> = policy.retry(|| async { Err(throttling()) }).await;
+        assert!(result.is_err());
+        assert!(delays.0.lock().unwrap().is_empty());
+    }
+}
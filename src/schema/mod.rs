@@ -0,0 +1,588 @@
+use super::{DynamodbTable, Error, Index, Key, TableNameResolver};
+
+use aws_sdk_dynamodb::{
+    client::Waiters,
+    operation::create_table::{builders::CreateTableInputBuilder, CreateTableInput},
+    types::{
+        AttributeDefinition, BillingMode, GlobalSecondaryIndex, KeySchemaElement, KeyType,
+        LocalSecondaryIndex, Projection, ProjectionType, ProvisionedThroughput,
+        ScalarAttributeType, StreamSpecification, TimeToLiveSpecification,
+    },
+    Client,
+};
+use std::time::Duration;
+
+#[cfg(feature = "blocking")]
+use super::blocking::block_on;
+
+mod verify;
+
+pub use verify::{verify_schema, IndexKind, SchemaDiff, SchemaDifference};
+
+/// Max time to wait for the table to become ACTIVE or to be deleted.
+const MAX_WAIT: Duration = Duration::from_secs(300);
+
+/// A trait describes the schema of the DynamoDB table your objects are mapped to.
+///
+/// The key attributes are taken from the [`Key`] of the table and the TTL attribute is taken
+/// from [`DynamodbTable::TTL_ATTRIBUTE`], so you should overwrite only the methods for the
+/// other settings of the table.
+pub trait Schema<'a>: DynamodbTable<'a> {
+    /// Return the schema of the table.
+    fn schema() -> TableSchema {
+        TableSchema {
+            table_name: Self::table_name(),
+            partition_key: KeyAttribute::new(
+                <Self::Key as Key>::PARTITION_KEY,
+                <Self::Key as Key>::PARTITION_KEY_TYPE,
+            ),
+            sort_key: <Self::Key as Key>::SORT_KEY
+                .map(|name| KeyAttribute::new(name, <Self::Key as Key>::SORT_KEY_TYPE)),
+            global_secondary_indexes: Self::global_secondary_indexes(),
+            local_secondary_indexes: Self::local_secondary_indexes(),
+            billing_mode: Self::billing_mode(),
+            provisioned_throughput: Self::provisioned_throughput(),
+            stream_specification: Self::stream_specification(),
+            ttl_attribute: Self::TTL_ATTRIBUTE.map(String::from),
+        }
+    }
+
+    /// Return the global secondary indexes of the table.
+    /// Default is empty.
+    fn global_secondary_indexes() -> Vec<IndexSchema> {
+        vec![]
+    }
+
+    /// Return the local secondary indexes of the table.
+    /// Default is empty.
+    fn local_secondary_indexes() -> Vec<IndexSchema> {
+        vec![]
+    }
+
+    /// Return the billing mode of the table.
+    /// Default is `PAY_PER_REQUEST`.
+    ///
+    /// If you overwrite this method to return `PROVISIONED`, you also have to overwrite
+    /// [`Schema::provisioned_throughput`].
+    fn billing_mode() -> BillingMode {
+        BillingMode::PayPerRequest
+    }
+
+    /// Return the provisioned throughput of the table.
+    /// Default is None.
+    ///
+    /// You should overwrite this method only if you use `PROVISIONED` billing mode.
+    fn provisioned_throughput() -> Option<ProvisionedThroughput> {
+        None
+    }
+
+    /// Return the stream specification of the table.
+    /// Default is None.
+    ///
+    /// You should overwrite this method only if you use DynamoDB Streams.
+    fn stream_specification() -> Option<StreamSpecification> {
+        None
+    }
+}
+
+/// Represents a key attribute of the table or the secondary index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyAttribute {
+    pub name: String,
+    pub attribute_type: ScalarAttributeType,
+}
+
+impl KeyAttribute {
+    /// Create a new instance.
+    pub fn new(name: impl Into<String>, attribute_type: ScalarAttributeType) -> Self {
+        Self {
+            name: name.into(),
+            attribute_type,
+        }
+    }
+
+    fn definition(&self) -> Result<AttributeDefinition, Error> {
+        AttributeDefinition::builder()
+            .attribute_name(&self.name)
+            .attribute_type(self.attribute_type.clone())
+            .build()
+            .map_err(|err| Error::Sdk(Box::new(err)))
+    }
+
+    fn key_schema(&self, key_type: KeyType) -> Result<KeySchemaElement, Error> {
+        KeySchemaElement::builder()
+            .attribute_name(&self.name)
+            .key_type(key_type)
+            .build()
+            .map_err(|err| Error::Sdk(Box::new(err)))
+    }
+}
+
+/// Represents the schema of a secondary index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexSchema {
+    pub name: String,
+    pub partition_key: KeyAttribute,
+    pub sort_key: Option<KeyAttribute>,
+    pub projection_type: ProjectionType,
+    pub non_key_attributes: Option<Vec<String>>,
+    pub provisioned_throughput: Option<ProvisionedThroughput>,
+}
+
+impl IndexSchema {
+    /// Create a new instance from the index name and the [`Key`] of the index.
+    /// The projection type is `ALL`.
+    pub fn new<'a, K: Key<'a>>(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            partition_key: KeyAttribute::new(K::PARTITION_KEY, K::PARTITION_KEY_TYPE),
+            sort_key: K::SORT_KEY.map(|name| KeyAttribute::new(name, K::SORT_KEY_TYPE)),
+            projection_type: ProjectionType::All,
+            non_key_attributes: None,
+            provisioned_throughput: None,
+        }
+    }
+
+    /// Create a new instance from the [`Index`].
+    pub fn from_index<'a, I: Index<'a>>() -> Self {
+        let non_key_attributes = Some(I::NON_KEY_ATTRIBUTES)
+            .filter(|attributes| !attributes.is_empty())
+            .map(|attributes| attributes.iter().map(|v| v.to_string()).collect());
+
+        Self::new::<I::Key>(I::INDEX_NAME).set_projection(I::PROJECTION_TYPE, non_key_attributes)
+    }
+
+    /// Set the projection type and the non-key attributes projected into the index.
+    pub fn set_projection(
+        self,
+        projection_type: ProjectionType,
+        non_key_attributes: Option<Vec<String>>,
+    ) -> Self {
+        Self {
+            projection_type,
+            non_key_attributes,
+            ..self
+        }
+    }
+
+    /// Set the provisioned throughput of the index.
+    pub fn set_provisioned_throughput(self, throughput: ProvisionedThroughput) -> Self {
+        Self {
+            provisioned_throughput: Some(throughput),
+            ..self
+        }
+    }
+
+    fn key_schema(&self) -> Result<Vec<KeySchemaElement>, Error> {
+        key_schema(&self.partition_key, self.sort_key.as_ref())
+    }
+
+    fn projection(&self) -> Projection {
+        Projection::builder()
+            .projection_type(self.projection_type.clone())
+            .set_non_key_attributes(self.non_key_attributes.clone())
+            .build()
+    }
+
+    fn global(&self) -> Result<GlobalSecondaryIndex, Error> {
+        GlobalSecondaryIndex::builder()
+            .index_name(&self.name)
+            .set_key_schema(Some(self.key_schema()?))
+            .projection(self.projection())
+            .set_provisioned_throughput(self.provisioned_throughput.clone())
+            .build()
+            .map_err(|err| Error::Sdk(Box::new(err)))
+    }
+
+    fn local(&self) -> Result<LocalSecondaryIndex, Error> {
+        LocalSecondaryIndex::builder()
+            .index_name(&self.name)
+            .set_key_schema(Some(self.key_schema()?))
+            .projection(self.projection())
+            .build()
+            .map_err(|err| Error::Sdk(Box::new(err)))
+    }
+}
+
+/// Represents the schema of the DynamoDB table.
+///
+/// You usually get this from [`Schema::schema`], so that the table definition is shared
+/// between your application, bootstrap scripts and tests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableSchema {
+    pub table_name: String,
+    pub partition_key: KeyAttribute,
+    pub sort_key: Option<KeyAttribute>,
+    pub global_secondary_indexes: Vec<IndexSchema>,
+    pub local_secondary_indexes: Vec<IndexSchema>,
+    pub billing_mode: BillingMode,
+    pub provisioned_throughput: Option<ProvisionedThroughput>,
+    pub stream_specification: Option<StreamSpecification>,
+    pub ttl_attribute: Option<String>,
+}
+
+impl TableSchema {
+    /// Resolve the table name with the resolver, so that the table is created with the name the
+    /// operations sent to [`Resolving`](crate::Resolving) use.
+    pub fn resolve_table_name(self, resolver: &impl TableNameResolver) -> Self {
+        Self {
+            table_name: resolver.resolve(&self.table_name),
+            ..self
+        }
+    }
+
+    /// Return the attribute definitions of all the keys used in the table and its indexes.
+    pub fn attribute_definitions(&self) -> Result<Vec<AttributeDefinition>, Error> {
+        let indexes = self
+            .global_secondary_indexes
+            .iter()
+            .chain(self.local_secondary_indexes.iter());
+
+        let attributes = [Some(&self.partition_key), self.sort_key.as_ref()]
+            .into_iter()
+            .flatten()
+            .chain(indexes.flat_map(|index| {
+                [Some(&index.partition_key), index.sort_key.as_ref()]
+                    .into_iter()
+                    .flatten()
+            }));
+
+        let mut definitions: Vec<AttributeDefinition> = vec![];
+
+        for attribute in attributes {
+            if definitions
+                .iter()
+                .all(|def| def.attribute_name != attribute.name)
+            {
+                definitions.push(attribute.definition()?);
+            }
+        }
+
+        Ok(definitions)
+    }
+
+    /// Create [`CreateTableInput`] from the schema.
+    pub fn create_table_input(&self) -> Result<CreateTableInput, Error> {
+        self.create_table_input_builder()?
+            .build()
+            .map_err(|err| Error::Sdk(Box::new(err)))
+    }
+
+    /// Create the table, wait for it to become ACTIVE and enable Time to Live if the schema
+    /// has the TTL attribute.
+    pub async fn create_table(&self, client: &Client) -> Result<(), Error> {
+        self.create_table_input_builder()?
+            .send_with(client)
+            .await
+            .map_err(|err| Error::Sdk(Box::new(err)))?;
+
+        client
+            .wait_until_table_exists()
+            .table_name(&self.table_name)
+            .wait(MAX_WAIT)
+            .await
+            .map_err(|err| Error::Sdk(Box::new(err)))?;
+
+        if let Some(attribute) = self.ttl_attribute.as_ref() {
+            let specification = TimeToLiveSpecification::builder()
+                .attribute_name(attribute)
+                .enabled(true)
+                .build()
+                .map_err(|err| Error::Sdk(Box::new(err)))?;
+
+            client
+                .update_time_to_live()
+                .table_name(&self.table_name)
+                .time_to_live_specification(specification)
+                .send()
+                .await
+                .map_err(|err| Error::Sdk(Box::new(err)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Delete the table and wait for it to be deleted.
+    pub async fn delete_table(&self, client: &Client) -> Result<(), Error> {
+        client
+            .delete_table()
+            .table_name(&self.table_name)
+            .send()
+            .await
+            .map_err(|err| Error::Sdk(Box::new(err)))?;
+
+        client
+            .wait_until_table_not_exists()
+            .table_name(&self.table_name)
+            .wait(MAX_WAIT)
+            .await
+            .map_err(|err| Error::Sdk(Box::new(err)))?;
+
+        Ok(())
+    }
+
+    /// Blocking version of [`TableSchema::create_table`].
+    #[cfg(feature = "blocking")]
+    pub fn create_table_blocking(&self, client: &Client) -> Result<(), Error> {
+        block_on(self.create_table(client))?
+    }
+
+    /// Blocking version of [`TableSchema::delete_table`].
+    #[cfg(feature = "blocking")]
+    pub fn delete_table_blocking(&self, client: &Client) -> Result<(), Error> {
+        block_on(self.delete_table(client))?
+    }
+
+    fn create_table_input_builder(&self) -> Result<CreateTableInputBuilder, Error> {
+        let global_secondary_indexes = self
+            .global_secondary_indexes
+            .iter()
+            .map(IndexSchema::global)
+            .collect::<Result<Vec<GlobalSecondaryIndex>, Error>>()?;
+
+        let local_secondary_indexes = self
+            .local_secondary_indexes
+            .iter()
+            .map(IndexSchema::local)
+            .collect::<Result<Vec<LocalSecondaryIndex>, Error>>()?;
+
+        Ok(CreateTableInput::builder()
+            .table_name(&self.table_name)
+            .set_attribute_definitions(Some(self.attribute_definitions()?))
+            .set_key_schema(Some(key_schema(
+                &self.partition_key,
+                self.sort_key.as_ref(),
+            )?))
+            .set_global_secondary_indexes(
+                Some(global_secondary_indexes).filter(|indexes| !indexes.is_empty()),
+            )
+            .set_local_secondary_indexes(
+                Some(local_secondary_indexes).filter(|indexes| !indexes.is_empty()),
+            )
+            .billing_mode(self.billing_mode.clone())
+            .set_provisioned_throughput(self.provisioned_throughput.clone())
+            .set_stream_specification(self.stream_specification.clone()))
+    }
+}
+
+fn key_schema(
+    partition_key: &KeyAttribute,
+    sort_key: Option<&KeyAttribute>,
+) -> Result<Vec<KeySchemaElement>, Error> {
+    let mut elements = vec![partition_key.key_schema(KeyType::Hash)?];
+
+    if let Some(sort_key) = sort_key {
+        elements.push(sort_key.key_schema(KeyType::Range)?);
+    }
+
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_dynamodb::types::{AttributeValue, StreamViewType};
+
+    struct Entity;
+
+    impl<'a> DynamodbTable<'a> for Entity {
+        const TABLE_NAME: &'a str = "Entities";
+        const TTL_ATTRIBUTE: Option<&'a str> = Some("ttl");
+
+        type Key = EntityKey;
+
+        fn key_inputs(&self) -> (String, u64) {
+            ("foo".into(), 0)
+        }
+    }
+
+    impl<'a> Schema<'a> for Entity {
+        fn global_secondary_indexes() -> Vec<IndexSchema> {
+            vec![IndexSchema::from_index::<Gsi1>()]
+        }
+
+        fn local_secondary_indexes() -> Vec<IndexSchema> {
+            vec![IndexSchema::new::<Lsi1Key>("LSI1")]
+        }
+
+        fn stream_specification() -> Option<StreamSpecification> {
+            StreamSpecification::builder()
+                .stream_enabled(true)
+                .stream_view_type(StreamViewType::NewAndOldImages)
+                .build()
+                .ok()
+        }
+    }
+
+    struct EntityKey;
+
+    impl<'a> Key<'a> for EntityKey {
+        const PARTITION_KEY: &'a str = "pk";
+        const SORT_KEY: Option<&'a str> = Some("sk");
+        const SORT_KEY_TYPE: ScalarAttributeType = ScalarAttributeType::N;
+
+        type PartitionInput = String;
+        type SortInput = u64;
+
+        fn partition_key(input: String) -> AttributeValue {
+            AttributeValue::S(input)
+        }
+
+        fn sort_key(input: u64) -> Option<AttributeValue> {
+            Some(AttributeValue::N(input.to_string()))
+        }
+    }
+
+    struct Gsi1;
+
+    impl<'a> Index<'a> for Gsi1 {
+        const INDEX_NAME: &'a str = "GSI1";
+        const PROJECTION_TYPE: ProjectionType = ProjectionType::Include;
+        const NON_KEY_ATTRIBUTES: &'a [&'a str] = &["name"];
+
+        type Key = Gsi1Key;
+    }
+
+    struct Gsi1Key;
+
+    impl<'a> Key<'a> for Gsi1Key {
+        const PARTITION_KEY: &'a str = "gsi1pk";
+        const SORT_KEY: Option<&'a str> = Some("sk");
+        const SORT_KEY_TYPE: ScalarAttributeType = ScalarAttributeType::N;
+
+        type PartitionInput = String;
+        type SortInput = u64;
+
+        fn partition_key(input: String) -> AttributeValue {
+            AttributeValue::S(input)
+        }
+
+        fn sort_key(input: u64) -> Option<AttributeValue> {
+            Some(AttributeValue::N(input.to_string()))
+        }
+    }
+
+    struct Lsi1Key;
+
+    impl<'a> Key<'a> for Lsi1Key {
+        const PARTITION_KEY: &'a str = "pk";
+        const SORT_KEY: Option<&'a str> = Some("lsi1sk");
+
+        type PartitionInput = String;
+        type SortInput = String;
+
+        fn partition_key(input: String) -> AttributeValue {
+            AttributeValue::S(input)
+        }
+
+        fn sort_key(input: String) -> Option<AttributeValue> {
+            Some(AttributeValue::S(input))
+        }
+    }
+
+    fn attribute(name: &str, attribute_type: ScalarAttributeType) -> AttributeDefinition {
+        AttributeDefinition::builder()
+            .attribute_name(name)
+            .attribute_type(attribute_type)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn schema_is_derived_from_table_and_key() {
+        let schema = Entity::schema();
+        assert_eq!(schema.table_name, "Entities");
+        assert_eq!(
+            schema.partition_key,
+            KeyAttribute::new("pk", ScalarAttributeType::S)
+        );
+        assert_eq!(
+            schema.sort_key,
+            Some(KeyAttribute::new("sk", ScalarAttributeType::N))
+        );
+        assert_eq!(schema.billing_mode, BillingMode::PayPerRequest);
+        assert_eq!(schema.ttl_attribute, Some("ttl".into()));
+    }
+
+    #[test]
+    fn attribute_definitions_are_deduplicated() {
+        let definitions = Entity::schema().attribute_definitions().unwrap();
+        assert_eq!(
+            definitions,
+            vec![
+                attribute("pk", ScalarAttributeType::S),
+                attribute("sk", ScalarAttributeType::N),
+                attribute("gsi1pk", ScalarAttributeType::S),
+                attribute("lsi1sk", ScalarAttributeType::S),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_creates_create_table_input() {
+        let input = Entity::schema().create_table_input().unwrap();
+        assert_eq!(input.table_name(), Some("Entities"));
+        assert_eq!(input.billing_mode(), Some(&BillingMode::PayPerRequest));
+
+        let key_schema = input.key_schema();
+        assert_eq!(key_schema.len(), 2);
+        assert_eq!(key_schema[0].attribute_name(), "pk");
+        assert_eq!(key_schema[0].key_type(), &KeyType::Hash);
+        assert_eq!(key_schema[1].attribute_name(), "sk");
+        assert_eq!(key_schema[1].key_type(), &KeyType::Range);
+
+        let gsi = input.global_secondary_indexes();
+        assert_eq!(gsi.len(), 1);
+        assert_eq!(gsi[0].index_name(), "GSI1");
+        assert_eq!(gsi[0].key_schema()[0].attribute_name(), "gsi1pk");
+        let projection = gsi[0].projection().unwrap();
+        assert_eq!(projection.projection_type(), Some(&ProjectionType::Include));
+        assert_eq!(projection.non_key_attributes(), ["name".to_string()]);
+
+        let lsi = input.local_secondary_indexes();
+        assert_eq!(lsi.len(), 1);
+        assert_eq!(lsi[0].index_name(), "LSI1");
+        assert_eq!(lsi[0].key_schema()[1].attribute_name(), "lsi1sk");
+
+        let stream = input.stream_specification().unwrap();
+        assert!(stream.stream_enabled());
+    }
+
+    #[test]
+    fn index_schema_is_derived_from_index() {
+        let schema = IndexSchema::from_index::<Gsi1>();
+        assert_eq!(schema.name, "GSI1");
+        assert_eq!(
+            schema.partition_key,
+            KeyAttribute::new("gsi1pk", ScalarAttributeType::S)
+        );
+        assert_eq!(
+            schema.sort_key,
+            Some(KeyAttribute::new("sk", ScalarAttributeType::N))
+        );
+        assert_eq!(schema.projection_type, ProjectionType::Include);
+        assert_eq!(schema.non_key_attributes, Some(vec!["name".into()]));
+    }
+
+    #[test]
+    fn it_omits_empty_indexes() {
+        struct Simple;
+
+        impl<'a> DynamodbTable<'a> for Simple {
+            const TABLE_NAME: &'a str = "Simple";
+
+            type Key = Lsi1Key;
+
+            fn key_inputs(&self) -> (String, String) {
+                ("foo".into(), "bar".into())
+            }
+        }
+
+        impl<'a> Schema<'a> for Simple {}
+
+        let input = Simple::schema().create_table_input().unwrap();
+        assert!(input.global_secondary_indexes.is_none());
+        assert!(input.local_secondary_indexes.is_none());
+        assert!(input.stream_specification.is_none());
+    }
+}
@@ -0,0 +1,622 @@
+use super::{Error, IndexSchema, KeyAttribute, Schema, TableNameResolver, TableSchema};
+
+use aws_sdk_dynamodb::{
+    types::{
+        AttributeDefinition, KeySchemaElement, KeyType, Projection, ScalarAttributeType,
+        StreamSpecification, StreamViewType, TableDescription, TimeToLiveDescription,
+        TimeToLiveStatus,
+    },
+    Client,
+};
+use std::fmt;
+
+#[cfg(feature = "blocking")]
+use super::block_on;
+
+/// Compare the schema of the live table with the one declared by the type.
+///
+/// Call this at startup and fail fast if the returned diff is not empty, so that your
+/// service never runs against a table provisioned differently than the code expects. The table
+/// name is resolved with the resolver like [`TableSchema::resolve_table_name`], so pass the one
+/// of the backend, or [`AffixResolver::new`](crate::AffixResolver::new) if it has none.
+///
+/// ```no_run
+/// # use dynamo_mapper::{schema::{verify_schema, Schema}, AffixResolver, Error};
+/// # async fn run<T: for<'a> Schema<'a>>(client: aws_sdk_dynamodb::Client) -> Result<(), Error> {
+/// let resolver = AffixResolver::new().set_prefix("dev-");
+/// let diff = verify_schema::<T>(&client, &resolver).await?;
+/// assert!(diff.is_empty(), "{diff}");
+/// # Ok(())
+/// # }
+/// ```
+pub async fn verify_schema<'a, T: Schema<'a>>(
+    client: &Client,
+    resolver: &impl TableNameResolver,
+) -> Result<SchemaDiff, Error> {
+    T::schema()
+        .resolve_table_name(resolver)
+        .verify(client)
+        .await
+}
+
+impl TableSchema {
+    /// Call DescribeTable and DescribeTimeToLive, and compare the live table with the schema.
+    pub async fn verify(&self, client: &Client) -> Result<SchemaDiff, Error> {
+        let table = client
+            .describe_table()
+            .table_name(&self.table_name)
+            .send()
+            .await
+            .map_err(|err| Error::Sdk(Box::new(err)))?
+            .table
+            .unwrap_or_else(|| TableDescription::builder().build());
+
+        let ttl = client
+            .describe_time_to_live()
+            .table_name(&self.table_name)
+            .send()
+            .await
+            .map_err(|err| Error::Sdk(Box::new(err)))?
+            .time_to_live_description;
+
+        Ok(self.diff(&table, ttl.as_ref()))
+    }
+
+    /// Blocking version of [`TableSchema::verify`].
+    #[cfg(feature = "blocking")]
+    pub fn verify_blocking(&self, client: &Client) -> Result<SchemaDiff, Error> {
+        block_on(self.verify(client))?
+    }
+
+    /// Compare the described table with the schema.
+    pub fn diff(
+        &self,
+        table: &TableDescription,
+        ttl: Option<&TimeToLiveDescription>,
+    ) -> SchemaDiff {
+        let definitions = table.attribute_definitions();
+        let mut differences: Vec<SchemaDifference> = vec![];
+
+        let (partition_key, sort_key) = live_keys(table.key_schema(), definitions);
+        if partition_key.as_ref() != Some(&self.partition_key) {
+            differences.push(SchemaDifference::TableKey {
+                key_type: KeyType::Hash,
+                expected: Some(self.partition_key.clone()),
+                actual: partition_key,
+            });
+        }
+        if sort_key != self.sort_key {
+            differences.push(SchemaDifference::TableKey {
+                key_type: KeyType::Range,
+                expected: self.sort_key.clone(),
+                actual: sort_key,
+            });
+        }
+
+        let global_indexes = table
+            .global_secondary_indexes()
+            .iter()
+            .map(|index| {
+                live_index(
+                    index.index_name(),
+                    index.key_schema(),
+                    index.projection(),
+                    definitions,
+                )
+            })
+            .collect::<Vec<LiveIndex>>();
+        diff_indexes(
+            IndexKind::Global,
+            &self.global_secondary_indexes,
+            &global_indexes,
+            &mut differences,
+        );
+
+        let local_indexes = table
+            .local_secondary_indexes()
+            .iter()
+            .map(|index| {
+                live_index(
+                    index.index_name(),
+                    index.key_schema(),
+                    index.projection(),
+                    definitions,
+                )
+            })
+            .collect::<Vec<LiveIndex>>();
+        diff_indexes(
+            IndexKind::Local,
+            &self.local_secondary_indexes,
+            &local_indexes,
+            &mut differences,
+        );
+
+        let ttl_attribute = ttl
+            .filter(|ttl| {
+                matches!(
+                    ttl.time_to_live_status(),
+                    Some(TimeToLiveStatus::Enabled | TimeToLiveStatus::Enabling)
+                )
+            })
+            .and_then(|ttl| ttl.attribute_name())
+            .map(String::from);
+        if ttl_attribute != self.ttl_attribute {
+            differences.push(SchemaDifference::TimeToLive {
+                expected: self.ttl_attribute.clone(),
+                actual: ttl_attribute,
+            });
+        }
+
+        let expected_stream = stream_view_type(self.stream_specification.as_ref());
+        let actual_stream = stream_view_type(table.stream_specification());
+        if expected_stream != actual_stream {
+            differences.push(SchemaDifference::Stream {
+                expected: expected_stream,
+                actual: actual_stream,
+            });
+        }
+
+        SchemaDiff {
+            table_name: self.table_name.clone(),
+            differences,
+        }
+    }
+}
+
+/// Result of comparing the declared schema with the live table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaDiff {
+    pub table_name: String,
+    pub differences: Vec<SchemaDifference>,
+}
+
+impl SchemaDiff {
+    /// Return true if the live table matches the declared schema.
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "table {} matches the schema", self.table_name);
+        }
+
+        write!(f, "table {} differs from the schema:", self.table_name)?;
+        for difference in self.differences.iter() {
+            write!(f, "\n  - {difference}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Kind of the secondary index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexKind {
+    Global,
+    Local,
+}
+
+impl fmt::Display for IndexKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Global => write!(f, "global secondary index"),
+            Self::Local => write!(f, "local secondary index"),
+        }
+    }
+}
+
+/// A difference between the declared schema and the live table.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaDifference {
+    /// The partition key (`HASH`) or the sort key (`RANGE`) of the table differs.
+    TableKey {
+        key_type: KeyType,
+        expected: Option<KeyAttribute>,
+        actual: Option<KeyAttribute>,
+    },
+
+    /// The index is declared in the schema but does not exist on the table.
+    MissingIndex { kind: IndexKind, name: String },
+
+    /// The index exists on the table but is not declared in the schema.
+    UndeclaredIndex { kind: IndexKind, name: String },
+
+    /// The partition key (`HASH`) or the sort key (`RANGE`) of the index differs.
+    IndexKey {
+        kind: IndexKind,
+        name: String,
+        key_type: KeyType,
+        expected: Option<KeyAttribute>,
+        actual: Option<KeyAttribute>,
+    },
+
+    /// The projection of the index differs.
+    IndexProjection {
+        kind: IndexKind,
+        name: String,
+        expected: Projection,
+        actual: Option<Projection>,
+    },
+
+    /// The Time to Live attribute differs. None means Time to Live is disabled.
+    TimeToLive {
+        expected: Option<String>,
+        actual: Option<String>,
+    },
+
+    /// The stream view type differs. None means the stream is disabled.
+    Stream {
+        expected: Option<StreamViewType>,
+        actual: Option<StreamViewType>,
+    },
+}
+
+impl fmt::Display for SchemaDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TableKey {
+                key_type,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} key: expected {}, actual {}",
+                key_type.as_str(),
+                DisplayKey(expected.as_ref()),
+                DisplayKey(actual.as_ref())
+            ),
+            Self::MissingIndex { kind, name } => write!(f, "{kind} {name} does not exist"),
+            Self::UndeclaredIndex { kind, name } => write!(f, "{kind} {name} is not declared"),
+            Self::IndexKey {
+                kind,
+                name,
+                key_type,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{kind} {name} {} key: expected {}, actual {}",
+                key_type.as_str(),
+                DisplayKey(expected.as_ref()),
+                DisplayKey(actual.as_ref())
+            ),
+            Self::IndexProjection {
+                kind,
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{kind} {name} projection: expected {expected:?}, actual {actual:?}"
+            ),
+            Self::TimeToLive { expected, actual } => {
+                write!(f, "TTL attribute: expected {expected:?}, actual {actual:?}")
+            }
+            Self::Stream { expected, actual } => {
+                write!(
+                    f,
+                    "stream view type: expected {expected:?}, actual {actual:?}"
+                )
+            }
+        }
+    }
+}
+
+struct DisplayKey<'a>(Option<&'a KeyAttribute>);
+
+impl fmt::Display for DisplayKey<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(key) => write!(f, "{} ({})", key.name, key.attribute_type.as_str()),
+            None => write!(f, "none"),
+        }
+    }
+}
+
+struct LiveIndex {
+    name: String,
+    partition_key: Option<KeyAttribute>,
+    sort_key: Option<KeyAttribute>,
+    projection: Option<Projection>,
+}
+
+fn live_keys(
+    key_schema: &[KeySchemaElement],
+    definitions: &[AttributeDefinition],
+) -> (Option<KeyAttribute>, Option<KeyAttribute>) {
+    let key = |key_type: KeyType| {
+        key_schema
+            .iter()
+            .find(|element| element.key_type() == &key_type)
+            .map(|element| {
+                let attribute_type = definitions
+                    .iter()
+                    .find(|def| def.attribute_name() == element.attribute_name())
+                    .map(|def| def.attribute_type().clone())
+                    .unwrap_or_else(|| ScalarAttributeType::from("UNKNOWN"));
+                KeyAttribute::new(element.attribute_name(), attribute_type)
+            })
+    };
+
+    (key(KeyType::Hash), key(KeyType::Range))
+}
+
+fn live_index(
+    name: Option<&str>,
+    key_schema: &[KeySchemaElement],
+    projection: Option<&Projection>,
+    definitions: &[AttributeDefinition],
+) -> LiveIndex {
+    let (partition_key, sort_key) = live_keys(key_schema, definitions);
+    LiveIndex {
+        name: name.unwrap_or_default().into(),
+        partition_key,
+        sort_key,
+        projection: projection.map(normalize_projection),
+    }
+}
+
+fn diff_indexes(
+    kind: IndexKind,
+    expected: &[IndexSchema],
+    actual: &[LiveIndex],
+    differences: &mut Vec<SchemaDifference>,
+) {
+    for index in expected.iter() {
+        let Some(live) = actual.iter().find(|live| live.name == index.name) else {
+            differences.push(SchemaDifference::MissingIndex {
+                kind,
+                name: index.name.clone(),
+            });
+            continue;
+        };
+
+        if live.partition_key.as_ref() != Some(&index.partition_key) {
+            differences.push(SchemaDifference::IndexKey {
+                kind,
+                name: index.name.clone(),
+                key_type: KeyType::Hash,
+                expected: Some(index.partition_key.clone()),
+                actual: live.partition_key.clone(),
+            });
+        }
+
+        if live.sort_key != index.sort_key {
+            differences.push(SchemaDifference::IndexKey {
+                kind,
+                name: index.name.clone(),
+                key_type: KeyType::Range,
+                expected: index.sort_key.clone(),
+                actual: live.sort_key.clone(),
+            });
+        }
+
+        let projection = normalize_projection(&index.projection());
+        if live.projection.as_ref() != Some(&projection) {
+            differences.push(SchemaDifference::IndexProjection {
+                kind,
+                name: index.name.clone(),
+                expected: projection,
+                actual: live.projection.clone(),
+            });
+        }
+    }
+
+    for live in actual.iter() {
+        if expected.iter().all(|index| index.name != live.name) {
+            differences.push(SchemaDifference::UndeclaredIndex {
+                kind,
+                name: live.name.clone(),
+            });
+        }
+    }
+}
+
+fn normalize_projection(projection: &Projection) -> Projection {
+    let mut attributes = projection.non_key_attributes().to_vec();
+    attributes.sort();
+
+    Projection::builder()
+        .set_projection_type(projection.projection_type().cloned())
+        .set_non_key_attributes(Some(attributes).filter(|attributes| !attributes.is_empty()))
+        .build()
+}
+
+fn stream_view_type(specification: Option<&StreamSpecification>) -> Option<StreamViewType> {
+    specification
+        .filter(|spec| spec.stream_enabled())
+        .and_then(|spec| spec.stream_view_type().cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_dynamodb::types::{
+        BillingMode, GlobalSecondaryIndexDescription, LocalSecondaryIndexDescription,
+        ProjectionType,
+    };
+
+    fn schema() -> TableSchema {
+        TableSchema {
+            table_name: "Entities".into(),
+            partition_key: KeyAttribute::new("pk", ScalarAttributeType::S),
+            sort_key: Some(KeyAttribute::new("sk", ScalarAttributeType::S)),
+            global_secondary_indexes: vec![IndexSchema {
+                name: "GSI1".into(),
+                partition_key: KeyAttribute::new("gsi1pk", ScalarAttributeType::S),
+                sort_key: None,
+                projection_type: ProjectionType::Include,
+                non_key_attributes: Some(vec!["name".into(), "age".into()]),
+                provisioned_throughput: None,
+            }],
+            local_secondary_indexes: vec![],
+            billing_mode: BillingMode::PayPerRequest,
+            provisioned_throughput: None,
+            stream_specification: None,
+            ttl_attribute: Some("ttl".into()),
+        }
+    }
+
+    fn definition(name: &str, attribute_type: ScalarAttributeType) -> AttributeDefinition {
+        AttributeDefinition::builder()
+            .attribute_name(name)
+            .attribute_type(attribute_type)
+            .build()
+            .unwrap()
+    }
+
+    fn element(name: &str, key_type: KeyType) -> KeySchemaElement {
+        KeySchemaElement::builder()
+            .attribute_name(name)
+            .key_type(key_type)
+            .build()
+            .unwrap()
+    }
+
+    fn ttl(attribute: &str) -> TimeToLiveDescription {
+        TimeToLiveDescription::builder()
+            .attribute_name(attribute)
+            .time_to_live_status(TimeToLiveStatus::Enabled)
+            .build()
+    }
+
+    fn table() -> TableDescription {
+        TableDescription::builder()
+            .table_name("Entities")
+            .attribute_definitions(definition("pk", ScalarAttributeType::S))
+            .attribute_definitions(definition("sk", ScalarAttributeType::S))
+            .attribute_definitions(definition("gsi1pk", ScalarAttributeType::S))
+            .key_schema(element("pk", KeyType::Hash))
+            .key_schema(element("sk", KeyType::Range))
+            .global_secondary_indexes(
+                GlobalSecondaryIndexDescription::builder()
+                    .index_name("GSI1")
+                    .key_schema(element("gsi1pk", KeyType::Hash))
+                    .projection(
+                        Projection::builder()
+                            .projection_type(ProjectionType::Include)
+                            .non_key_attributes("age")
+                            .non_key_attributes("name")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .build()
+    }
+
+    #[test]
+    fn diff_is_empty_if_table_matches_schema() {
+        let diff = schema().diff(&table(), Some(&ttl("ttl")));
+        assert!(diff.is_empty(), "{diff}");
+    }
+
+    #[test]
+    fn diff_detects_table_key_differences() {
+        let table = TableDescription::builder()
+            .attribute_definitions(definition("pk", ScalarAttributeType::N))
+            .key_schema(element("pk", KeyType::Hash))
+            .global_secondary_indexes(table().global_secondary_indexes()[0].clone())
+            .attribute_definitions(definition("gsi1pk", ScalarAttributeType::S))
+            .build();
+
+        let diff = schema().diff(&table, Some(&ttl("ttl")));
+        assert_eq!(
+            diff.differences,
+            vec![
+                SchemaDifference::TableKey {
+                    key_type: KeyType::Hash,
+                    expected: Some(KeyAttribute::new("pk", ScalarAttributeType::S)),
+                    actual: Some(KeyAttribute::new("pk", ScalarAttributeType::N)),
+                },
+                SchemaDifference::TableKey {
+                    key_type: KeyType::Range,
+                    expected: Some(KeyAttribute::new("sk", ScalarAttributeType::S)),
+                    actual: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_detects_index_differences() {
+        let mut schema = schema();
+        schema.global_secondary_indexes[0].projection_type = ProjectionType::All;
+        schema.global_secondary_indexes[0].non_key_attributes = None;
+        schema.local_secondary_indexes.push(IndexSchema {
+            name: "LSI1".into(),
+            partition_key: KeyAttribute::new("pk", ScalarAttributeType::S),
+            sort_key: Some(KeyAttribute::new("lsi1sk", ScalarAttributeType::S)),
+            projection_type: ProjectionType::All,
+            non_key_attributes: None,
+            provisioned_throughput: None,
+        });
+
+        let mut table = table();
+        table.local_secondary_indexes = Some(vec![LocalSecondaryIndexDescription::builder()
+            .index_name("LSI2")
+            .key_schema(element("pk", KeyType::Hash))
+            .build()]);
+
+        let diff = schema.diff(&table, Some(&ttl("ttl")));
+        assert_eq!(diff.differences.len(), 3);
+        assert!(matches!(
+            diff.differences[0],
+            SchemaDifference::IndexProjection {
+                kind: IndexKind::Global,
+                ..
+            }
+        ));
+        assert_eq!(
+            diff.differences[1],
+            SchemaDifference::MissingIndex {
+                kind: IndexKind::Local,
+                name: "LSI1".into(),
+            }
+        );
+        assert_eq!(
+            diff.differences[2],
+            SchemaDifference::UndeclaredIndex {
+                kind: IndexKind::Local,
+                name: "LSI2".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn diff_detects_ttl_and_stream_differences() {
+        let mut schema = schema();
+        schema.stream_specification = StreamSpecification::builder()
+            .stream_enabled(true)
+            .stream_view_type(StreamViewType::NewImage)
+            .build()
+            .ok();
+
+        let disabled = TimeToLiveDescription::builder()
+            .attribute_name("ttl")
+            .time_to_live_status(TimeToLiveStatus::Disabled)
+            .build();
+
+        let diff = schema.diff(&table(), Some(&disabled));
+        assert_eq!(
+            diff.differences,
+            vec![
+                SchemaDifference::TimeToLive {
+                    expected: Some("ttl".into()),
+                    actual: None,
+                },
+                SchemaDifference::Stream {
+                    expected: Some(StreamViewType::NewImage),
+                    actual: None,
+                },
+            ]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "table Entities differs from the schema:\n  \
+             - TTL attribute: expected Some(\"ttl\"), actual None\n  \
+             - stream view type: expected Some(NewImage), actual None"
+        );
+    }
+}
@@ -0,0 +1,407 @@
+use super::{helpers::json::item_from_json, BoxError, Error, Item};
+
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodbstreams::types::{
+    AttributeValue as StreamAttributeValue, OperationType, Record,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A change of the item captured by DynamoDB Streams, decoded into your object.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent<T> {
+    /// A new item was added to the table.
+    Insert { new: T },
+
+    /// One or more attributes of the item were updated.
+    Modify { old: T, new: T },
+
+    /// The item was deleted from the table.
+    Remove { old: T },
+}
+
+/// The type of the change captured by DynamoDB Streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventName {
+    Insert,
+    Modify,
+    Remove,
+}
+
+impl TryFrom<&str> for EventName {
+    type Error = Error;
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        match name {
+            "INSERT" => Ok(Self::Insert),
+            "MODIFY" => Ok(Self::Modify),
+            "REMOVE" => Ok(Self::Remove),
+            _ => Err(Error::Stream(format!("unknown event name {name}"))),
+        }
+    }
+}
+
+/// A DynamoDB Streams record independent of where it comes from.
+///
+/// You can get this from the [`Record`] of `aws_sdk_dynamodbstreams` or from the event JSON
+/// which AWS Lambda receives, then decode it into a [`StreamEvent`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamRecord {
+    pub event_id: Option<String>,
+    pub event_name: EventName,
+    pub keys: Item,
+    pub old_image: Option<Item>,
+    pub new_image: Option<Item>,
+    pub sequence_number: Option<String>,
+}
+
+impl StreamRecord {
+    /// Decode the images into your object.
+    ///
+    /// Fail if the image the event requires is not in the record, for example when the
+    /// `StreamViewType` of the table is `KEYS_ONLY`. If the table stores multiple entities,
+    /// decode the record into an enum implementing [`EntityEnum`](crate::EntityEnum) to
+    /// dispatch to the right entity.
+    pub fn decode<T>(self) -> Result<StreamEvent<T>, Error>
+    where
+        T: TryFrom<Item, Error = BoxError>,
+    {
+        let Self {
+            event_name,
+            old_image,
+            new_image,
+            ..
+        } = self;
+
+        let image = |image: Option<Item>, kind: &str| {
+            image
+                .ok_or_else(|| Error::Stream(format!("{kind} image is not in the record")))
+                .and_then(|item| T::try_from(item).map_err(Error::Conversion))
+        };
+
+        match event_name {
+            EventName::Insert => Ok(StreamEvent::Insert {
+                new: image(new_image, "new")?,
+            }),
+            EventName::Modify => Ok(StreamEvent::Modify {
+                old: image(old_image, "old")?,
+                new: image(new_image, "new")?,
+            }),
+            EventName::Remove => Ok(StreamEvent::Remove {
+                old: image(old_image, "old")?,
+            }),
+        }
+    }
+
+    /// Parse all the records of the DynamoDB Streams event JSON which AWS Lambda receives.
+    ///
+    /// ```
+    /// # use dynamo_mapper::streams::{EventName, StreamRecord};
+    /// let event = r#"{
+    ///     "Records": [{
+    ///         "eventID": "1",
+    ///         "eventName": "INSERT",
+    ///         "dynamodb": {
+    ///             "Keys": { "pk": { "S": "SHOP#1" } },
+    ///             "NewImage": { "pk": { "S": "SHOP#1" }, "name": { "S": "ShoesShop" } },
+    ///             "SequenceNumber": "111"
+    ///         }
+    ///     }]
+    /// }"#;
+    ///
+    /// let records = StreamRecord::from_lambda_event(event).unwrap();
+    /// assert_eq!(records.len(), 1);
+    /// assert_eq!(records[0].event_name, EventName::Insert);
+    /// ```
+    pub fn from_lambda_event(json: &str) -> Result<Vec<Self>, Error> {
+        let event: Value =
+            serde_json::from_str(json).map_err(|err| Error::Stream(err.to_string()))?;
+
+        event
+            .get("Records")
+            .and_then(Value::as_array)
+            .ok_or_else(|| Error::Stream("Records is not in the event".into()))?
+            .iter()
+            .map(Self::try_from)
+            .collect()
+    }
+}
+
+impl TryFrom<Record> for StreamRecord {
+    type Error = Error;
+
+    fn try_from(record: Record) -> Result<Self, Self::Error> {
+        let event_name = match record.event_name() {
+            Some(OperationType::Insert) => EventName::Insert,
+            Some(OperationType::Modify) => EventName::Modify,
+            Some(OperationType::Remove) => EventName::Remove,
+            other => {
+                return Err(Error::Stream(format!("unknown event name {other:?}")));
+            }
+        };
+
+        let stream = record
+            .dynamodb
+            .ok_or_else(|| Error::Stream("dynamodb is not in the record".into()))?;
+
+        Ok(Self {
+            event_id: record.event_id,
+            event_name,
+            keys: stream
+                .keys
+                .map(from_stream_item)
+                .transpose()?
+                .unwrap_or_default(),
+            old_image: stream.old_image.map(from_stream_item).transpose()?,
+            new_image: stream.new_image.map(from_stream_item).transpose()?,
+            sequence_number: stream.sequence_number,
+        })
+    }
+}
+
+impl TryFrom<&Value> for StreamRecord {
+    type Error = Error;
+
+    fn try_from(record: &Value) -> Result<Self, Self::Error> {
+        let event_name = record
+            .get("eventName")
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::Stream("eventName is not in the record".into()))
+            .and_then(EventName::try_from)?;
+
+        let stream = record
+            .get("dynamodb")
+            .and_then(Value::as_object)
+            .ok_or_else(|| Error::Stream("dynamodb is not in the record".into()))?;
+
+        let image = |key: &str| {
+            stream
+                .get(key)
+                .map(|value| {
+                    value
+                        .as_object()
+                        .ok_or_else(|| Error::Stream(format!("{key} is not an object")))
+                        .and_then(|item| {
+                            item_from_json(item).map_err(|err| Error::Stream(err.to_string()))
+                        })
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            event_id: record
+                .get("eventID")
+                .and_then(Value::as_str)
+                .map(String::from),
+            event_name,
+            keys: image("Keys")?.unwrap_or_default(),
+            old_image: image("OldImage")?,
+            new_image: image("NewImage")?,
+            sequence_number: stream
+                .get("SequenceNumber")
+                .and_then(Value::as_str)
+                .map(String::from),
+        })
+    }
+}
+
+fn from_stream_item(item: HashMap<String, StreamAttributeValue>) -> Result<Item, Error> {
+    item.into_iter()
+        .map(|(key, value)| from_stream_value(value).map(|value| (key, value)))
+        .collect()
+}
+
+fn from_stream_value(value: StreamAttributeValue) -> Result<AttributeValue, Error> {
+    let value = match value {
+        StreamAttributeValue::B(v) => AttributeValue::B(v),
+        StreamAttributeValue::Bool(v) => AttributeValue::Bool(v),
+        StreamAttributeValue::Bs(v) => AttributeValue::Bs(v),
+        StreamAttributeValue::L(v) => AttributeValue::L(
+            v.into_iter()
+                .map(from_stream_value)
+                .collect::<Result<Vec<AttributeValue>, Error>>()?,
+        ),
+        StreamAttributeValue::M(v) => AttributeValue::M(from_stream_item(v)?),
+        StreamAttributeValue::N(v) => AttributeValue::N(v),
+        StreamAttributeValue::Ns(v) => AttributeValue::Ns(v),
+        StreamAttributeValue::Null(v) => AttributeValue::Null(v),
+        StreamAttributeValue::S(v) => AttributeValue::S(v),
+        StreamAttributeValue::Ss(v) => AttributeValue::Ss(v),
+        other => {
+            return Err(Error::Stream(format!("unknown attribute value {other:?}")));
+        }
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::attribute_value::AttributeMap;
+    use aws_sdk_dynamodb::primitives::Blob;
+    use aws_sdk_dynamodbstreams::types::StreamRecord as SdkStreamRecord;
+
+    #[derive(Debug, PartialEq)]
+    struct Shop {
+        id: String,
+        name: String,
+    }
+
+    impl TryFrom<Item> for Shop {
+        type Error = BoxError;
+
+        fn try_from(item: Item) -> Result<Self, Self::Error> {
+            let map = AttributeMap::from(item);
+            Ok(Shop {
+                id: map.s("id").ok_or("no id")?.into(),
+                name: map.s("name").ok_or("no name")?.into(),
+            })
+        }
+    }
+
+    fn shop(name: &str) -> Shop {
+        Shop {
+            id: "1".into(),
+            name: name.into(),
+        }
+    }
+
+    const MODIFY_EVENT: &str = r#"{
+        "Records": [{
+            "eventID": "c4ca4238a0b923820dcc509a6f75849b",
+            "eventName": "MODIFY",
+            "eventSource": "aws:dynamodb",
+            "dynamodb": {
+                "Keys": { "pk": { "S": "SHOP#1" } },
+                "OldImage": {
+                    "pk": { "S": "SHOP#1" },
+                    "id": { "S": "1" },
+                    "name": { "S": "ShoesShop" }
+                },
+                "NewImage": {
+                    "pk": { "S": "SHOP#1" },
+                    "id": { "S": "1" },
+                    "name": { "S": "BagShop" },
+                    "visits": { "N": "10" },
+                    "open": { "BOOL": true },
+                    "closedOn": { "NULL": true },
+                    "tags": { "SS": ["bag", "shoes"] },
+                    "logo": { "B": "aGVsbG8=" },
+                    "staff": { "L": [{ "M": { "name": { "S": "Tanaka" } } }] }
+                },
+                "SequenceNumber": "222",
+                "StreamViewType": "NEW_AND_OLD_IMAGES"
+            }
+        }]
+    }"#;
+
+    #[test]
+    fn it_parses_lambda_event() {
+        let records = StreamRecord::from_lambda_event(MODIFY_EVENT).unwrap();
+        assert_eq!(records.len(), 1);
+
+        let record = &records[0];
+        assert_eq!(
+            record.event_id.as_deref(),
+            Some("c4ca4238a0b923820dcc509a6f75849b")
+        );
+        assert_eq!(record.event_name, EventName::Modify);
+        assert_eq!(record.sequence_number.as_deref(), Some("222"));
+        assert_eq!(
+            record.keys,
+            AttributeMap::new().set_s("pk", "SHOP#1").into_item()
+        );
+
+        let new_image = AttributeMap::from(record.new_image.clone().unwrap());
+        assert_eq!(new_image.n("visits"), Some(&"10".to_string()));
+        assert_eq!(new_image.bool("open"), Some(&true));
+        assert_eq!(new_image.null("closedOn"), Some(&true));
+        assert_eq!(
+            new_image.ss("tags"),
+            Some(&vec!["bag".to_string(), "shoes".to_string()])
+        );
+        assert_eq!(new_image.b("logo"), Some(&Blob::new("hello")));
+        assert_eq!(
+            new_image.l("staff"),
+            Some(&vec![AttributeMap::new().set_s("name", "Tanaka").into_m()])
+        );
+    }
+
+    #[test]
+    fn it_decodes_modify_event() {
+        let record = StreamRecord::from_lambda_event(MODIFY_EVENT)
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            record.decode::<Shop>().unwrap(),
+            StreamEvent::Modify {
+                old: shop("ShoesShop"),
+                new: shop("BagShop"),
+            }
+        );
+    }
+
+    #[test]
+    fn it_fails_to_decode_without_image() {
+        let event = r#"{
+            "Records": [{
+                "eventName": "REMOVE",
+                "dynamodb": { "Keys": { "pk": { "S": "SHOP#1" } } }
+            }]
+        }"#;
+
+        let record = StreamRecord::from_lambda_event(event).unwrap().remove(0);
+        assert!(matches!(record.decode::<Shop>(), Err(Error::Stream(_))));
+    }
+
+    #[test]
+    fn it_fails_to_parse_invalid_event() {
+        let event = r#"{ "Records": [{ "eventName": "UNKNOWN", "dynamodb": {} }] }"#;
+        assert!(StreamRecord::from_lambda_event(event).is_err());
+
+        let event = r#"{
+            "Records": [{
+                "eventName": "INSERT",
+                "dynamodb": { "NewImage": { "id": { "X": "1" } } }
+            }]
+        }"#;
+        assert!(StreamRecord::from_lambda_event(event).is_err());
+    }
+
+    #[test]
+    fn it_converts_sdk_record() {
+        let image: HashMap<String, StreamAttributeValue> = [
+            ("id".to_string(), StreamAttributeValue::S("1".into())),
+            (
+                "name".to_string(),
+                StreamAttributeValue::S("ShoesShop".into()),
+            ),
+        ]
+        .into();
+
+        let record = Record::builder()
+            .event_id("1")
+            .event_name(OperationType::Insert)
+            .dynamodb(
+                SdkStreamRecord::builder()
+                    .keys("id", StreamAttributeValue::S("1".into()))
+                    .set_new_image(Some(image))
+                    .sequence_number("111")
+                    .build(),
+            )
+            .build();
+
+        let record = StreamRecord::try_from(record).unwrap();
+        assert_eq!(record.event_name, EventName::Insert);
+        assert_eq!(
+            record.keys,
+            AttributeMap::new().set_s("id", "1").into_item()
+        );
+        assert_eq!(
+            record.decode::<Shop>().unwrap(),
+            StreamEvent::Insert {
+                new: shop("ShoesShop"),
+            }
+        );
+    }
+}
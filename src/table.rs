@@ -1,4 +1,6 @@
-use aws_sdk_dynamodb::types::AttributeValue;
+use super::{BoxError, Item};
+
+use aws_sdk_dynamodb::types::{AttributeValue, ProjectionType, ScalarAttributeType};
 use std::collections::HashMap;
 
 /// A builder for the DynamoDB table keys like partition key or sort key.
@@ -9,6 +11,12 @@ pub trait Key<'a> {
     /// Attribute name for sort key
     const SORT_KEY: Option<&'a str>;
 
+    /// Attribute type for partition key. Default is String.
+    const PARTITION_KEY_TYPE: ScalarAttributeType = ScalarAttributeType::S;
+
+    /// Attribute type for sort key. Default is String.
+    const SORT_KEY_TYPE: ScalarAttributeType = ScalarAttributeType::S;
+
     /// Input types to get partition key.
     type PartitionInput;
 
@@ -38,15 +46,159 @@ pub trait Key<'a> {
     }
 }
 
+/// A [`Key`] which parses the key values back into the inputs, so that you can get the typed
+/// inputs from `last_evaluated_key` or stream records.
+///
+/// [`KeyTemplate`](crate::helpers::key_template::KeyTemplate) helps to implement both of
+/// [`Key`] and this trait.
+pub trait ParseKey<'a>: Key<'a> {
+    /// Define how to parse partition key value.
+    fn parse_partition_key(value: &AttributeValue) -> Result<Self::PartitionInput, BoxError>;
+
+    /// Define how to parse sort key value.
+    ///
+    /// If the table doesn't have the sort key, the value is None.
+    fn parse_sort_key(value: Option<&AttributeValue>) -> Result<Self::SortInput, BoxError>;
+
+    /// Parse the primary key of the item into the inputs.
+    fn parse_key(item: &Item) -> Result<(Self::PartitionInput, Self::SortInput), BoxError> {
+        let pk = item
+            .get(Self::PARTITION_KEY)
+            .ok_or_else(|| format!("partition key {} is not in the item", Self::PARTITION_KEY))?;
+        let sk = match Self::SORT_KEY {
+            Some(attr) => Some(
+                item.get(attr)
+                    .ok_or_else(|| format!("sort key {attr} is not in the item"))?,
+            ),
+            None => None,
+        };
+        Ok((Self::parse_partition_key(pk)?, Self::parse_sort_key(sk)?))
+    }
+}
+
+/// A partial input of the sort key of [`Key`], which is used as the `begins_with` condition.
+///
+/// Implement this trait to your type for each prefix, so that the prefix is checked against
+/// the key of the query.
+///
+/// ```
+/// # use aws_sdk_dynamodb::types::AttributeValue;
+/// # use dynamo_mapper::{helpers::key_template::KeyTemplate, Key, SortKeyPrefix};
+/// const SHOP_KEY: KeyTemplate = KeyTemplate::new("SHOP#{id}");
+/// const STAFF_KEY: KeyTemplate = KeyTemplate::new("STAFF#{id}");
+///
+/// struct ShopKey;
+///
+/// impl<'a> Key<'a> for ShopKey {
+///     const PARTITION_KEY: &'a str = "pk";
+///     const SORT_KEY: Option<&'a str> = Some("sk");
+///
+///     type PartitionInput = String;
+///     type SortInput = String;
+///
+///     fn partition_key(input: Self::PartitionInput) -> AttributeValue {
+///         SHOP_KEY.render(&input)
+///     }
+///
+///     fn sort_key(input: Self::SortInput) -> Option<AttributeValue> {
+///         Some(SHOP_KEY.render(&input))
+///     }
+/// }
+///
+/// /// All the staff of the shop.
+/// struct StaffPrefix;
+///
+/// impl<'a> SortKeyPrefix<'a, ShopKey> for StaffPrefix {
+///     fn sort_key_prefix(self) -> AttributeValue {
+///         STAFF_KEY.render_prefix(&())
+///     }
+/// }
+///
+/// assert_eq!(StaffPrefix.sort_key_prefix(), AttributeValue::S("STAFF#".into()));
+/// ```
+pub trait SortKeyPrefix<'a, K: Key<'a>> {
+    /// Define how to create the prefix of the sort key value.
+    fn sort_key_prefix(self) -> AttributeValue;
+}
+
+/// The raw value is used as the prefix as it is.
+impl<'a, K: Key<'a>> SortKeyPrefix<'a, K> for AttributeValue {
+    fn sort_key_prefix(self) -> AttributeValue {
+        self
+    }
+}
+
+/// Represents a secondary index of the DynamoDB table.
+///
+/// The index has its own [`Key`], so that you can query the index with its key attributes
+/// instead of the ones of the base table.
+pub trait Index<'a> {
+    /// The index name.
+    const INDEX_NAME: &'a str;
+
+    /// A KeyBuilder type of the index.
+    type Key: Key<'a>;
+
+    /// Attributes projected into the index. Default is `ALL`.
+    const PROJECTION_TYPE: ProjectionType = ProjectionType::All;
+
+    /// Non-key attributes projected into the index.
+    ///
+    /// You should overwrite this constant only if [`Index::PROJECTION_TYPE`] is `INCLUDE`.
+    const NON_KEY_ATTRIBUTES: &'a [&'a str] = &[];
+
+    /// Whether the index is a local secondary index. Default is false.
+    ///
+    /// Only local secondary indexes support the strongly consistent reads.
+    const LOCAL: bool = false;
+}
+
 /// Represents DynamoDB Table and you should implement this trait to the object to which you map
 /// the DyanmoDB table.
 pub trait DynamodbTable<'a> {
     /// The DynamoDB table name.
+    ///
+    /// The operations send requests to the name resolved by the
+    /// [`TableNameResolver`](crate::TableNameResolver) of the backend if it has one. See
+    /// [`DynamodbTable::table_name`].
     const TABLE_NAME: &'a str;
 
     /// A KeyBuilder type.
     type Key: Key<'a>;
 
+    /// Attribute name of the Time to Live attribute.
+    /// Default is None.
+    ///
+    /// You should overwrite this constant only if the table uses Time to Live. Then the items
+    /// which have already expired but DynamoDB has not deleted yet are filtered out on read.
+    const TTL_ATTRIBUTE: Option<&'a str> = None;
+
+    /// The entity type of the object in a single-table design.
+    /// Default is None.
+    ///
+    /// If you overwrite this constant, [`PutItem`](crate::operations::put_item::PutItem) writes
+    /// it into [`DynamodbTable::ENTITY_TYPE_ATTRIBUTE`], [`Query`](crate::operations::query::Query)
+    /// filters the items by it and the items of other entities fail to be decoded.
+    const ENTITY_TYPE: Option<&'a str> = None;
+
+    /// Attribute name of the entity type. Default is `_et`.
+    const ENTITY_TYPE_ATTRIBUTE: &'a str = "_et";
+
+    /// Whether to hide the key values in the spans of the `tracing` feature. Default is false.
+    ///
+    /// You should overwrite this constant if the keys contain personal or secret data. Then
+    /// only the key attribute names are recorded.
+    const REDACT_KEY: bool = false;
+
+    /// Return the table name before the backend resolves it. Default is
+    /// [`DynamodbTable::TABLE_NAME`].
+    ///
+    /// You should overwrite this method only if the table name is decided at runtime
+    /// differently from the other tables.
+    fn table_name() -> String {
+        Self::TABLE_NAME.into()
+    }
+
     /// Create inputs for partition key and sort key from the instance.
     fn key_inputs(
         &self,
@@ -0,0 +1,189 @@
+use crate::{interceptor::Decoded, retry::RetryPolicy, BoxError, DynamoBackend};
+
+use aws_sdk_dynamodb::operation::{
+    batch_get_item::{builders::BatchGetItemInputBuilder, BatchGetItemOutput},
+    batch_write_item::{builders::BatchWriteItemInputBuilder, BatchWriteItemOutput},
+    delete_item::{builders::DeleteItemInputBuilder, DeleteItemOutput},
+    get_item::{builders::GetItemInputBuilder, GetItemOutput},
+    put_item::{builders::PutItemInputBuilder, PutItemOutput},
+    query::{builders::QueryInputBuilder, QueryOutput},
+    scan::{builders::ScanInputBuilder, ScanOutput},
+    transact_get_items::{builders::TransactGetItemsInputBuilder, TransactGetItemsOutput},
+    transact_write_items::{builders::TransactWriteItemsInputBuilder, TransactWriteItemsOutput},
+    update_item::{builders::UpdateItemInputBuilder, UpdateItemOutput},
+    update_time_to_live::{builders::UpdateTimeToLiveInputBuilder, UpdateTimeToLiveOutput},
+};
+use std::env;
+
+/// Environment variable for the prefix of [`AffixResolver::from_env`].
+pub const TABLE_NAME_PREFIX_ENV: &str = "DYNAMO_MAPPER_TABLE_PREFIX";
+
+/// Environment variable for the suffix of [`AffixResolver::from_env`].
+pub const TABLE_NAME_SUFFIX_ENV: &str = "DYNAMO_MAPPER_TABLE_SUFFIX";
+
+/// Resolves [`DynamodbTable::TABLE_NAME`](crate::DynamodbTable::TABLE_NAME) into the actual
+/// table name at runtime, so that you can deploy the same code to the tables of each stage
+/// like `dev-orders` and `prod-orders`.
+///
+/// Wrap the backend with [`Resolving`] to resolve the table names of the operations sent to it,
+/// so that each backend can have its own resolver.
+pub trait TableNameResolver: Send + Sync {
+    /// Return the actual table name.
+    fn resolve(&self, table_name: &str) -> String;
+}
+
+impl<F> TableNameResolver for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn resolve(&self, table_name: &str) -> String {
+        self(table_name)
+    }
+}
+
+/// A [`TableNameResolver`] which adds the prefix and the suffix to the table name.
+///
+/// ```
+/// # use dynamo_mapper::{AffixResolver, TableNameResolver};
+/// let resolver = AffixResolver::new().set_prefix("dev-");
+/// assert_eq!(resolver.resolve("orders"), "dev-orders");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AffixResolver {
+    prefix: String,
+    suffix: String,
+}
+
+impl AffixResolver {
+    /// Create a new instance which adds nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new instance from the environment variables [`TABLE_NAME_PREFIX_ENV`] and
+    /// [`TABLE_NAME_SUFFIX_ENV`]. The unset variables are regarded as empty.
+    pub fn from_env() -> Self {
+        Self {
+            prefix: env::var(TABLE_NAME_PREFIX_ENV).unwrap_or_default(),
+            suffix: env::var(TABLE_NAME_SUFFIX_ENV).unwrap_or_default(),
+        }
+    }
+
+    /// Set the prefix.
+    pub fn set_prefix(self, prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Set the suffix.
+    pub fn set_suffix(self, suffix: impl Into<String>) -> Self {
+        Self {
+            suffix: suffix.into(),
+            ..self
+        }
+    }
+}
+
+impl TableNameResolver for AffixResolver {
+    fn resolve(&self, table_name: &str) -> String {
+        format!("{}{table_name}{}", self.prefix, self.suffix)
+    }
+}
+
+/// A [`DynamoBackend`] which resolves the table names of the operations with the
+/// [`TableNameResolver`].
+///
+/// ```no_run
+/// # use dynamo_mapper::{AffixResolver, Resolving};
+/// # async fn run(client: aws_sdk_dynamodb::Client) {
+/// let backend = Resolving::new(client, AffixResolver::from_env());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Resolving<B, R> {
+    inner: B,
+    resolver: R,
+}
+
+impl<B: DynamoBackend, R: TableNameResolver> Resolving<B, R> {
+    /// Wrap the backend with the resolver.
+    pub fn new(inner: B, resolver: R) -> Self {
+        Self { inner, resolver }
+    }
+
+    /// Return the inner backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+macro_rules! impl_resolving_backend {
+    ($($method:ident: $input:ty => $output:ty,)*) => {
+        impl<B: DynamoBackend, R: TableNameResolver> DynamoBackend for Resolving<B, R> {
+            $(
+                async fn $method(&self, input: $input) -> Result<$output, BoxError> {
+                    self.inner.$method(input).await
+                }
+            )*
+
+            fn retry_policy(&self) -> Option<&RetryPolicy> {
+                self.inner.retry_policy()
+            }
+
+            fn table_name_resolver(&self) -> Option<&dyn TableNameResolver> {
+                Some(&self.resolver)
+            }
+
+            fn after_decode(&self, decoded: &Decoded<'_>) {
+                self.inner.after_decode(decoded)
+            }
+        }
+    };
+}
+
+impl_resolving_backend! {
+    get_item: GetItemInputBuilder => GetItemOutput,
+    put_item: PutItemInputBuilder => PutItemOutput,
+    update_item: UpdateItemInputBuilder => UpdateItemOutput,
+    delete_item: DeleteItemInputBuilder => DeleteItemOutput,
+    query: QueryInputBuilder => QueryOutput,
+    scan: ScanInputBuilder => ScanOutput,
+    batch_get_item: BatchGetItemInputBuilder => BatchGetItemOutput,
+    batch_write_item: BatchWriteItemInputBuilder => BatchWriteItemOutput,
+    transact_get_items: TransactGetItemsInputBuilder => TransactGetItemsOutput,
+    transact_write_items: TransactWriteItemsInputBuilder => TransactWriteItemsOutput,
+    update_time_to_live: UpdateTimeToLiveInputBuilder => UpdateTimeToLiveOutput,
+}
+
+/// Resolve the table name with the resolver of the backend.
+pub(crate) fn resolve_table_name<B: DynamoBackend>(
+    backend: &B,
+    table_name: &Option<String>,
+) -> Option<String> {
+    match (backend.table_name_resolver(), table_name) {
+        (Some(resolver), Some(table_name)) => Some(resolver.resolve(table_name)),
+        _ => table_name.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn affix_resolver_adds_prefix_and_suffix() {
+        let resolver = AffixResolver::new();
+        assert_eq!(resolver.resolve("orders"), "orders");
+
+        let resolver = AffixResolver::new().set_prefix("dev-").set_suffix("-v2");
+        assert_eq!(resolver.resolve("orders"), "dev-orders-v2");
+    }
+
+    #[test]
+    fn closure_resolves_table_name() {
+        let resolver = |name: &str| format!("staging-{name}");
+        assert_eq!(resolver.resolve("orders"), "staging-orders");
+    }
+}
@@ -0,0 +1,250 @@
+//! Spans of the operations recorded with the `tracing` feature.
+//!
+//! [`OperationSpan`] also drives the [`OperationMeter`] of the `metrics` feature, so the
+//! operations are instrumented at one place for both features.
+//!
+//! The span name and its fields are documented in the [crate docs](crate#tracing).
+use crate::{Error, Item};
+
+#[cfg(feature = "metrics")]
+use crate::meter::OperationMeter;
+
+use aws_sdk_dynamodb::types::ConsumedCapacity;
+use std::{future::Future, time::Instant};
+
+/// The span of an operation.
+pub(crate) struct OperationSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "metrics")]
+    meter: OperationMeter,
+}
+
+impl OperationSpan {
+    /// Open the span of the operation.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn new(
+        operation: &'static str,
+        table_name: Option<&str>,
+        index_name: Option<&str>,
+    ) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!(
+                "dynamodb",
+                operation,
+                table_name,
+                index_name,
+                key = tracing::field::Empty,
+                page = tracing::field::Empty,
+                consumed_capacity = tracing::field::Empty,
+                item_count = tracing::field::Empty,
+                latency_ms = tracing::field::Empty,
+                error.kind = tracing::field::Empty,
+            ),
+            #[cfg(feature = "metrics")]
+            meter: OperationMeter::new(operation, table_name),
+        }
+    }
+
+    /// Set the entity type of the object to label the metrics.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_mut, unused_variables))]
+    pub(crate) fn entity_type(mut self, entity_type: Option<&str>) -> Self {
+        #[cfg(feature = "metrics")]
+        self.meter.set_entity_type(entity_type);
+        self
+    }
+
+    /// Record the key, or only its attribute names if `redact` is true.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn key(self, key: Option<&Item>, redact: bool) -> Self {
+        #[cfg(feature = "tracing")]
+        if let Some(key) = key {
+            self.span.record("key", format_key(key, redact).as_str());
+        }
+        self
+    }
+
+    /// Record the page number of the request if it is known.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn page(self, page: Option<u32>) -> Self {
+        #[cfg(feature = "tracing")]
+        if let Some(page) = page {
+            self.span.record("page", page);
+        }
+        self
+    }
+
+    /// Record the consumed capacity and the number of the items of the response.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn output(&self, consumed_capacity: Option<&ConsumedCapacity>, item_count: usize) {
+        #[cfg(feature = "tracing")]
+        {
+            if let Some(units) = consumed_capacity.and_then(ConsumedCapacity::capacity_units) {
+                self.span.record("consumed_capacity", units);
+            }
+            self.span.record("item_count", item_count);
+        }
+
+        #[cfg(feature = "metrics")]
+        self.meter.output(consumed_capacity, item_count);
+    }
+
+    /// Run the operation in the span and record its latency and error.
+    pub(crate) async fn instrument<T>(
+        &self,
+        operation: impl Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        let started = Instant::now();
+
+        #[cfg(feature = "tracing")]
+        let result = tracing::Instrument::instrument(operation, self.span.clone()).await;
+        #[cfg(not(feature = "tracing"))]
+        let result = operation.await;
+
+        #[cfg_attr(
+            not(any(feature = "tracing", feature = "metrics")),
+            allow(unused_variables)
+        )]
+        let elapsed = started.elapsed();
+
+        #[cfg(feature = "tracing")]
+        {
+            let latency_ms = elapsed.as_secs_f64() * 1000.0;
+            self.span.record("latency_ms", latency_ms);
+
+            let _enter = self.span.enter();
+            match &result {
+                Ok(_) => tracing::debug!(latency_ms, "operation completed"),
+                Err(err) => {
+                    self.span.record("error.kind", err.kind().as_str());
+                    tracing::warn!(latency_ms, error = %err, "operation failed");
+                }
+            }
+        }
+
+        #[cfg(feature = "metrics")]
+        self.meter.finish(elapsed, &result);
+
+        result
+    }
+}
+
+/// Format the key like `customer=tanaka, id=1` in the order of the attribute names.
+#[cfg(feature = "tracing")]
+fn format_key(key: &Item, redact: bool) -> String {
+    use aws_sdk_dynamodb::types::AttributeValue;
+
+    let mut attributes: Vec<_> = key.iter().collect();
+    attributes.sort_by_key(|(name, _)| *name);
+    attributes
+        .into_iter()
+        .map(|(name, value)| match value {
+            _ if redact => format!("{name}=***"),
+            AttributeValue::S(v) | AttributeValue::N(v) => format!("{name}={v}"),
+            AttributeValue::B(v) => format!("{name}=<{} bytes>", v.as_ref().len()),
+            v => format!("{name}={v:?}"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::*;
+    use crate::{helpers::attribute_value::AttributeMap, BoxError};
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    #[test]
+    fn format_key_in_name_order() {
+        let key = AttributeMap::new()
+            .set_n("id", "1")
+            .set_s("customer", "tanaka")
+            .into_item();
+        assert_eq!(format_key(&key, false), "customer=tanaka, id=1");
+        assert_eq!(format_key(&key, true), "customer=***, id=***");
+    }
+
+    /// A subscriber which collects the fields of the span.
+    #[derive(Clone, Default)]
+    struct Fields(Arc<Mutex<HashMap<String, String>>>);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(field.name().into(), format!("{value:?}"));
+        }
+    }
+
+    impl Subscriber for Fields {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+            span.record(&mut self.clone());
+            span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &span::Id, values: &span::Record<'_>) {
+            values.record(&mut self.clone());
+        }
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, _: &span::Id) {}
+
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    #[tokio::test]
+    async fn record_fields_of_operation() {
+        let fields = Fields::default();
+        let _guard = tracing::subscriber::set_default(fields.clone());
+
+        let key = AttributeMap::new().set_s("id", "secret").into_item();
+        let span = OperationSpan::new("GetItem", Some("Secrets"), None).key(Some(&key), true);
+        let capacity = ConsumedCapacity::builder().capacity_units(0.5).build();
+        let result = span
+            .instrument(async {
+                span.output(Some(&capacity), 1);
+                Err::<(), _>(Error::Sdk(BoxError::from("connection refused")))
+            })
+            .await;
+        assert!(result.is_err());
+
+        let fields = fields.0.lock().unwrap();
+        assert_eq!(fields["operation"], "\"GetItem\"");
+        assert_eq!(fields["table_name"], "\"Secrets\"");
+        assert_eq!(fields["key"], "\"id=***\"");
+        assert_eq!(fields["consumed_capacity"], "0.5");
+        assert_eq!(fields["item_count"], "1");
+        assert_eq!(fields["error.kind"], "\"other\"");
+        assert!(fields.contains_key("latency_ms"));
+        assert!(!fields.contains_key("index_name"));
+        assert!(!fields.contains_key("page"));
+    }
+
+    #[test]
+    fn record_page_of_query() {
+        let fields = Fields::default();
+        let _guard = tracing::subscriber::set_default(fields.clone());
+
+        let _span = OperationSpan::new("Query", Some("Orders"), Some("Status")).page(Some(3));
+
+        let fields = fields.0.lock().unwrap();
+        assert_eq!(fields["index_name"], "\"Status\"");
+        assert_eq!(fields["page"], "3");
+    }
+}
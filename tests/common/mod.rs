@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 use dynamo_mapper::Item;
 
 use aws_config::{retry::RetryConfig, BehaviorVersion, Region, SdkConfig};
@@ -0,0 +1,756 @@
+use dynamo_mapper::{
+    helpers::attribute_value::AttributeMap,
+    interceptor::{Decoded, InterceptedBackend, Interceptor, Operation, Request},
+    memory::MemoryBackend,
+    operations::{
+        counter::Counter, delete_item::DeleteItem, get_item::GetItem, put_item::PutItem,
+        query::Query, update_item::UpdateItem,
+    },
+    retry::{RetryPolicy, Retrying},
+    schema::{IndexSchema, Schema},
+    BoxError, DynamoBackend, DynamodbTable, Error, ErrorKind, Index, Item, Key,
+};
+
+use aws_sdk_dynamodb::{
+    operation::{put_item::PutItemInput, transact_write_items::TransactWriteItemsInput},
+    types::{
+        error::ThrottlingException, AttributeValue, ProjectionType, Put, ReturnValue,
+        ScalarAttributeType, TransactWriteItem, Update,
+    },
+};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering::SeqCst},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+const TABLE_NAME: &str = "Orders";
+const PK: &str = "customer";
+const SK: &str = "id";
+
+#[derive(Debug, Clone, PartialEq)]
+struct Order {
+    customer: String,
+    id: u32,
+    status: String,
+    total: u32,
+}
+
+/// An entity stored in the same table, which is distinguished by the entity type.
+#[derive(Debug, Clone, PartialEq)]
+struct Tally {
+    customer: String,
+    id: u32,
+    count: u32,
+}
+
+fn order(id: u32, status: &str, total: u32) -> Order {
+    Order {
+        customer: "tanaka".into(),
+        id,
+        status: status.into(),
+        total,
+    }
+}
+
+#[tokio::test]
+async fn put_get_and_delete_item() {
+    let backend = setup();
+    let order = order(1, "pending", 100);
+
+    let result = order.clone().put().send(&backend).await;
+    assert!(result.is_ok());
+
+    let result = Order::get_item()
+        .set_key("tanaka".into(), 1)
+        .send(&backend)
+        .await;
+    assert_eq!(result.unwrap(), Some(order.clone()));
+
+    let result = order.delete().send(&backend).await;
+    assert!(result.is_ok());
+
+    let result = Order::get_item()
+        .set_key("tanaka".into(), 1)
+        .send(&backend)
+        .await;
+    assert_eq!(result.unwrap(), None);
+}
+
+#[tokio::test]
+async fn put_item_with_failed_condition() {
+    let backend = setup();
+
+    let result = order(1, "pending", 100).put().send(&backend).await;
+    assert!(result.is_ok());
+
+    let result = order(1, "shipped", 200).put().send(&backend).await;
+    match result {
+        Err(Error::Sdk(err)) => assert!(matches!(
+            err.downcast_ref::<aws_sdk_dynamodb::Error>(),
+            Some(aws_sdk_dynamodb::Error::ConditionalCheckFailedException(_))
+        )),
+        other => unreachable!("unexpected result: {other:?}"),
+    }
+
+    let items = backend.items(TABLE_NAME).unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(Order::try_from(items[0].clone()).unwrap().status, "pending");
+}
+
+#[tokio::test]
+async fn query_in_sort_key_order_with_pagination() {
+    let backend = setup();
+    for id in [10, 2, 9, 1] {
+        order(id, "pending", 100)
+            .put()
+            .send(&backend)
+            .await
+            .unwrap();
+    }
+
+    let output = Order::query()
+        .pk_eq("tanaka".into())
+        .set_limit(3)
+        .send(&backend, None)
+        .await
+        .unwrap();
+    let ids: Vec<u32> = output.items.iter().map(|order| order.id).collect();
+    assert_eq!(ids, vec![1, 2, 9]);
+    assert!(output.last_evaluated_key.is_some());
+
+    let output = Order::query()
+        .pk_eq("tanaka".into())
+        .set_limit(3)
+        .send(&backend, output.last_evaluated_key)
+        .await
+        .unwrap();
+    let ids: Vec<u32> = output.items.iter().map(|order| order.id).collect();
+    assert_eq!(ids, vec![10]);
+    assert!(output.last_evaluated_key.is_none());
+}
+
+#[tokio::test]
+async fn query_with_sort_key_condition_and_filter() {
+    let backend = setup();
+    order(1, "pending", 100).put().send(&backend).await.unwrap();
+    order(2, "shipped", 200).put().send(&backend).await.unwrap();
+    order(3, "pending", 300).put().send(&backend).await.unwrap();
+    order(4, "pending", 400).put().send(&backend).await.unwrap();
+
+    let output = Order::query()
+        .pk_eq("tanaka".into())
+        .sk_between(1, 3)
+        .set_scan_index_forward(false)
+        .set_filter_expression("#status = :status")
+        .set_expression_attribute_names(HashMap::from([("#status".into(), "status".into())]))
+        .set_expression_attribute_values(HashMap::from([(
+            ":status".into(),
+            AttributeValue::S("pending".into()),
+        )]))
+        .send(&backend, None)
+        .await
+        .unwrap();
+    let ids: Vec<u32> = output.items.iter().map(|order| order.id).collect();
+    assert_eq!(ids, vec![3, 1]);
+    assert_eq!(output.count, 2);
+    assert_eq!(output.scanned_count, 3);
+}
+
+#[tokio::test]
+async fn query_output_in_envelope() {
+    let backend = setup();
+    order(1, "pending", 100).put().send(&backend).await.unwrap();
+    order(2, "shipped", 200).put().send(&backend).await.unwrap();
+
+    let output = Order::query()
+        .pk_eq("tanaka".into())
+        .set_filter_expression("#status = :status")
+        .set_expression_attribute_names(HashMap::from([("#status".into(), "status".into())]))
+        .set_expression_attribute_values(HashMap::from([(
+            ":status".into(),
+            AttributeValue::S("shipped".into()),
+        )]))
+        .send_output(&backend, None)
+        .await
+        .unwrap();
+    let ids: Vec<u32> = output.value.items.iter().map(|order| order.id).collect();
+    assert_eq!(ids, vec![2]);
+    assert_eq!(output.value.count, 1);
+    assert_eq!(output.value.scanned_count, 2);
+    assert!(output.value.last_evaluated_key.is_none());
+    assert!(output.consumed_capacity.is_none());
+    assert!(output.item_collection_metrics.is_none());
+}
+
+#[tokio::test]
+async fn query_global_secondary_index() {
+    let backend = setup();
+    order(1, "pending", 300).put().send(&backend).await.unwrap();
+    order(2, "shipped", 200).put().send(&backend).await.unwrap();
+    order(3, "pending", 100).put().send(&backend).await.unwrap();
+
+    let output = Order::query_index::<OrdersByStatus>()
+        .pk_eq("pending".into())
+        .set_limit(1)
+        .send(&backend, None)
+        .await
+        .unwrap();
+    let ids: Vec<u32> = output.items.iter().map(|order| order.id).collect();
+    assert_eq!(ids, vec![3]);
+
+    let key = output.last_evaluated_key.unwrap();
+    assert_eq!(key.len(), 4);
+
+    let output = Order::query_index::<OrdersByStatus>()
+        .pk_eq("pending".into())
+        .send(&backend, Some(key))
+        .await
+        .unwrap();
+    let ids: Vec<u32> = output.items.iter().map(|order| order.id).collect();
+    assert_eq!(ids, vec![1]);
+}
+
+#[tokio::test]
+async fn consistent_read_on_global_secondary_index() {
+    let backend = setup();
+
+    let result = Order::query_index::<OrdersByStatus>()
+        .pk_eq("pending".into())
+        .set_consistent_read(true)
+        .send(&backend, None)
+        .await;
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::Validation);
+}
+
+#[tokio::test]
+async fn query_keys_only_index_of_entity() {
+    let backend = setup();
+    order(1, "pending", 100).put().send(&backend).await.unwrap();
+    let count: u32 = Tally::increment(("tanaka".into(), 2), "count", 3)
+        .send(&backend)
+        .await
+        .unwrap();
+    assert_eq!(count, 3);
+
+    // The index doesn't project the entity type, so the items are not filtered by it.
+    let output = Tally::query_index::<TalliesByCount>()
+        .pk_eq(3)
+        .send(&backend, None)
+        .await
+        .unwrap();
+    let expected = Tally {
+        customer: "tanaka".into(),
+        id: 2,
+        count: 3,
+    };
+    assert_eq!(output.items, vec![expected]);
+}
+
+#[tokio::test]
+async fn get_item_written_before_entity_type() {
+    let backend = setup();
+    let item = AttributeMap::new()
+        .set_s(PK, "tanaka")
+        .set_n(SK, "1")
+        .set_n("count", "3")
+        .into_item();
+    backend
+        .put_item(
+            PutItemInput::builder()
+                .table_name(TABLE_NAME)
+                .set_item(Some(item)),
+        )
+        .await
+        .unwrap();
+
+    let result = Tally::get_item()
+        .set_key("tanaka".into(), 1)
+        .send(&backend)
+        .await;
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::EntityType);
+
+    let result = Tally::get_item()
+        .set_key("tanaka".into(), 1)
+        .set_entity_filter(false)
+        .send(&backend)
+        .await;
+    let expected = Tally {
+        customer: "tanaka".into(),
+        id: 1,
+        count: 3,
+    };
+    assert_eq!(result.unwrap(), Some(expected));
+}
+
+#[tokio::test]
+async fn update_item() {
+    let backend = setup();
+    order(1, "pending", 100).put().send(&backend).await.unwrap();
+
+    let result = Order::update_item()
+        .set_key("tanaka".into(), 1)
+        .set_update_expression("SET #status = :status ADD #total :inc")
+        .set_condition_expression("#status = :pending")
+        .set_expression_attribute_names(HashMap::from([
+            ("#status".into(), "status".into()),
+            ("#total".into(), "total".into()),
+        ]))
+        .set_expression_attribute_values(HashMap::from([
+            (":status".into(), AttributeValue::S("shipped".into())),
+            (":pending".into(), AttributeValue::S("pending".into())),
+            (":inc".into(), AttributeValue::N("50".into())),
+        ]))
+        .send(&backend)
+        .await;
+    assert_eq!(result.unwrap(), Some(order(1, "shipped", 150)));
+}
+
+#[tokio::test]
+async fn save_changes_of_object() {
+    let backend = setup();
+    let original = order(1, "pending", 100);
+    original.clone().put().send(&backend).await.unwrap();
+
+    let mut modified = original.clone();
+    modified.status = "shipped".into();
+    // The names and values of the condition are added to the ones of the changes.
+    let result = modified
+        .save_changes(&original)
+        .unwrap()
+        .set_condition_expression("#old_status = :old_status")
+        .add_expression_attribute_names(HashMap::from([("#old_status".into(), "status".into())]))
+        .add_expression_attribute_values(HashMap::from([(
+            ":old_status".into(),
+            AttributeValue::S("pending".into()),
+        )]))
+        .send(&backend)
+        .await;
+    assert_eq!(result.unwrap(), Some(modified.clone()));
+
+    // No request is sent without changes.
+    assert!(modified.save_changes(&modified).is_none());
+}
+
+#[cfg(feature = "derive")]
+#[tokio::test]
+async fn update_item_with_patch() {
+    use dynamo_mapper::helpers::expression::patch::{Patch, PatchItem};
+
+    #[derive(PatchItem)]
+    struct OrderPatch {
+        status: Option<String>,
+        total: Option<u32>,
+        #[patch(rename = "note")]
+        memo: Patch<String>,
+    }
+
+    let backend = setup();
+    order(1, "pending", 100).put().send(&backend).await.unwrap();
+
+    let patch = OrderPatch {
+        status: Some("shipped".into()),
+        total: None,
+        memo: Patch::Remove,
+    };
+    let result = Order::update_item()
+        .set_key("tanaka".into(), 1)
+        .set_patch(patch)
+        .send(&backend)
+        .await;
+    assert_eq!(result.unwrap(), Some(order(1, "shipped", 100)));
+}
+
+#[tokio::test]
+async fn increment_returns_new_value() {
+    let backend = setup();
+    order(1, "pending", 100).put().send(&backend).await.unwrap();
+
+    let total: u32 = Order::increment(("tanaka".into(), 1), "total", 5)
+        .send(&backend)
+        .await
+        .unwrap();
+    assert_eq!(total, 105);
+
+    // The counter starts from zero on a missing item.
+    let visits: u64 = Order::increment(("tanaka".into(), 2), "visits", 2)
+        .send(&backend)
+        .await
+        .unwrap();
+    assert_eq!(visits, 2);
+}
+
+#[tokio::test]
+async fn increment_writes_entity_type() {
+    let backend = setup();
+
+    // The item doesn't exist, so it is created with the entity type.
+    let count: u32 = Tally::increment(("tanaka".into(), 1), "count", 3)
+        .send(&backend)
+        .await
+        .unwrap();
+    assert_eq!(count, 3);
+
+    let count: u32 = Tally::increment(("tanaka".into(), 1), "count", 5)
+        .send(&backend)
+        .await
+        .unwrap();
+    assert_eq!(count, 8);
+
+    let result = Tally::get_item()
+        .set_key("tanaka".into(), 1)
+        .send(&backend)
+        .await;
+    let expected = Tally {
+        customer: "tanaka".into(),
+        id: 1,
+        count: 8,
+    };
+    assert_eq!(result.unwrap(), Some(expected));
+}
+
+#[tokio::test]
+async fn decrement_stops_at_zero() {
+    let backend = setup();
+    order(1, "pending", 1).put().send(&backend).await.unwrap();
+
+    let total: u32 = Order::decrement_if_positive(("tanaka".into(), 1), "total", 1)
+        .send(&backend)
+        .await
+        .unwrap();
+    assert_eq!(total, 0);
+
+    let err = Order::decrement_if_positive::<u32>(("tanaka".into(), 1), "total", 1)
+        .send(&backend)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ConditionalCheckFailed);
+}
+
+#[tokio::test]
+async fn transaction_is_all_or_nothing() {
+    let backend = setup();
+    order(1, "pending", 100).put().send(&backend).await.unwrap();
+
+    let put = Put::builder()
+        .table_name(TABLE_NAME)
+        .set_item(Some(order(2, "pending", 200).into()))
+        .build()
+        .unwrap();
+    let update = Update::builder()
+        .table_name(TABLE_NAME)
+        .key(PK, AttributeValue::S("tanaka".into()))
+        .key(SK, AttributeValue::N("1".into()))
+        .update_expression("SET #status = :status")
+        .condition_expression("#status = :status")
+        .expression_attribute_names("#status", "status")
+        .expression_attribute_values(":status", AttributeValue::S("shipped".into()))
+        .build()
+        .unwrap();
+    let input = TransactWriteItemsInput::builder()
+        .transact_items(TransactWriteItem::builder().put(put).build())
+        .transact_items(TransactWriteItem::builder().update(update).build());
+
+    let result = backend.transact_write_items(input).await;
+    let err = result.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<aws_sdk_dynamodb::Error>(),
+        Some(aws_sdk_dynamodb::Error::TransactionCanceledException(_))
+    ));
+
+    let items = backend.items(TABLE_NAME).unwrap();
+    assert_eq!(items.len(), 1);
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn blocking_operations() {
+    let backend = setup();
+    order(1, "pending", 100)
+        .put()
+        .send_blocking(&backend)
+        .unwrap();
+    order(2, "shipped", 200)
+        .put()
+        .send_blocking(&backend)
+        .unwrap();
+
+    let result = Order::get_item()
+        .set_key("tanaka".into(), 2)
+        .send_blocking(&backend);
+    assert_eq!(result.unwrap(), Some(order(2, "shipped", 200)));
+
+    let output = Order::query()
+        .pk_eq("tanaka".into())
+        .send_blocking(&backend, None)
+        .unwrap();
+    assert_eq!(output.items.len(), 2);
+}
+
+#[tokio::test]
+async fn retry_throttled_operations() {
+    /// Throttle the given number of the next requests.
+    struct Throttle(AtomicUsize);
+
+    impl Interceptor for Throttle {
+        fn before_request(&self, _: &mut Request<'_>) -> Result<(), BoxError> {
+            match self.0.fetch_update(SeqCst, SeqCst, |n| n.checked_sub(1)) {
+                Ok(_) => Err(Box::new(aws_sdk_dynamodb::Error::ThrottlingException(
+                    ThrottlingException::builder().build(),
+                ))),
+                Err(_) => Ok(()),
+            }
+        }
+    }
+
+    let throttle = Arc::new(Throttle(AtomicUsize::new(2)));
+    let policy = RetryPolicy::new()
+        .with_base_delay(Duration::from_millis(1))
+        .with_max_delay(Duration::from_millis(5));
+    let backend = Retrying::new(
+        InterceptedBackend::new(setup()).with_shared(throttle.clone()),
+        policy,
+    );
+
+    let result = order(1, "pending", 100).put().send(&backend).await;
+    assert!(result.is_ok());
+
+    // The operation opts out of the policy of the backend.
+    throttle.0.store(1, SeqCst);
+    let result = Order::get_item()
+        .set_key("tanaka".into(), 1)
+        .set_retry_policy(RetryPolicy::never())
+        .send(&backend)
+        .await;
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::Throttled);
+}
+
+#[tokio::test]
+async fn interceptor_sees_decoded_objects() {
+    /// Record the ids of the decoded orders.
+    #[derive(Default)]
+    struct Audit(Mutex<Vec<(Operation, u32)>>);
+
+    impl Interceptor for Audit {
+        fn after_decode(&self, decoded: &Decoded<'_>) {
+            let mut ids = self.0.lock().unwrap();
+            ids.extend(
+                decoded
+                    .objects::<Order>()
+                    .map(|order| (decoded.operation(), order.id)),
+            );
+        }
+    }
+
+    let audit = Arc::new(Audit::default());
+    let backend = InterceptedBackend::new(setup()).with_shared(audit.clone());
+    for id in [1, 2] {
+        order(id, "pending", 100)
+            .put()
+            .send(&backend)
+            .await
+            .unwrap();
+    }
+
+    Order::get_item()
+        .set_key("tanaka".into(), 2)
+        .send(&backend)
+        .await
+        .unwrap();
+    Order::query()
+        .pk_eq("tanaka".into())
+        .send(&backend, None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        *audit.0.lock().unwrap(),
+        vec![
+            (Operation::GetItem, 2),
+            (Operation::Query, 1),
+            (Operation::Query, 2),
+        ]
+    );
+}
+
+// -----------------------------------------
+// setup section
+// -----------------------------------------
+impl<'a> DynamodbTable<'a> for Order {
+    const TABLE_NAME: &'a str = TABLE_NAME;
+
+    type Key = OrderKey;
+
+    fn key_inputs(&self) -> (String, u32) {
+        (self.customer.clone(), self.id)
+    }
+}
+
+impl<'a> GetItem<'a> for Order {}
+impl<'a> DeleteItem<'a> for Order {}
+impl<'a> Counter<'a> for Order {}
+impl<'a> Query<'a> for Order {
+    fn consistent_read() -> Option<bool> {
+        Some(true)
+    }
+}
+
+impl<'a> PutItem<'a> for Order {
+    fn condition_expression() -> Option<String> {
+        Some(format!("attribute_not_exists({PK})"))
+    }
+}
+
+impl<'a> UpdateItem<'a> for Order {
+    fn return_values() -> Option<ReturnValue> {
+        Some(ReturnValue::AllNew)
+    }
+}
+
+impl<'a> Schema<'a> for Order {
+    fn global_secondary_indexes() -> Vec<IndexSchema> {
+        vec![
+            IndexSchema::from_index::<OrdersByStatus>(),
+            IndexSchema::from_index::<TalliesByCount>(),
+        ]
+    }
+}
+
+struct OrderKey;
+
+impl<'a> Key<'a> for OrderKey {
+    const PARTITION_KEY: &'a str = PK;
+    const SORT_KEY: Option<&'a str> = Some(SK);
+    const SORT_KEY_TYPE: ScalarAttributeType = ScalarAttributeType::N;
+
+    type PartitionInput = String;
+    type SortInput = u32;
+
+    fn partition_key(input: String) -> AttributeValue {
+        AttributeValue::S(input)
+    }
+
+    fn sort_key(input: u32) -> Option<AttributeValue> {
+        Some(AttributeValue::N(input.to_string()))
+    }
+}
+
+struct OrdersByStatus;
+
+impl<'a> Index<'a> for OrdersByStatus {
+    const INDEX_NAME: &'a str = "OrdersByStatus";
+
+    type Key = OrdersByStatusKey;
+}
+
+struct OrdersByStatusKey;
+
+impl<'a> Key<'a> for OrdersByStatusKey {
+    const PARTITION_KEY: &'a str = "status";
+    const SORT_KEY: Option<&'a str> = Some("total");
+    const SORT_KEY_TYPE: ScalarAttributeType = ScalarAttributeType::N;
+
+    type PartitionInput = String;
+    type SortInput = u32;
+
+    fn partition_key(input: String) -> AttributeValue {
+        AttributeValue::S(input)
+    }
+
+    fn sort_key(input: u32) -> Option<AttributeValue> {
+        Some(AttributeValue::N(input.to_string()))
+    }
+}
+
+impl<'a> DynamodbTable<'a> for Tally {
+    const TABLE_NAME: &'a str = TABLE_NAME;
+    const ENTITY_TYPE: Option<&'a str> = Some("Tally");
+
+    type Key = OrderKey;
+
+    fn key_inputs(&self) -> (String, u32) {
+        (self.customer.clone(), self.id)
+    }
+}
+
+impl<'a> GetItem<'a> for Tally {}
+impl<'a> Counter<'a> for Tally {}
+impl<'a> Query<'a> for Tally {}
+
+struct TalliesByCount;
+
+impl<'a> Index<'a> for TalliesByCount {
+    const INDEX_NAME: &'a str = "TalliesByCount";
+    const PROJECTION_TYPE: ProjectionType = ProjectionType::KeysOnly;
+
+    type Key = TalliesByCountKey;
+}
+
+struct TalliesByCountKey;
+
+impl<'a> Key<'a> for TalliesByCountKey {
+    const PARTITION_KEY: &'a str = "count";
+    const SORT_KEY: Option<&'a str> = None;
+    const PARTITION_KEY_TYPE: ScalarAttributeType = ScalarAttributeType::N;
+
+    type PartitionInput = u32;
+    type SortInput = ();
+
+    fn partition_key(input: u32) -> AttributeValue {
+        AttributeValue::N(input.to_string())
+    }
+
+    fn sort_key(_: ()) -> Option<AttributeValue> {
+        None
+    }
+}
+
+impl TryFrom<Item> for Tally {
+    type Error = BoxError;
+
+    fn try_from(item: Item) -> Result<Self, Self::Error> {
+        let map = AttributeMap::from(item);
+        Ok(Tally {
+            customer: map.s(PK).ok_or("no customer")?.into(),
+            id: map.n(SK).ok_or("no id")?.parse()?,
+            count: map.n("count").ok_or("no count")?.parse()?,
+        })
+    }
+}
+
+impl TryFrom<Item> for Order {
+    type Error = BoxError;
+
+    fn try_from(item: Item) -> Result<Self, Self::Error> {
+        let map = AttributeMap::from(item);
+        Ok(Order {
+            customer: map.s(PK).ok_or("no customer")?.into(),
+            id: map.n(SK).ok_or("no id")?.parse()?,
+            status: map.s("status").ok_or("no status")?.into(),
+            total: map.n("total").ok_or("no total")?.parse()?,
+        })
+    }
+}
+
+impl From<Order> for Item {
+    fn from(order: Order) -> Item {
+        AttributeMap::new()
+            .set_s(PK, order.customer)
+            .set_n(SK, order.id.to_string())
+            .set_s("status", order.status)
+            .set_n("total", order.total.to_string())
+            .into_item()
+    }
+}
+
+// -----------------------------------------
+// utility section
+// -----------------------------------------
+fn setup() -> MemoryBackend {
+    let backend = MemoryBackend::new();
+    backend.create_table(Order::schema()).unwrap();
+    backend
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::get_first)]
+
 mod common;
 
 use dynamo_mapper::{
@@ -8,7 +10,7 @@ use dynamo_mapper::{
     op,
     operations::{
         delete_item::DeleteItem, get_item::GetItem, put_item::PutItem, query::Query,
-        update_item::UpdateItem,
+        update_item::UpdateItem, update_time_to_live::UpdateTimeToLive,
     },
     BoxError, DynamodbTable, Item, Key,
 };
@@ -21,9 +23,11 @@ use aws_sdk_dynamodb::{
     Client,
 };
 use common::{assert_str, assert_u8, get_client, tear_down};
+use std::time::{Duration, SystemTime};
 
 const TABLE_NAME: &str = "People";
 const PK: &str = "pk";
+const TTL: &str = "expiresAt";
 
 #[derive(Debug, Clone, PartialEq)]
 struct Person {
@@ -74,6 +78,29 @@ async fn get_item_but_not_found() {
     tear_down(&client, TABLE_NAME).await;
 }
 
+#[tokio::test]
+async fn get_item_but_expired() {
+    let client = setup().await;
+
+    let person = Person {
+        id: "12345".into(),
+        name: "Tanaka".into(),
+        age: 20,
+    };
+    sdk_put_expired_item(&client, &person).await;
+
+    let result = Person::get_item()
+        .set_key(person.id.clone(), ())
+        .send(&client)
+        .await;
+    assert!(result.is_ok());
+
+    let opt = result.unwrap();
+    assert!(opt.is_none());
+
+    tear_down(&client, TABLE_NAME).await;
+}
+
 #[tokio::test]
 async fn put_item() {
     let client = setup().await;
@@ -130,6 +157,26 @@ async fn query() {
     tear_down(&client, TABLE_NAME).await;
 }
 
+#[tokio::test]
+async fn query_without_expired_items() {
+    let client = setup().await;
+
+    let person = Person {
+        id: "0".into(),
+        name: "Tanaka".into(),
+        age: 10,
+    };
+    sdk_put_expired_item(&client, &person).await;
+
+    let result = Person::query().pk_eq("0".into()).send(&client, None).await;
+    assert!(result.is_ok());
+
+    let output = result.unwrap();
+    assert!(output.items.is_empty());
+
+    tear_down(&client, TABLE_NAME).await;
+}
+
 #[tokio::test]
 async fn update_item() {
     let client = setup().await;
@@ -186,6 +233,20 @@ async fn delete_item() {
     tear_down(&client, TABLE_NAME).await;
 }
 
+#[tokio::test]
+async fn update_time_to_live() {
+    let client = setup().await;
+
+    let result = Person::update_time_to_live().send(&client).await;
+    assert!(result.is_ok());
+
+    let spec = result.unwrap().time_to_live_specification.unwrap();
+    assert_eq!(spec.attribute_name, TTL);
+    assert!(spec.enabled);
+
+    tear_down(&client, TABLE_NAME).await;
+}
+
 // -----------------------------------------
 // setup section
 // -----------------------------------------
@@ -194,6 +255,8 @@ impl<'a> DynamodbTable<'a> for Person {
 
     type Key = PersonKey;
 
+    const TTL_ATTRIBUTE: Option<&'a str> = Some(TTL);
+
     fn key_inputs(&self) -> (String, ()) {
         (self.id.to_string(), ())
     }
@@ -208,6 +271,7 @@ impl<'a> UpdateItem<'a> for Person {
     }
 }
 impl<'a> DeleteItem<'a> for Person {}
+impl<'a> UpdateTimeToLive<'a> for Person {}
 
 struct PersonKey;
 
@@ -302,6 +366,21 @@ async fn sdk_put_item(client: &Client, person: &Person) {
         .unwrap();
 }
 
+async fn sdk_put_expired_item(client: &Client, person: &Person) {
+    let item = AttributeMap::from(Item::from(person.clone()))
+        .set(PK, pk(&person.id))
+        .set_expires_at(TTL, SystemTime::now() - Duration::from_secs(60))
+        .into_item();
+
+    client
+        .put_item()
+        .table_name(TABLE_NAME)
+        .set_item(Some(item))
+        .send()
+        .await
+        .unwrap();
+}
+
 async fn sdk_get_item(client: &Client, pk: &str) -> Option<Item> {
     client
         .get_item()
@@ -1,23 +1,25 @@
+#![allow(clippy::get_first)]
+
 mod common;
 
 use dynamo_mapper::{
     helpers::{
         attribute_value::AttributeMap,
         expression::update::{self, Update},
+        key_template::KeyTemplate,
     },
     op,
     operations::{
         delete_item::DeleteItem, get_item::GetItem, put_item::PutItem, query::Query,
         update_item::UpdateItem,
     },
-    BoxError, DynamodbTable, Item, Key,
+    schema::{verify_schema, IndexSchema, Schema},
+    AffixResolver, BoxError, CollectionEnum, Discriminator, DynamodbTable, EntityEnum, Index, Item,
+    ItemCollection, Key, Member, ParseKey, SortKeyPrefix, Variant,
 };
 
 use aws_sdk_dynamodb::{
-    types::{
-        AttributeDefinition, AttributeValue, BillingMode, KeySchemaElement, KeyType, ReturnValue,
-        ScalarAttributeType,
-    },
+    types::{AttributeValue, ReturnValue, ScalarAttributeType},
     Client,
 };
 use common::{assert_str, assert_u8, get_client, tear_down};
@@ -25,6 +27,9 @@ use common::{assert_str, assert_u8, get_client, tear_down};
 const TABLE_NAME: &str = "E-Commerse";
 const PK: &str = "pk";
 const SK: &str = "sk";
+const ET: &str = "_et";
+const SHOP_KEY: KeyTemplate = KeyTemplate::new("SHOP#{id}");
+const STAFF_KEY: KeyTemplate = KeyTemplate::new("STAFF#{id}");
 
 #[derive(Debug, Clone, PartialEq)]
 struct Shop {
@@ -113,6 +118,7 @@ async fn put_item() {
     assert_str(&item, SK, "SHOP#1");
     assert_str(&item, "id", "1");
     assert_str(&item, "name", "ShoesShop");
+    assert_str(&item, ET, "Shop");
 
     let opt = sdk_get_item(&client, "SHOP#1", "STAFF#100").await;
     assert!(opt.is_some());
@@ -124,6 +130,7 @@ async fn put_item() {
     assert_str(&item, "shopId", "1");
     assert_str(&item, "name", "Tanaka");
     assert_u8(&item, "age", 20);
+    assert_str(&item, ET, "Staff");
 
     tear_down(&client, TABLE_NAME).await;
 }
@@ -132,6 +139,10 @@ async fn put_item() {
 async fn query() {
     let client = setup().await;
 
+    let shop = Shop {
+        id: "1".into(),
+        name: "ShoesShop".into(),
+    };
     let staff_1 = Staff {
         id: "100".into(),
         shop_id: "1".into(),
@@ -144,6 +155,7 @@ async fn query() {
         name: "Suzuki".into(),
         age: 23,
     };
+    sdk_put_shop(&client, &shop).await;
     sdk_put_staff(&client, &staff_1).await;
     sdk_put_staff(&client, &staff_2).await;
 
@@ -160,6 +172,201 @@ async fn query() {
     let staff = output.items.get(1).unwrap().clone();
     assert_eq!(staff, staff_2);
 
+    let result = Staff::query()
+        .pk_eq("1".into())
+        .sk_gt("000".into())
+        .set_limit(1)
+        .send(&client, None)
+        .await;
+    assert!(result.is_ok());
+
+    let output = result.unwrap();
+    assert_eq!(output.items, vec![staff_1.clone()]);
+
+    let key = StaffKey::parse_key(&output.last_evaluated_key.unwrap());
+    assert_eq!(key.unwrap(), ("1".to_string(), "100".to_string()));
+
+    let result = Staff::query()
+        .pk_eq("1".into())
+        .sk_begins_with(AllStaff)
+        .send(&client, None)
+        .await;
+    assert!(result.is_ok());
+
+    let output = result.unwrap();
+    assert_eq!(output.items, vec![staff_1, staff_2]);
+
+    tear_down(&client, TABLE_NAME).await;
+}
+
+#[cfg(feature = "cursor")]
+#[tokio::test]
+async fn query_with_cursor() {
+    use dynamo_mapper::cursor::CursorCodec;
+
+    let client = setup().await;
+
+    let staff_1 = Staff {
+        id: "100".into(),
+        shop_id: "1".into(),
+        name: "Tanaka".into(),
+        age: 20,
+    };
+    let staff_2 = Staff {
+        id: "200".into(),
+        shop_id: "1".into(),
+        name: "Suzuki".into(),
+        age: 23,
+    };
+    sdk_put_staff(&client, &staff_1).await;
+    sdk_put_staff(&client, &staff_2).await;
+
+    let codec = CursorCodec::new()
+        .set_signing_key("signing secret")
+        .set_encryption_key([1; 32]);
+
+    let result = Staff::query()
+        .pk_eq("1".into())
+        .set_limit(1)
+        .send_with_cursor(&client, &codec, None)
+        .await;
+    assert!(result.is_ok());
+
+    let output = result.unwrap();
+    assert_eq!(output.items, vec![staff_1]);
+
+    let cursor = output.next_cursor(&codec).unwrap();
+    assert!(cursor.is_some());
+
+    let result = Staff::query()
+        .pk_eq("1".into())
+        .set_limit(1)
+        .send_with_cursor(&client, &codec, cursor.as_deref())
+        .await;
+    assert!(result.is_ok());
+
+    let output = result.unwrap();
+    assert_eq!(output.items, vec![staff_2]);
+
+    let result = Staff::query()
+        .pk_eq("1".into())
+        .send_with_cursor(&client, &CursorCodec::new(), cursor.as_deref())
+        .await;
+    assert!(matches!(result, Err(dynamo_mapper::Error::Cursor(_))));
+
+    tear_down(&client, TABLE_NAME).await;
+}
+
+#[tokio::test]
+async fn query_index() {
+    let client = setup().await;
+
+    let shop = Shop {
+        id: "1".into(),
+        name: "Tanaka".into(),
+    };
+    let staff_1 = Staff {
+        id: "100".into(),
+        shop_id: "1".into(),
+        name: "Tanaka".into(),
+        age: 20,
+    };
+    let staff_2 = Staff {
+        id: "200".into(),
+        shop_id: "2".into(),
+        name: "Tanaka".into(),
+        age: 23,
+    };
+    sdk_put_shop(&client, &shop).await;
+    sdk_put_staff(&client, &staff_1).await;
+    sdk_put_staff(&client, &staff_2).await;
+
+    let result = Staff::query_index::<StaffByName>()
+        .pk_eq("Tanaka".into())
+        .sk_gt(21)
+        .send(&client, None)
+        .await;
+    assert!(result.is_ok());
+
+    let output = result.unwrap();
+    assert_eq!(output.items, vec![staff_2]);
+
+    let result = Staff::query_index::<StaffByName>()
+        .pk_eq("Tanaka".into())
+        .project::<StaffName>()
+        .send(&client, None)
+        .await;
+    assert!(result.is_ok());
+
+    let output = result.unwrap();
+    assert_eq!(
+        output.items,
+        vec![
+            StaffName {
+                name: "Tanaka".into(),
+                age: 20,
+            },
+            StaffName {
+                name: "Tanaka".into(),
+                age: 23,
+            },
+        ]
+    );
+
+    tear_down(&client, TABLE_NAME).await;
+}
+
+#[tokio::test]
+async fn query_collection() {
+    let client = setup().await;
+
+    let shop = Shop {
+        id: "1".into(),
+        name: "ShoesShop".into(),
+    };
+    let staff_1 = Staff {
+        id: "100".into(),
+        shop_id: "1".into(),
+        name: "Tanaka".into(),
+        age: 20,
+    };
+    let staff_2 = Staff {
+        id: "200".into(),
+        shop_id: "1".into(),
+        name: "Suzuki".into(),
+        age: 23,
+    };
+    sdk_put_shop(&client, &shop).await;
+    sdk_put_staff(&client, &staff_1).await;
+    sdk_put_staff(&client, &staff_2).await;
+
+    let result = Shop::query()
+        .pk_eq("1".into())
+        .set_entity_filter(false)
+        .project::<ShopCollection>()
+        .send(&client, None)
+        .await;
+    assert!(result.is_ok());
+
+    let output = result.unwrap();
+    assert_eq!(
+        output.items,
+        vec![
+            ShopCollection::Shop(shop.clone()),
+            ShopCollection::Staff(staff_1.clone()),
+            ShopCollection::Staff(staff_2.clone()),
+        ]
+    );
+
+    let collection = output.into_collection();
+    assert_eq!(
+        collection.unwrap(),
+        ItemCollection {
+            parent: Some(shop),
+            children: vec![staff_1, staff_2],
+        }
+    );
+
     tear_down(&client, TABLE_NAME).await;
 }
 
@@ -224,11 +431,26 @@ async fn delete_item() {
 
     tear_down(&client, TABLE_NAME).await;
 }
+
+#[tokio::test]
+async fn verify_table_schema() {
+    let client = setup().await;
+
+    let result = verify_schema::<Shop>(&client, &AffixResolver::new()).await;
+    assert!(result.is_ok());
+
+    let diff = result.unwrap();
+    assert!(diff.is_empty(), "{diff}");
+
+    tear_down(&client, TABLE_NAME).await;
+}
+
 // -----------------------------------------
 // setup section
 // -----------------------------------------
 impl<'a> DynamodbTable<'a> for Shop {
     const TABLE_NAME: &'a str = TABLE_NAME;
+    const ENTITY_TYPE: Option<&'a str> = Some("Shop");
 
     type Key = ShopKey;
 
@@ -246,6 +468,11 @@ impl<'a> UpdateItem<'a> for Shop {
     }
 }
 impl<'a> DeleteItem<'a> for Shop {}
+impl<'a> Schema<'a> for Shop {
+    fn global_secondary_indexes() -> Vec<IndexSchema> {
+        vec![IndexSchema::from_index::<StaffByName>()]
+    }
+}
 
 struct ShopKey;
 
@@ -289,6 +516,7 @@ impl From<Shop> for Item {
 
 impl<'a> DynamodbTable<'a> for Staff {
     const TABLE_NAME: &'a str = TABLE_NAME;
+    const ENTITY_TYPE: Option<&'a str> = Some("Staff");
 
     type Key = StaffKey;
 
@@ -317,11 +545,29 @@ impl<'a> Key<'a> for StaffKey {
     type SortInput = String;
 
     fn partition_key(input: Self::PartitionInput) -> AttributeValue {
-        AttributeValue::S(format!("SHOP#{input}"))
+        SHOP_KEY.render(&input)
     }
 
     fn sort_key(input: Self::SortInput) -> Option<AttributeValue> {
-        Some(AttributeValue::S(format!("STAFF#{input}")))
+        Some(STAFF_KEY.render(&input))
+    }
+}
+
+impl<'a> ParseKey<'a> for StaffKey {
+    fn parse_partition_key(value: &AttributeValue) -> Result<Self::PartitionInput, BoxError> {
+        SHOP_KEY.parse(value)
+    }
+
+    fn parse_sort_key(value: Option<&AttributeValue>) -> Result<Self::SortInput, BoxError> {
+        STAFF_KEY.parse(value.ok_or("no sort key")?)
+    }
+}
+
+struct AllStaff;
+
+impl<'a> SortKeyPrefix<'a, StaffKey> for AllStaff {
+    fn sort_key_prefix(self) -> AttributeValue {
+        STAFF_KEY.render_prefix(&())
     }
 }
 
@@ -356,50 +602,104 @@ impl From<Staff> for Item {
     }
 }
 
+struct StaffByName;
+
+impl<'a> Index<'a> for StaffByName {
+    const INDEX_NAME: &'a str = "StaffByName";
+
+    type Key = StaffByNameKey;
+}
+
+struct StaffByNameKey;
+
+impl<'a> Key<'a> for StaffByNameKey {
+    const PARTITION_KEY: &'a str = "name";
+    const SORT_KEY: Option<&'a str> = Some("age");
+    const SORT_KEY_TYPE: ScalarAttributeType = ScalarAttributeType::N;
+
+    type PartitionInput = String;
+    type SortInput = u8;
+
+    fn partition_key(input: Self::PartitionInput) -> AttributeValue {
+        AttributeValue::S(input)
+    }
+
+    fn sort_key(input: Self::SortInput) -> Option<AttributeValue> {
+        Some(AttributeValue::N(input.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct StaffName {
+    name: String,
+    age: u8,
+}
+
+impl TryFrom<Item> for StaffName {
+    type Error = BoxError;
+
+    fn try_from(item: Item) -> Result<Self, Self::Error> {
+        let map = AttributeMap::from(item);
+        Ok(StaffName {
+            name: map.s("name").unwrap().into(),
+            age: map.n("age").unwrap().parse().unwrap(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ShopCollection {
+    Shop(Shop),
+    Staff(Staff),
+}
+
+impl EntityEnum for ShopCollection {
+    fn variants() -> Vec<Variant<Self>> {
+        vec![
+            Variant::new(
+                Discriminator::KeyPrefix {
+                    name: SK,
+                    prefix: "SHOP#",
+                },
+                |item| Shop::try_from(item).map(Self::Shop),
+            ),
+            Variant::new(
+                Discriminator::KeyPrefix {
+                    name: SK,
+                    prefix: "STAFF#",
+                },
+                |item| Staff::try_from(item).map(Self::Staff),
+            ),
+        ]
+    }
+}
+
+impl CollectionEnum for ShopCollection {
+    type Parent = Shop;
+    type Child = Staff;
+
+    fn into_member(self) -> Member<Shop, Staff> {
+        match self {
+            Self::Shop(shop) => Member::Parent(shop),
+            Self::Staff(staff) => Member::Child(staff),
+        }
+    }
+}
+
+impl TryFrom<Item> for ShopCollection {
+    type Error = BoxError;
+
+    fn try_from(item: Item) -> Result<Self, Self::Error> {
+        Self::decode(item)
+    }
+}
+
 // -----------------------------------------
 // utility section
 // -----------------------------------------
-async fn create_table(client: &Client) {
-    client
-        .create_table()
-        .attribute_definitions(
-            AttributeDefinition::builder()
-                .attribute_name(PK)
-                .attribute_type(ScalarAttributeType::S)
-                .build()
-                .unwrap(),
-        )
-        .attribute_definitions(
-            AttributeDefinition::builder()
-                .attribute_name(SK)
-                .attribute_type(ScalarAttributeType::S)
-                .build()
-                .unwrap(),
-        )
-        .table_name(TABLE_NAME)
-        .key_schema(
-            KeySchemaElement::builder()
-                .attribute_name(PK)
-                .key_type(KeyType::Hash)
-                .build()
-                .unwrap(),
-        )
-        .key_schema(
-            KeySchemaElement::builder()
-                .attribute_name(SK)
-                .key_type(KeyType::Range)
-                .build()
-                .unwrap(),
-        )
-        .billing_mode(BillingMode::PayPerRequest)
-        .send()
-        .await
-        .unwrap();
-}
-
 async fn setup() -> Client {
     let client = get_client();
-    create_table(&client).await;
+    Shop::schema().create_table(&client).await.unwrap();
     client
 }
 
@@ -407,6 +707,7 @@ async fn sdk_put_shop(client: &Client, shop: &Shop) {
     let mut item: Item = shop.clone().into();
     item.insert(PK.into(), pk(&shop.id));
     item.insert(SK.into(), sk_shop(&shop.id));
+    item.insert(ET.into(), AttributeValue::S("Shop".into()));
 
     client
         .put_item()
@@ -421,6 +722,7 @@ async fn sdk_put_staff(client: &Client, staff: &Staff) {
     let mut item: Item = staff.clone().into();
     item.insert(PK.into(), pk(&staff.shop_id));
     item.insert(SK.into(), sk_staff(&staff.id));
+    item.insert(ET.into(), AttributeValue::S("Staff".into()));
 
     client
         .put_item()
@@ -0,0 +1,143 @@
+use dynamo_mapper::{
+    helpers::attribute_value::AttributeMap,
+    memory::MemoryBackend,
+    operations::{get_item::GetItem, put_item::PutItem},
+    schema::Schema,
+    AffixResolver, BoxError, DynamoBackend, DynamodbTable, Item, Key, Resolving,
+};
+
+use aws_sdk_dynamodb::{operation::get_item::GetItemInput, types::AttributeValue};
+
+const TABLE_NAME: &str = "Settings";
+const PK: &str = "pk";
+
+#[derive(Debug, Clone, PartialEq)]
+struct Setting {
+    name: String,
+    value: String,
+}
+
+#[tokio::test]
+async fn resolve_table_name() {
+    let backend = setup("dev-");
+
+    let setting = Setting {
+        name: "theme".into(),
+        value: "dark".into(),
+    };
+
+    let result = setting.clone().put().send(&backend).await;
+    assert!(result.is_ok());
+
+    let opt = raw_get_item(&backend, "dev-Settings", "theme").await;
+    assert!(opt.is_some());
+
+    let item = AttributeMap::from(opt.unwrap());
+    assert_eq!(item.s("value").map(String::as_str), Some("dark"));
+
+    let result = Setting::get_item()
+        .set_key("theme".into(), ())
+        .send(&backend)
+        .await;
+    assert!(result.is_ok());
+    assert_eq!(result.unwrap(), Some(setting));
+}
+
+#[tokio::test]
+async fn resolve_table_name_for_each_backend() {
+    let dev = setup("dev-");
+    let prod = setup("prod-");
+
+    let setting = Setting {
+        name: "theme".into(),
+        value: "dark".into(),
+    };
+    setting.clone().put().send(&dev).await.unwrap();
+
+    let result = Setting::get_item()
+        .set_key("theme".into(), ())
+        .send(&prod)
+        .await;
+    assert_eq!(result.unwrap(), None);
+}
+
+// -----------------------------------------
+// setup section
+// -----------------------------------------
+impl<'a> DynamodbTable<'a> for Setting {
+    const TABLE_NAME: &'a str = TABLE_NAME;
+
+    type Key = SettingKey;
+
+    fn key_inputs(&self) -> (String, ()) {
+        (self.name.to_string(), ())
+    }
+}
+
+impl<'a> GetItem<'a> for Setting {}
+impl<'a> PutItem<'a> for Setting {}
+impl<'a> Schema<'a> for Setting {}
+
+struct SettingKey;
+
+impl<'a> Key<'a> for SettingKey {
+    const PARTITION_KEY: &'a str = PK;
+    const SORT_KEY: Option<&'a str> = None;
+
+    type PartitionInput = String;
+    type SortInput = ();
+
+    fn partition_key(input: Self::PartitionInput) -> AttributeValue {
+        AttributeValue::S(input)
+    }
+
+    fn sort_key(_: Self::SortInput) -> Option<AttributeValue> {
+        None
+    }
+}
+
+impl TryFrom<Item> for Setting {
+    type Error = BoxError;
+
+    fn try_from(item: Item) -> Result<Self, Self::Error> {
+        let map = AttributeMap::from(item);
+        Ok(Setting {
+            name: map.s("name").unwrap().into(),
+            value: map.s("value").unwrap().into(),
+        })
+    }
+}
+
+impl From<Setting> for Item {
+    fn from(setting: Setting) -> Item {
+        let Setting { name, value } = setting;
+        AttributeMap::new()
+            .set_s("name", name)
+            .set_s("value", value)
+            .into_item()
+    }
+}
+
+// -----------------------------------------
+// utility section
+// -----------------------------------------
+fn setup(prefix: &str) -> Resolving<MemoryBackend, AffixResolver> {
+    let resolver = AffixResolver::new().set_prefix(prefix);
+    let backend = MemoryBackend::new();
+    let schema = Setting::schema().resolve_table_name(&resolver);
+    assert_eq!(schema.table_name, format!("{prefix}{TABLE_NAME}"));
+    backend.create_table(schema).unwrap();
+    Resolving::new(backend, resolver)
+}
+
+async fn raw_get_item<B: DynamoBackend>(backend: &B, table_name: &str, name: &str) -> Option<Item> {
+    backend
+        .get_item(
+            GetItemInput::builder()
+                .table_name(table_name)
+                .key(PK, AttributeValue::S(name.into())),
+        )
+        .await
+        .unwrap()
+        .item
+}